@@ -0,0 +1,4 @@
+use criterion::{criterion_group, criterion_main};
+
+criterion_group!(benches, slint_layer_shell::bench::render_scenes);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+/* C interface to the slint-layer-shell platform.
+ *
+ * The functions must be exported from the library that contains Slint, see the
+ * documentation of the `ffi` module of the crate. Call `slint_layer_shell_init` before
+ * creating any component, configure the windows to create, then run the event loop:
+ *
+ *     slint_layer_shell_init(NULL);
+ *     slint_layer_shell_set_anchor(SLINT_LAYER_SHELL_ANCHOR_TOP | SLINT_LAYER_SHELL_ANCHOR_LEFT
+ *                                  | SLINT_LAYER_SHELL_ANCHOR_RIGHT);
+ *     slint_layer_shell_set_auto_exclusive_zone(true);
+ *     // create and show components
+ *     slint_layer_shell_run();
+ */
+
+#pragma once
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SLINT_LAYER_SHELL_LAYER_BACKGROUND 0
+#define SLINT_LAYER_SHELL_LAYER_BOTTOM 1
+#define SLINT_LAYER_SHELL_LAYER_TOP 2
+#define SLINT_LAYER_SHELL_LAYER_OVERLAY 3
+
+#define SLINT_LAYER_SHELL_ANCHOR_TOP 1
+#define SLINT_LAYER_SHELL_ANCHOR_BOTTOM 2
+#define SLINT_LAYER_SHELL_ANCHOR_LEFT 4
+#define SLINT_LAYER_SHELL_ANCHOR_RIGHT 8
+
+#define SLINT_LAYER_SHELL_KEYBOARD_NONE 0
+#define SLINT_LAYER_SHELL_KEYBOARD_EXCLUSIVE 1
+#define SLINT_LAYER_SHELL_KEYBOARD_ON_DEMAND 2
+
+typedef struct SlintLayerShellConfig
+{
+    uint32_t layer;
+    uint32_t anchor;
+    /* Requested size in logical pixels, 0 to use the preferred size of the window. */
+    uint32_t width;
+    uint32_t height;
+    int32_t margin_top;
+    int32_t margin_right;
+    int32_t margin_bottom;
+    int32_t margin_left;
+    int32_t exclusive_zone;
+    bool auto_exclusive_zone;
+    uint32_t keyboard_interactivity;
+    /* NUL-terminated namespace, or NULL for the default one. */
+    const char *namespace_;
+} SlintLayerShellConfig;
+
+/* Connects to `display`, or to WAYLAND_DISPLAY if NULL, and installs the platform. */
+bool slint_layer_shell_init(const char *display);
+
+/* Replaces the layer configuration of new windows, NULL makes them regular toplevels.
+ *
+ * This and the setters below return false without changing anything if the platform is not
+ * installed or is dispatching events, e.g. when called from a Slint callback. */
+bool slint_layer_shell_set_default_layer_config(const SlintLayerShellConfig *config);
+
+/* Change one property of the layer configuration of new windows. */
+bool slint_layer_shell_set_layer(uint32_t layer);
+bool slint_layer_shell_set_anchor(uint32_t anchor);
+bool slint_layer_shell_set_size(uint32_t width, uint32_t height);
+bool slint_layer_shell_set_margins(int32_t top, int32_t right, int32_t bottom, int32_t left);
+bool slint_layer_shell_set_exclusive_zone(int32_t exclusive_zone);
+bool slint_layer_shell_set_auto_exclusive_zone(bool enabled);
+bool slint_layer_shell_set_keyboard_interactivity(uint32_t keyboard_interactivity);
+bool slint_layer_shell_set_namespace(const char *namespace_);
+
+/* Runs the event loop until `slint_layer_shell_quit` is called. */
+bool slint_layer_shell_run(void);
+void slint_layer_shell_quit(int32_t code);
+int32_t slint_layer_shell_exit_code(void);
+
+#ifdef __cplusplus
+}
+#endif
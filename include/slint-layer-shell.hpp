@@ -0,0 +1,99 @@
+// C++ interface to the slint-layer-shell platform, for Slint's C++ API.
+//
+// The platform must be linked into the same library as Slint's C++ runtime, see the
+// documentation of the `ffi` module of the crate. Install it before creating components:
+//
+//     slint_layer_shell::install();
+//     slint_layer_shell::LayerConfig bar;
+//     bar.anchor = slint_layer_shell::anchor::top | slint_layer_shell::anchor::left
+//             | slint_layer_shell::anchor::right;
+//     bar.auto_exclusive_zone = true;
+//     slint_layer_shell::set_default_layer_config(bar);
+//     auto ui = Bar::create();
+//     ui->run();
+
+#pragma once
+
+#include "slint-layer-shell.h"
+
+#include <cstdint>
+#include <optional>
+#include <string>
+
+namespace slint_layer_shell {
+
+enum class Layer : uint32_t { Background = 0, Bottom = 1, Top = 2, Overlay = 3 };
+
+namespace anchor {
+constexpr uint32_t top = 1;
+constexpr uint32_t bottom = 2;
+constexpr uint32_t left = 4;
+constexpr uint32_t right = 8;
+}
+
+enum class KeyboardInteractivity : uint32_t { None = 0, Exclusive = 1, OnDemand = 2 };
+
+/// Layer surface properties of new windows, see `LayerConfig` in the crate.
+struct LayerConfig
+{
+    Layer layer = Layer::Top;
+    uint32_t anchor = 0;
+    /// Requested size in logical pixels, 0 to use the preferred size of the window.
+    uint32_t width = 0;
+    uint32_t height = 0;
+    int32_t margin_top = 0;
+    int32_t margin_right = 0;
+    int32_t margin_bottom = 0;
+    int32_t margin_left = 0;
+    int32_t exclusive_zone = 0;
+    bool auto_exclusive_zone = false;
+    KeyboardInteractivity keyboard_interactivity = KeyboardInteractivity::None;
+    std::string namespace_ = "slint-layer-shell";
+};
+
+/// Connects to the Wayland display, or to `WAYLAND_DISPLAY` if `display` is null, and
+/// installs the platform. Returns false if the compositor is unreachable or lacks layer
+/// shell support.
+inline bool install(const char *display = nullptr)
+{
+    return slint_layer_shell_init(display);
+}
+
+/// Sets the layer configuration of windows created from now on, or makes them regular
+/// toplevels when empty. Returns false if the platform is not installed or is dispatching
+/// events, e.g. when called from a Slint callback.
+inline bool set_default_layer_config(const std::optional<LayerConfig> &config)
+{
+    if (!config) {
+        return slint_layer_shell_set_default_layer_config(nullptr);
+    }
+    SlintLayerShellConfig c_config {
+        static_cast<uint32_t>(config->layer),
+        config->anchor,
+        config->width,
+        config->height,
+        config->margin_top,
+        config->margin_right,
+        config->margin_bottom,
+        config->margin_left,
+        config->exclusive_zone,
+        config->auto_exclusive_zone,
+        static_cast<uint32_t>(config->keyboard_interactivity),
+        config->namespace_.c_str(),
+    };
+    return slint_layer_shell_set_default_layer_config(&c_config);
+}
+
+/// Runs the event loop until `quit` is called, as an alternative to `slint::run_event_loop`
+/// that reports the exit code.
+inline int run()
+{
+    return slint_layer_shell_run() ? slint_layer_shell_exit_code() : 1;
+}
+
+inline void quit(int code = 0)
+{
+    slint_layer_shell_quit(code);
+}
+
+}
@@ -0,0 +1,197 @@
+//! Animation of layer surface margins, e.g. to slide panels and on-screen displays in and out
+//! of view.
+//!
+//! Margins are interpolated by a timer of the event loop, and each step is committed like a
+//! margin change made with [`WindowExt::set_margins`]. Negative margins move a surface past
+//! the edge it is anchored to, which hides it:
+//!
+//! ```ignore
+//! let height = osd.window().size().to_logical(osd.window().scale_factor()).height as i32;
+//! let weak = osd.as_weak();
+//! let slide_out = MarginAnimation::new(Margins::new(0, 0, -height, 0), Duration::from_millis(200))
+//!     .easing(Easing::EaseIn)
+//!     .on_finished(move || {
+//!         if let Some(osd) = weak.upgrade() {
+//!             let _ = osd.hide();
+//!         }
+//!     })
+//!     .start(osd.window())?;
+//! ```
+//!
+//! [`WindowExt::set_margins`]: crate::window_ext::WindowExt::set_margins
+
+use crate::layer::{LayerConfig, Margins};
+use crate::window_adapter::LayerShellWindowAdapter;
+use slint::{PlatformError, Timer, TimerMode};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Interval between animation steps, about one frame at 60 Hz.
+const STEP_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Progress of an animation over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Starts fast and slows down, suiting surfaces that slide into view.
+    #[default]
+    EaseOut,
+    EaseIn,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the elapsed fraction `t` of the duration, between 0 and 1, to the progress of the
+    /// animation.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseIn => t.powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Animates the margins of a layer surface from their current value to a target, see the
+/// [module documentation](self).
+pub struct MarginAnimation {
+    to: Margins,
+    duration: Duration,
+    easing: Easing,
+    on_finished: Option<Box<dyn FnOnce()>>,
+}
+
+impl MarginAnimation {
+    pub fn new(to: impl Into<Margins>, duration: Duration) -> Self {
+        Self {
+            to: to.into(),
+            duration,
+            easing: Easing::default(),
+            on_finished: None,
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Registers a callback invoked once the target margins are reached, e.g. to hide a
+    /// surface that slid out of view. It is not invoked when the animation is dropped early.
+    pub fn on_finished(mut self, callback: impl FnOnce() + 'static) -> Self {
+        self.on_finished = Some(Box::new(callback));
+        self
+    }
+
+    /// Starts animating the margins of `window`. The animation stops when the returned handle
+    /// is dropped, leaving the margins where they are.
+    pub fn start(self, window: &slint::Window) -> Result<RunningAnimation, PlatformError> {
+        let window_adapter =
+            LayerShellWindowAdapter::from_slint_window(window).ok_or_else(|| {
+                PlatformError::Other("the window is not backed by this platform".into())
+            })?;
+        let from = window_adapter
+            .layer_config
+            .borrow()
+            .as_ref()
+            .map(|config| config.margins)
+            .ok_or_else(|| PlatformError::Other("the window is not a layer surface".into()))?;
+
+        let timer = Rc::new(Timer::default());
+        let weak_timer = Rc::downgrade(&timer);
+        let weak_adapter = Rc::downgrade(&window_adapter);
+        let start = Instant::now();
+        let on_finished = RefCell::new(self.on_finished);
+        let (to, duration, easing) = (self.to, self.duration, self.easing);
+        timer.start(TimerMode::Repeated, STEP_INTERVAL, move || {
+            let Some(window_adapter) = weak_adapter.upgrade() else {
+                return;
+            };
+            let t = if duration.is_zero() {
+                1.0
+            } else {
+                (start.elapsed().as_secs_f32() / duration.as_secs_f32()).min(1.0)
+            };
+            let margins = interpolate(from, to, easing.apply(t));
+            let config = window_adapter
+                .layer_config
+                .borrow()
+                .clone()
+                .map(|config| LayerConfig { margins, ..config });
+            let result = match config {
+                Some(config) => window_adapter.set_layer_config(config),
+                None => Ok(()),
+            };
+            if let Err(err) = &result {
+                log::warn!("failed to animate margins: {err}");
+            }
+            if t >= 1.0 || result.is_err() {
+                if let Some(timer) = weak_timer.upgrade() {
+                    timer.stop();
+                }
+                if result.is_ok() {
+                    if let Some(on_finished) = on_finished.borrow_mut().take() {
+                        on_finished();
+                    }
+                }
+            }
+        });
+        Ok(RunningAnimation { _timer: timer })
+    }
+}
+
+/// Handle of a started [`MarginAnimation`], which stops it when dropped.
+pub struct RunningAnimation {
+    _timer: Rc<Timer>,
+}
+
+fn interpolate(from: Margins, to: Margins, progress: f32) -> Margins {
+    let lerp = |from: i32, to: i32| (from as f32 + (to - from) as f32 * progress).round() as i32;
+    Margins::new(
+        lerp(from.top, to.top),
+        lerp(from.right, to.right),
+        lerp(from.bottom, to.bottom),
+        lerp(from.left, to.left),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_go_from_start_to_end() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseOut,
+            Easing::EaseIn,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+            let steps = (0..=10).map(|step| easing.apply(step as f32 / 10.0));
+            let progress = steps.collect::<Vec<_>>();
+            assert!(progress.is_sorted(), "{easing:?} goes back: {progress:?}");
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+    }
+
+    #[test]
+    fn interpolate_rounds_between_margins() {
+        let from = Margins::new(0, 10, -20, 4);
+        let to = Margins::new(100, 10, 0, 5);
+        assert_eq!(interpolate(from, to, 0.0), from);
+        assert_eq!(interpolate(from, to, 1.0), to);
+        assert_eq!(interpolate(from, to, 0.25), Margins::new(25, 10, -15, 4));
+    }
+}
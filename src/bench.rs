@@ -0,0 +1,160 @@
+//! Renderer benchmarks, run with `cargo bench --features bench`.
+//!
+//! Each scene is shown as a layer surface in a [`HeadlessCompositor`] and rendered with every
+//! available renderer at integer scales 1 and 2, through the same `render` call the event loop
+//! makes for a redraw. As Slint allows a single platform per process, the renderer is switched
+//! on the platform state between windows.
+
+use crate::builder::Renderer;
+use crate::event::BackendEvent;
+use crate::interpreter::{block_on, instantiate, log_diagnostics};
+use crate::layer::{Anchor, LayerConfig};
+use crate::platform::SlintLayerShell;
+use crate::testing::HeadlessCompositor;
+use crate::window_adapter::LayerShellWindowAdapter;
+use criterion::{BenchmarkId, Criterion};
+use slint::ComponentHandle;
+use slint_interpreter::{Compiler, ComponentDefinition};
+
+const RENDERERS: &[Renderer] = &[
+    #[cfg(feature = "wgpu")]
+    Renderer::Wgpu,
+    Renderer::Software,
+];
+
+const SCALES: &[i32] = &[1, 2];
+
+/// Scenes resembling common shell components, as name and `.slint` source.
+const SCENES: &[(&str, &str)] = &[
+    (
+        "bar",
+        r#"
+export component Bar inherits Window {
+    preferred-height: 32px;
+    background: #1e1e2e;
+    HorizontalLayout {
+        padding: 4px;
+        spacing: 6px;
+        for index in 9: Rectangle {
+            width: 24px;
+            border-radius: 4px;
+            background: index == 2 ? #89b4fa : #313244;
+            Text { text: index + 1; color: #cdd6f4; }
+        }
+        Rectangle {}
+        Text { text: "Wed 16 Oct  12:34"; color: #cdd6f4; vertical-alignment: center; }
+    }
+}
+"#,
+    ),
+    (
+        "notifications",
+        r#"
+export component Notifications inherits Window {
+    preferred-width: 360px;
+    preferred-height: 400px;
+    background: transparent;
+    VerticalLayout {
+        spacing: 8px;
+        for index in 5: Rectangle {
+            height: 72px;
+            border-radius: 10px;
+            background: #313244;
+            drop-shadow-blur: 6px;
+            drop-shadow-color: #00000080;
+            VerticalLayout {
+                padding: 10px;
+                Text { text: "Notification " + index; font-weight: 700; color: #cdd6f4; }
+                Text {
+                    text: "A body long enough to wrap onto a second line of the notification.";
+                    wrap: word-wrap;
+                    color: #a6adc8;
+                }
+            }
+        }
+    }
+}
+"#,
+    ),
+    (
+        "gradient",
+        r#"
+export component Gradient inherits Window {
+    preferred-width: 480px;
+    preferred-height: 270px;
+    background: @linear-gradient(135deg, #1e66f5 0%, #8839ef 50%, #ea76cb 100%);
+    Rectangle {
+        width: 60%;
+        height: 40%;
+        border-radius: 16px;
+        background: #ffffff30;
+        border-width: 1px;
+        border-color: #ffffff60;
+    }
+}
+"#,
+    ),
+];
+
+/// Renders every scene with every renderer and scale.
+pub fn render_scenes(c: &mut Criterion) {
+    let compositor = HeadlessCompositor::spawn().expect("failed to start a headless compositor");
+    let platform = compositor
+        .platform_builder()
+        .build()
+        .expect("failed to connect to the headless compositor");
+    slint::platform::set_platform(Box::new(platform.clone()))
+        .expect("another platform is already installed");
+
+    let mut group = c.benchmark_group("render");
+    for (name, source) in SCENES {
+        let definition = compile(name, source);
+        for renderer in RENDERERS {
+            for scale in SCALES {
+                platform.state.borrow_mut().renderer = *renderer;
+                let config = LayerConfig {
+                    anchor: Anchor::TOP,
+                    ..LayerConfig::default()
+                };
+                let instance = instantiate(&platform, &definition, config)
+                    .expect("failed to create the scene");
+                instance.show().expect("failed to show the scene");
+                wait_until_configured(&platform);
+
+                let window_adapter = LayerShellWindowAdapter::from_slint_window(instance.window())
+                    .expect("the scene is not backed by the platform");
+                platform
+                    .state
+                    .borrow_mut()
+                    .apply_scale_factor(&window_adapter, *scale);
+
+                group.bench_function(
+                    BenchmarkId::new(format!("{name}/{renderer:?}"), scale),
+                    |b| b.iter(|| window_adapter.render.render().expect("failed to render")),
+                );
+                instance.hide().expect("failed to hide the scene");
+            }
+        }
+    }
+    group.finish();
+}
+
+fn compile(name: &str, source: &str) -> ComponentDefinition {
+    let result = block_on(Compiler::default().build_from_source(source.into(), name.into()));
+    log_diagnostics(&result);
+    result
+        .components()
+        .last()
+        .unwrap_or_else(|| panic!("the {name} scene failed to compile"))
+}
+
+/// Runs the event loop until a window is configured, after which it can be rendered.
+fn wait_until_configured(platform: &SlintLayerShell) {
+    platform
+        .run_event_loop_with(|event| {
+            if let BackendEvent::WindowConfigured { .. } = event {
+                platform.quit_with_code(0);
+            }
+        })
+        .expect("the event loop failed");
+}
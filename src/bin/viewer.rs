@@ -0,0 +1,167 @@
+//! Shows a `.slint` file as a layer surface, to prototype bars and overlays without Rust.
+//! The component is reloaded whenever the file changes.
+//!
+//! ```text
+//! slint-layer-shell-viewer bar.slint --anchor tlr --auto-exclusive-zone --output DP-1
+//! ```
+
+use slint_layer_shell::hot_reload::HotReload;
+use slint_layer_shell::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig, Margins};
+use slint_layer_shell::output::PrimaryOutput;
+use slint_layer_shell::platform::SlintLayerShell;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: slint-layer-shell-viewer <file.slint> [options]
+
+options:
+  --component <name>         component to show, defaults to the last exported one
+  --layer <layer>            background, bottom, top (default) or overlay
+  --anchor <edges>           edges to anchor to, any of t, b, l and r, e.g. `tlr`
+  --margins <t,r,b,l>        margins in logical pixels
+  --size <w>x<h>             size in logical pixels, 0 stretches an anchored axis
+  --exclusive-zone <n>       exclusive zone in logical pixels, -1 to ignore other zones
+  --auto-exclusive-zone      reserve as much space as the window is thick
+  --keyboard <mode>          none (default), exclusive or on-demand
+  --namespace <namespace>    layer surface namespace
+  --output <name|index>      connector name of the output, e.g. DP-1, or its index
+  -I <dir>                   additional include path
+";
+
+struct Options {
+    file: PathBuf,
+    component: Option<String>,
+    include_paths: Vec<PathBuf>,
+    output: Option<String>,
+    config: LayerConfig,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut file = None;
+    let mut options = Options {
+        file: PathBuf::new(),
+        component: None,
+        include_paths: vec![slint_layer_shell::SLINT_INCLUDE_DIR.into()],
+        output: None,
+        config: LayerConfig {
+            namespace: "slint-layer-shell-viewer".into(),
+            ..LayerConfig::default()
+        },
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        let config = &mut options.config;
+        match arg.as_str() {
+            "-h" | "--help" => return Err(String::new()),
+            "--component" => options.component = Some(value()?),
+            "-I" => options.include_paths.push(value()?.into()),
+            "--output" => options.output = Some(value()?),
+            "--namespace" => config.namespace = value()?,
+            "--auto-exclusive-zone" => config.auto_exclusive_zone = true,
+            "--exclusive-zone" => {
+                config.exclusive_zone = value()?
+                    .parse()
+                    .map_err(|e| format!("invalid exclusive zone: {e}"))?
+            }
+            "--layer" => {
+                config.layer = match value()?.as_str() {
+                    "background" => Layer::Background,
+                    "bottom" => Layer::Bottom,
+                    "top" => Layer::Top,
+                    "overlay" => Layer::Overlay,
+                    layer => return Err(format!("unknown layer {layer}")),
+                }
+            }
+            "--keyboard" => {
+                config.keyboard_interactivity = match value()?.as_str() {
+                    "none" => KeyboardInteractivity::None,
+                    "exclusive" => KeyboardInteractivity::Exclusive,
+                    "on-demand" => KeyboardInteractivity::OnDemand,
+                    mode => return Err(format!("unknown keyboard mode {mode}")),
+                }
+            }
+            "--anchor" => {
+                for edge in value()?.chars() {
+                    config.anchor |= match edge {
+                        't' => Anchor::TOP,
+                        'b' => Anchor::BOTTOM,
+                        'l' => Anchor::LEFT,
+                        'r' => Anchor::RIGHT,
+                        edge => return Err(format!("unknown edge {edge}")),
+                    };
+                }
+            }
+            "--margins" => {
+                let margins = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<Vec<i32>, _>>()
+                    .map_err(|e| format!("invalid margins: {e}"))?;
+                let [top, right, bottom, left] = margins[..] else {
+                    return Err("expected four margins".into());
+                };
+                config.margins = Margins::new(top, right, bottom, left);
+            }
+            "--size" => {
+                let size = value()?;
+                let (width, height) = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid size {size}"))?;
+                config.size = Some((width, height));
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ if file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    options.file = file.ok_or_else(|| "missing .slint file".to_string())?;
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let platform = SlintLayerShell::builder()
+        .default_layer_config(options.config)
+        .build()
+        .map_err(|e| e.to_string())?;
+    if let Some(name) = options.output {
+        platform.set_primary_output(Some(PrimaryOutput::parse(&name)));
+    }
+    slint::platform::set_platform(Box::new(platform.clone())).map_err(|e| e.to_string())?;
+
+    // Keeps the component up to date with the file while the viewer runs.
+    let _reload = HotReload::new(
+        &options.file,
+        options.component.as_deref(),
+        options.include_paths,
+    )
+    .map_err(|e| e.to_string())?;
+    slint::run_event_loop_until_quit().map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{err}\n");
+            }
+            eprint!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::layer::LayerConfig;
+use crate::platform::SlintLayerShell;
+use slint::PlatformError;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use wayland_backend::client::Backend;
+use wayland_client::Connection;
+
+/// How windows are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Renderer {
+    /// Skia on the GPU through wgpu. Requires the `wgpu` feature.
+    #[cfg(feature = "wgpu")]
+    #[default]
+    Wgpu,
+    /// Skia on the CPU into shared memory buffers, for systems without a usable GPU.
+    #[cfg_attr(not(feature = "wgpu"), default)]
+    Software,
+}
+
+/// When the event loop started by [`SlintLayerShell::run_event_loop_with`] returns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuitPolicy {
+    /// Only when [`slint::quit_event_loop`] or [`SlintLayerShell::quit_with_code`] is
+    /// called, as shell components usually run for the whole session.
+    #[default]
+    Explicit,
+    /// Also once every window has been hidden, after at least one was shown.
+    AfterLastWindowHidden,
+}
+
+/// Configures and creates a [`SlintLayerShell`].
+#[derive(Default)]
+pub struct SlintLayerShellBuilder {
+    display: Option<PathBuf>,
+    connection: Option<Connection>,
+    fallback_to_default_backend: bool,
+    fallback_to_xdg_shell: bool,
+    systemd_notify: bool,
+    renderer: Renderer,
+    default_layer_config: Option<LayerConfig>,
+    quit_policy: QuitPolicy,
+    frame_stats: bool,
+}
+
+impl SlintLayerShellBuilder {
+    /// Connects to the given Wayland display instead of the one from `WAYLAND_DISPLAY`.
+    ///
+    /// Relative names such as `wayland-1` are resolved against `XDG_RUNTIME_DIR`, absolute
+    /// paths are used as is. This is handy to develop against a nested compositor.
+    pub fn display(mut self, display: impl Into<PathBuf>) -> Self {
+        self.display = Some(display.into());
+        self
+    }
+
+    /// Uses an existing connection instead of opening a new one, to share it with other code
+    /// in the process that talks Wayland, such as a toolkit or a game engine. The platform
+    /// dispatches its objects on its own event queue, so the other users of the connection
+    /// are not affected. Takes precedence over [`Self::display`].
+    ///
+    /// Other code may also keep its own separate connection, nothing in this crate is global
+    /// to the process. Slint however has a single platform per process, so Slint windows are
+    /// always created by the installed platform, and winit windows driven by Slint cannot be
+    /// mixed with layer surfaces.
+    pub fn connection(mut self, connection: Connection) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    /// Shares the `wl_display` of a C library, such as one returned by
+    /// `gdk_wayland_display_get_wl_display` or found in a raw window handle, see
+    /// [`Self::connection`].
+    ///
+    /// # Safety
+    ///
+    /// `display` must be a valid `wl_display` pointer that outlives the platform.
+    pub unsafe fn foreign_display(self, display: *mut std::ffi::c_void) -> Self {
+        let backend = unsafe { Backend::from_foreign_display(display.cast()) };
+        self.connection(Connection::from_backend(backend))
+    }
+
+    /// When enabled, [`Self::install`] initializes Slint's default winit backend if the session
+    /// is not Wayland or the compositor lacks a layer shell (e.g. GNOME).
+    ///
+    /// Requires the `fallback-winit` feature.
+    pub fn fallback_to_default_backend(mut self, enabled: bool) -> Self {
+        self.fallback_to_default_backend = enabled;
+        self
+    }
+
+    /// Creates windows as regular xdg toplevels with a warning on compositors without
+    /// a layer shell, such as GNOME, instead of failing. Layer configurations are
+    /// ignored then, see [`SlintLayerShell::uses_layer_shell`].
+    pub fn fallback_to_xdg_shell(mut self, enabled: bool) -> Self {
+        self.fallback_to_xdg_shell = enabled;
+        self
+    }
+
+    /// Notifies systemd (`Type=notify` services) once the first surface is mapped, and pings
+    /// the watchdog if `WatchdogSec=` is configured. Does nothing outside of systemd.
+    pub fn systemd_notify(mut self, enabled: bool) -> Self {
+        self.systemd_notify = enabled;
+        self
+    }
+
+    pub fn renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Makes windows layer surfaces with `config` by default, see
+    /// [`SlintLayerShell::set_default_layer_config`]. Without it, windows are xdg toplevels.
+    pub fn default_layer_config(mut self, config: LayerConfig) -> Self {
+        self.default_layer_config = Some(config);
+        self
+    }
+
+    pub fn quit_policy(mut self, quit_policy: QuitPolicy) -> Self {
+        self.quit_policy = quit_policy;
+        self
+    }
+
+    /// Logs frame rate and render times every second, see
+    /// [`SlintLayerShell::enable_frame_stats`].
+    pub fn frame_stats(mut self, enabled: bool) -> Self {
+        self.frame_stats = enabled;
+        self
+    }
+
+    /// Creates the platform and installs it with [`slint::platform::set_platform`], falling
+    /// back to the default backend if configured to.
+    ///
+    /// Returns the layer shell platform, or `None` if the fallback backend was installed.
+    pub fn install(self) -> Result<Option<SlintLayerShell>, PlatformError> {
+        let fallback_to_default_backend = self.fallback_to_default_backend;
+        match self.build() {
+            Ok(platform) => {
+                slint::platform::set_platform(Box::new(platform.clone()))
+                    .map_err(|e| PlatformError::Other(e.to_string()))?;
+                Ok(Some(platform))
+            }
+            Err(err) if fallback_to_default_backend => {
+                install_default_backend(err)?;
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Connects to the compositor and creates the platform, without installing it.
+    pub fn build(self) -> Result<SlintLayerShell, PlatformError> {
+        let connection = match (self.connection, self.display) {
+            (Some(connection), _) => Ok(connection),
+            (None, Some(display)) => {
+                let path = if display.is_absolute() {
+                    display
+                } else {
+                    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+                        .ok_or_else(|| PlatformError::Other("XDG_RUNTIME_DIR is not set".into()))?;
+                    PathBuf::from(runtime_dir).join(display)
+                };
+                let stream = UnixStream::connect(&path).map_err(|e| {
+                    PlatformError::Other(format!("failed to connect to {}: {e}", path.display()))
+                })?;
+                Connection::from_socket(stream)
+            }
+            (None, None) => Connection::connect_to_env(),
+        }
+        .map_err(|e| PlatformError::Other(format!("failed to connect to Wayland: {e}")))?;
+
+        let mut platform =
+            SlintLayerShell::with_connection(connection, self.fallback_to_xdg_shell)?;
+        if self.systemd_notify {
+            platform.enable_systemd_notify();
+        }
+        platform.set_default_layer_config(self.default_layer_config);
+        platform.set_quit_policy(self.quit_policy);
+        if self.frame_stats {
+            platform.enable_frame_stats(true);
+        }
+        platform.state.borrow_mut().renderer = self.renderer;
+        Ok(platform)
+    }
+}
+
+#[cfg(feature = "fallback-winit")]
+fn install_default_backend(err: PlatformError) -> Result<(), PlatformError> {
+    log::warn!("layer shell unavailable ({err}), falling back to the default backend");
+    slint::BackendSelector::new()
+        .backend_name("winit".into())
+        .select()
+}
+
+/// Without a fallback backend, the error of the layer shell is returned as is.
+#[cfg(not(feature = "fallback-winit"))]
+fn install_default_backend(err: PlatformError) -> Result<(), PlatformError> {
+    Err(err)
+}
@@ -0,0 +1,121 @@
+use crate::layer_shell::LayerShell;
+use crate::platform::LayerShellState;
+use std::fmt;
+use wayland_client::Proxy;
+
+/// Protocols available to the platform, with the version they were bound at.
+///
+/// Features needing a newer version than the compositor provides degrade with a warning,
+/// e.g. on-demand keyboard interactivity becomes exclusive before layer shell version 4.
+///
+/// Optional protocols are `None` when the compositor does not advertise them, or when the
+/// crate feature using them is disabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    pub compositor: u32,
+    /// Version of the layer shell in use, `ext_layer_shell_v1` if `ext_layer_shell` is set
+    /// and `zwlr_layer_shell_v1` otherwise. 0 when the compositor has no layer shell, see
+    /// [`SlintLayerShell::uses_layer_shell`].
+    ///
+    /// [`SlintLayerShell::uses_layer_shell`]: crate::platform::SlintLayerShell::uses_layer_shell
+    pub layer_shell: u32,
+    pub xdg_shell: u32,
+    pub session_lock: Option<u32>,
+    pub text_input: Option<u32>,
+    pub virtual_keyboard: Option<u32>,
+    pub foreign_toplevel: Option<u32>,
+    pub screencopy: Option<u32>,
+    /// The proposed `ext_layer_shell_v1`, which layer surfaces are created with in place of
+    /// `zwlr_layer_shell_v1` when the compositor advertises it.
+    pub ext_layer_shell: Option<u32>,
+    /// Advertised by the compositor, but not used by this backend yet.
+    pub fractional_scale: Option<u32>,
+    /// Advertised by the compositor, but not used by this backend yet.
+    pub viewporter: Option<u32>,
+    /// Every global advertised by the compositor, as interface name and version.
+    pub globals: Vec<(String, u32)>,
+}
+
+impl Capabilities {
+    pub(crate) fn new(state: &LayerShellState) -> Self {
+        let globals = state
+            .registry_state
+            .globals()
+            .map(|global| (global.interface.clone(), global.version))
+            .collect::<Vec<_>>();
+
+        let mut capabilities = Self {
+            compositor: state.compositor_state.wl_compositor().version(),
+            layer_shell: state.layer_shell_version(),
+            xdg_shell: state.xdg_shell.xdg_wm_base().version(),
+            #[cfg(feature = "text-input")]
+            text_input: state.text_input_manager.as_ref().map(Proxy::version),
+            #[cfg(not(feature = "text-input"))]
+            text_input: None,
+            #[cfg(feature = "virtual-keyboard")]
+            virtual_keyboard: state.virtual_keyboard_manager.as_ref().map(Proxy::version),
+            #[cfg(not(feature = "virtual-keyboard"))]
+            virtual_keyboard: None,
+            #[cfg(feature = "foreign-toplevel")]
+            foreign_toplevel: state.foreign_toplevel_manager.as_ref().map(Proxy::version),
+            #[cfg(not(feature = "foreign-toplevel"))]
+            foreign_toplevel: None,
+            #[cfg(feature = "screencopy")]
+            screencopy: state.screencopy_manager.as_ref().map(Proxy::version),
+            #[cfg(not(feature = "screencopy"))]
+            screencopy: None,
+            ext_layer_shell: match &state.layer_shell {
+                Some(LayerShell::Ext(layer_shell)) => Some(layer_shell.version()),
+                _ => None,
+            },
+            globals,
+            ..Self::default()
+        };
+        // sctk binds the session lock manager at version 1.
+        #[cfg(feature = "session-lock")]
+        {
+            capabilities.session_lock = capabilities
+                .advertised("ext_session_lock_manager_v1")
+                .map(|_| 1);
+        }
+        capabilities.fractional_scale = capabilities.advertised("wp_fractional_scale_manager_v1");
+        capabilities.viewporter = capabilities.advertised("wp_viewporter");
+        capabilities
+    }
+
+    /// Returns the version the compositor advertises `interface` at, if it does.
+    pub fn advertised(&self, interface: &str) -> Option<u32> {
+        self.globals
+            .iter()
+            .find(|(name, _)| name == interface)
+            .map(|(_, version)| *version)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    /// Lists the protocols one per line, for diagnostics.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = [
+            ("ext_session_lock_manager_v1", self.session_lock),
+            ("zwp_text_input_manager_v3", self.text_input),
+            ("zwp_virtual_keyboard_manager_v1", self.virtual_keyboard),
+            ("zwlr_foreign_toplevel_manager_v1", self.foreign_toplevel),
+            ("zwlr_screencopy_manager_v1", self.screencopy),
+            ("wp_fractional_scale_manager_v1", self.fractional_scale),
+            ("wp_viewporter", self.viewporter),
+        ];
+        writeln!(f, "wl_compositor: v{}", self.compositor)?;
+        match self.ext_layer_shell {
+            Some(version) => writeln!(f, "ext_layer_shell_v1: v{version}")?,
+            None => writeln!(f, "zwlr_layer_shell_v1: v{}", self.layer_shell)?,
+        }
+        write!(f, "xdg_wm_base: v{}", self.xdg_shell)?;
+        for (interface, version) in optional {
+            match version {
+                Some(version) => write!(f, "\n{interface}: v{version}")?,
+                None => write!(f, "\n{interface}: unavailable")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,663 @@
+//! Copy and paste through `wl_data_device`, data dropped onto windows, and the primary
+//! selection through `zwp_primary_selection_v1`.
+//!
+//! Besides text, images are exchanged as `image/png` and files as `text/uri-list`. Text is
+//! also what Slint's `TextInput` copies and pastes through the platform. Selecting text in a
+//! `TextInput` sets the primary selection, and middle-clicking one pastes it, as usual on
+//! Linux.
+
+use crate::event::BackendEvent;
+use crate::platform::{LayerShellState, SlintLayerShell};
+use slint::{Image, PlatformError, Rgba8Pixel, SharedPixelBuffer};
+use smithay_client_toolkit::data_device_manager::data_device::{DataDevice, DataDeviceHandler};
+use smithay_client_toolkit::data_device_manager::data_offer::{
+    DataOfferHandler, DragOffer, SelectionOffer,
+};
+use smithay_client_toolkit::data_device_manager::data_source::{
+    CopyPasteSource, DataSourceHandler,
+};
+use smithay_client_toolkit::data_device_manager::{DataDeviceManagerState, ReadPipe, WritePipe};
+use smithay_client_toolkit::primary_selection::PrimarySelectionManagerState;
+use smithay_client_toolkit::primary_selection::device::{
+    PrimarySelectionDevice, PrimarySelectionDeviceHandler,
+};
+use smithay_client_toolkit::primary_selection::offer::PrimarySelectionOffer;
+use smithay_client_toolkit::primary_selection::selection::{
+    PrimarySelectionSource, PrimarySelectionSourceHandler,
+};
+use smithay_client_toolkit::reexports::protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+    zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+};
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use wayland_client::globals::GlobalList;
+use wayland_client::protocol::wl_data_device::WlDataDevice;
+use wayland_client::protocol::wl_data_device_manager::DndAction;
+use wayland_client::protocol::wl_data_source::WlDataSource;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{Connection, QueueHandle};
+
+const TEXT_MIME_TYPES: [&str; 3] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+const PNG_MIME_TYPE: &str = "image/png";
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+/// How long to wait for another application to send the clipboard contents.
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Data on the clipboard or dropped onto a window.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClipboardContent {
+    Text(String),
+    Image(Image),
+    /// Local files, e.g. copied in a file manager.
+    Files(Vec<PathBuf>),
+}
+
+impl ClipboardContent {
+    /// Returns the MIME types the content is offered as, preferred ones first. Files are also
+    /// offered as text, listing their paths.
+    fn mime_types(&self) -> Vec<&'static str> {
+        match self {
+            ClipboardContent::Text(_) => TEXT_MIME_TYPES.to_vec(),
+            ClipboardContent::Image(_) => vec![PNG_MIME_TYPE],
+            ClipboardContent::Files(_) => [URI_LIST_MIME_TYPE]
+                .into_iter()
+                .chain(TEXT_MIME_TYPES)
+                .collect(),
+        }
+    }
+
+    fn encode(&self, mime_type: &str) -> Option<Vec<u8>> {
+        match (self, mime_type) {
+            (ClipboardContent::Text(text), _) => Some(text.clone().into_bytes()),
+            (ClipboardContent::Image(image), _) => encode_png(&image.to_rgba8()?),
+            (ClipboardContent::Files(paths), URI_LIST_MIME_TYPE) => Some(
+                paths
+                    .iter()
+                    .map(|path| format!("file://{}\r\n", percent_encode(&path.to_string_lossy())))
+                    .collect::<String>()
+                    .into_bytes(),
+            ),
+            (ClipboardContent::Files(paths), _) => Some(
+                paths
+                    .iter()
+                    .map(|path| path.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    fn decode(mime_type: &str, data: Vec<u8>) -> Option<Self> {
+        match mime_type {
+            PNG_MIME_TYPE => decode_png(&data).map(|buffer| Self::Image(Image::from_rgba8(buffer))),
+            URI_LIST_MIME_TYPE => {
+                let paths = String::from_utf8_lossy(&data)
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| line.trim().strip_prefix("file://"))
+                    // Skip the host part of `file://host/path` URIs.
+                    .filter_map(|path| path.find('/').map(|start| &path[start..]))
+                    .map(|path| PathBuf::from(percent_decode(path)))
+                    .collect::<Vec<_>>();
+                (!paths.is_empty()).then_some(Self::Files(paths))
+            }
+            _ => String::from_utf8(data).ok().map(Self::Text),
+        }
+    }
+}
+
+/// Picks the MIME type to receive from those offered, preferring images, then files, then
+/// text.
+fn preferred_mime_type(offered: &[String]) -> Option<String> {
+    [PNG_MIME_TYPE, URI_LIST_MIME_TYPE]
+        .into_iter()
+        .chain(TEXT_MIME_TYPES)
+        .find(|mime_type| offered.iter().any(|offered| offered == mime_type))
+        .map(str::to_owned)
+}
+
+fn text_mime_type(offered: &[String]) -> Option<String> {
+    TEXT_MIME_TYPES
+        .into_iter()
+        .find(|mime_type| offered.iter().any(|offered| offered == mime_type))
+        .map(str::to_owned)
+}
+
+/// Clipboard state, shared between the Wayland state and the platform, as Slint reads the
+/// clipboard while input events are dispatched.
+pub(crate) struct ClipboardState {
+    manager: Option<DataDeviceManagerState>,
+    device: Option<DataDevice>,
+    /// Serial of the latest key or button press, which the compositor requires to change
+    /// the selection.
+    pub last_serial: u32,
+    selection: Option<SelectionOffer>,
+    /// Source and contents of the selection while this application owns it.
+    source: Option<(CopyPasteSource, ClipboardContent)>,
+    /// Drag offer being received after a drop, finished once its data arrived.
+    pending_drop: Option<DragOffer>,
+    primary_manager: Option<PrimarySelectionManagerState>,
+    primary_device: Option<PrimarySelectionDevice>,
+    primary_selection: Option<PrimarySelectionOffer>,
+    /// Source and text of the primary selection while this application owns it.
+    primary_source: Option<(PrimarySelectionSource, String)>,
+    drop_sender: calloop::channel::Sender<(WlSurface, String, Vec<u8>)>,
+}
+
+impl ClipboardState {
+    /// Binds the data device manager and delivers dropped data as [`BackendEvent::DataDropped`]
+    /// from the event loop.
+    pub fn new(
+        globals: &GlobalList,
+        qh: &QueueHandle<LayerShellState>,
+        loop_handle: &calloop::LoopHandle<'static, LayerShellState>,
+    ) -> Rc<RefCell<Self>> {
+        let (drop_sender, drop_channel) = calloop::channel::channel();
+        let _ = loop_handle.insert_source(drop_channel, |event, _, state: &mut LayerShellState| {
+            let calloop::channel::Event::Msg((surface, mime_type, data)) = event else {
+                return;
+            };
+            if let Some(offer) = state.clipboard.borrow_mut().pending_drop.take() {
+                offer.finish();
+                offer.destroy();
+            }
+            if let Some(content) = ClipboardContent::decode(&mime_type, data) {
+                state
+                    .backend_events
+                    .push_back(BackendEvent::DataDropped { surface, content });
+            }
+        });
+
+        Rc::new(RefCell::new(Self {
+            manager: DataDeviceManagerState::bind(globals, qh).ok(),
+            device: None,
+            last_serial: 0,
+            selection: None,
+            source: None,
+            pending_drop: None,
+            primary_manager: PrimarySelectionManagerState::bind(globals, qh).ok(),
+            primary_device: None,
+            primary_selection: None,
+            primary_source: None,
+            drop_sender,
+        }))
+    }
+}
+
+impl LayerShellState {
+    /// Creates the data device and primary selection device of `seat`, unless the
+    /// compositor lacks the protocols or they exist already.
+    pub(crate) fn create_data_device(&mut self, seat: &WlSeat, qh: &QueueHandle<Self>) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        if clipboard.device.is_none() {
+            clipboard.device = clipboard
+                .manager
+                .as_ref()
+                .map(|manager| manager.get_data_device(qh, seat));
+        }
+        if clipboard.primary_device.is_none() {
+            clipboard.primary_device = clipboard
+                .primary_manager
+                .as_ref()
+                .map(|manager| manager.get_selection_device(qh, seat));
+        }
+    }
+}
+
+impl SlintLayerShell {
+    /// Puts `content` on the clipboard. It is served from this application until another one
+    /// takes over the clipboard.
+    pub fn set_clipboard_content(&self, content: ClipboardContent) -> Result<(), PlatformError> {
+        let mut clipboard = self.clipboard.borrow_mut();
+        let (Some(manager), Some(device)) = (&clipboard.manager, &clipboard.device) else {
+            return Err(PlatformError::Other(
+                "wl_data_device_manager is not available".into(),
+            ));
+        };
+        let source = manager.create_copy_paste_source(self.queue_handle(), content.mime_types());
+        source.set_selection(device, clipboard.last_serial);
+        clipboard.source = Some((source, content));
+        let _ = self.connection().flush();
+        Ok(())
+    }
+
+    /// Returns the clipboard contents, converted from the richest type offered.
+    pub fn clipboard_content(&self) -> Option<ClipboardContent> {
+        if let Some((_, content)) = &self.clipboard.borrow().source {
+            return Some(content.clone());
+        }
+        self.receive_selection(preferred_mime_type)
+    }
+
+    /// Returns the MIME types offered by the clipboard.
+    pub fn clipboard_mime_types(&self) -> Vec<String> {
+        let clipboard = self.clipboard.borrow();
+        if let Some((_, content)) = &clipboard.source {
+            return content
+                .mime_types()
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+        }
+        clipboard
+            .selection
+            .as_ref()
+            .map(|offer| offer.with_mime_types(|mime_types| mime_types.to_vec()))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn read_clipboard_text(&self) -> Option<String> {
+        if let Some((_, content)) = &self.clipboard.borrow().source {
+            return content
+                .encode(TEXT_MIME_TYPES[0])
+                .and_then(|text| String::from_utf8(text).ok());
+        }
+        match self.receive_selection(text_mime_type)? {
+            ClipboardContent::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Sets the primary selection, which other applications paste on middle click.
+    pub fn set_primary_selection(&self, text: &str) -> Result<(), PlatformError> {
+        let mut clipboard = self.clipboard.borrow_mut();
+        let (Some(manager), Some(device)) = (&clipboard.primary_manager, &clipboard.primary_device)
+        else {
+            return Err(PlatformError::Other(
+                "zwp_primary_selection_device_manager_v1 is not available".into(),
+            ));
+        };
+        let source = manager.create_selection_source(self.queue_handle(), TEXT_MIME_TYPES);
+        source.set_selection(device, clipboard.last_serial);
+        clipboard.primary_source = Some((source, text.to_owned()));
+        let _ = self.connection().flush();
+        Ok(())
+    }
+
+    /// Returns the text of the primary selection.
+    pub fn primary_selection(&self) -> Option<String> {
+        let (mime_type, pipe) = {
+            let clipboard = self.clipboard.borrow();
+            if let Some((_, text)) = &clipboard.primary_source {
+                return Some(text.clone());
+            }
+            let offer = clipboard.primary_selection.as_ref()?;
+            let mime_type = offer.with_mime_types(text_mime_type)?;
+            let pipe = offer.receive(mime_type.clone()).ok()?;
+            (mime_type, pipe)
+        };
+        match self.receive(mime_type, pipe)? {
+            ClipboardContent::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Receives the selection as the MIME type picked by `choose`.
+    fn receive_selection(
+        &self,
+        choose: impl FnOnce(&[String]) -> Option<String>,
+    ) -> Option<ClipboardContent> {
+        let (mime_type, pipe) = {
+            let clipboard = self.clipboard.borrow();
+            let offer = clipboard.selection.as_ref()?;
+            let mime_type = offer.with_mime_types(choose)?;
+            let pipe = offer.receive(mime_type.clone()).ok()?;
+            (mime_type, pipe)
+        };
+        self.receive(mime_type, pipe)
+    }
+
+    /// Reads data sent by another application, waiting at most [`RECEIVE_TIMEOUT`].
+    fn receive(&self, mime_type: String, pipe: ReadPipe) -> Option<ClipboardContent> {
+        let _ = self.connection().flush();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(read_pipe(pipe));
+        });
+        let data = receiver.recv_timeout(RECEIVE_TIMEOUT).ok()??;
+        ClipboardContent::decode(&mime_type, data)
+    }
+}
+
+fn read_pipe(mut pipe: ReadPipe) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    pipe.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+impl DataDeviceHandler for LayerShellState {
+    fn enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+        _x: f64,
+        _y: f64,
+        _surface: &WlSurface,
+    ) {
+        let clipboard = self.clipboard.borrow();
+        let Some(offer) = clipboard
+            .device
+            .as_ref()
+            .and_then(|device| device.data().drag_offer())
+        else {
+            return;
+        };
+        let mime_type = offer.with_mime_types(preferred_mime_type);
+        offer.accept_mime_type(offer.serial, mime_type.clone());
+        if mime_type.is_some() {
+            offer.set_actions(DndAction::Copy, DndAction::Copy);
+        }
+    }
+
+    fn leave(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _data_device: &WlDataDevice) {}
+
+    fn motion(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+        _x: f64,
+        _y: f64,
+    ) {
+    }
+
+    fn selection(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+    ) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        clipboard.selection = clipboard
+            .device
+            .as_ref()
+            .and_then(|device| device.data().selection_offer());
+    }
+
+    fn drop_performed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _data_device: &WlDataDevice,
+    ) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        let Some(offer) = clipboard
+            .device
+            .as_ref()
+            .and_then(|device| device.data().drag_offer())
+        else {
+            return;
+        };
+        let Some(mime_type) = offer.with_mime_types(preferred_mime_type) else {
+            offer.destroy();
+            return;
+        };
+        let Ok(pipe) = offer.receive(mime_type.clone()) else {
+            offer.destroy();
+            return;
+        };
+        let surface = offer.surface.clone();
+        let sender = clipboard.drop_sender.clone();
+        clipboard.pending_drop = Some(offer);
+        std::thread::spawn(move || {
+            if let Some(data) = read_pipe(pipe) {
+                let _ = sender.send((surface, mime_type, data));
+            }
+        });
+    }
+}
+
+impl DataOfferHandler for LayerShellState {
+    fn source_actions(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _offer: &mut DragOffer,
+        _actions: DndAction,
+    ) {
+    }
+
+    fn selected_action(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _offer: &mut DragOffer,
+        _actions: DndAction,
+    ) {
+    }
+}
+
+impl DataSourceHandler for LayerShellState {
+    fn accept_mime(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+        _mime: Option<String>,
+    ) {
+    }
+
+    fn send_request(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &WlDataSource,
+        mime: String,
+        mut fd: WritePipe,
+    ) {
+        let data = match &self.clipboard.borrow().source {
+            Some((owned, content)) if owned.inner() == source => content.encode(&mime),
+            _ => None,
+        };
+        // Write from a thread, so a slow reader does not block the event loop.
+        if let Some(data) = data {
+            std::thread::spawn(move || {
+                let _ = fd.write_all(&data);
+            });
+        }
+    }
+
+    fn cancelled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &WlDataSource) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        if clipboard
+            .source
+            .as_ref()
+            .is_some_and(|(owned, _)| owned.inner() == source)
+        {
+            clipboard.source = None;
+        }
+    }
+
+    fn dnd_dropped(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _source: &WlDataSource) {
+    }
+
+    fn dnd_finished(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+    ) {
+    }
+
+    fn action(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &WlDataSource,
+        _action: DndAction,
+    ) {
+    }
+}
+
+impl PrimarySelectionDeviceHandler for LayerShellState {
+    fn selection(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _primary_selection_device: &ZwpPrimarySelectionDeviceV1,
+    ) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        clipboard.primary_selection = clipboard
+            .primary_device
+            .as_ref()
+            .and_then(|device| device.data().selection_offer());
+    }
+}
+
+impl PrimarySelectionSourceHandler for LayerShellState {
+    fn send_request(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+        _mime: String,
+        mut write_pipe: WritePipe,
+    ) {
+        let text = match &self.clipboard.borrow().primary_source {
+            Some((owned, text)) if owned.inner() == source => text.clone(),
+            _ => return,
+        };
+        std::thread::spawn(move || {
+            let _ = write_pipe.write_all(text.as_bytes());
+        });
+    }
+
+    fn cancelled(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+    ) {
+        let mut clipboard = self.clipboard.borrow_mut();
+        if clipboard
+            .primary_source
+            .as_ref()
+            .is_some_and(|(owned, _)| owned.inner() == source)
+        {
+            clipboard.primary_source = None;
+        }
+    }
+}
+
+smithay_client_toolkit::delegate_data_device!(LayerShellState);
+smithay_client_toolkit::delegate_primary_selection!(LayerShellState);
+
+fn encode_png(buffer: &SharedPixelBuffer<Rgba8Pixel>) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, buffer.width(), buffer.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(buffer.as_bytes()).ok()?;
+    writer.finish().ok()?;
+    Some(data)
+}
+
+fn decode_png(data: &[u8]) -> Option<SharedPixelBuffer<Rgba8Pixel>> {
+    let mut decoder = png::Decoder::new(data);
+    // Expand palettes, low bit depths and missing alpha into 8-bit channels with alpha.
+    decoder.set_transformations(
+        png::Transformations::normalize_to_color8() | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info().ok()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).ok()?;
+    let pixels = &pixels[..info.buffer_size()];
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        _ => return None,
+    };
+    Some(SharedPixelBuffer::clone_from_slice(
+        &rgba,
+        info.width,
+        info.height,
+    ))
+}
+
+/// Escapes the characters of `path` that are not allowed in a URI path.
+fn percent_encode(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("/tmp/a%20b%C3%A9"), "/tmp/a bé");
+        assert_eq!(percent_decode("/tmp/100%"), "/tmp/100%");
+        assert_eq!(percent_decode("/tmp/%zz%4"), "/tmp/%zz%4");
+        assert_eq!(
+            percent_decode(&percent_encode("/tmp/ä b;c%")),
+            "/tmp/ä b;c%"
+        );
+    }
+
+    #[test]
+    fn uri_lists_decode_to_local_paths() {
+        let list = "# copied\r\nfile:///tmp/a%20b\r\nfile://host/home/c\r\n\
+                    https://example.com/d\r\n";
+        assert_eq!(
+            ClipboardContent::decode(URI_LIST_MIME_TYPE, list.into()),
+            Some(ClipboardContent::Files(vec![
+                "/tmp/a b".into(),
+                "/home/c".into()
+            ]))
+        );
+        assert_eq!(
+            ClipboardContent::decode(URI_LIST_MIME_TYPE, "https://example.com/d\n".into()),
+            None
+        );
+
+        let files = ClipboardContent::Files(vec!["/tmp/é f.txt".into(), "/home/g".into()]);
+        let encoded = files.encode(URI_LIST_MIME_TYPE).unwrap();
+        assert_eq!(
+            ClipboardContent::decode(URI_LIST_MIME_TYPE, encoded),
+            Some(files)
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! Workspace, window and binding mode data from the IPC of Sway and Hyprland, for
+//! compositors lacking `ext-workspace` and `foreign-toplevel` support.
+//!
+//! The compositor is queried on a background thread whenever it reports a change, and the
+//! new data is applied to the models on the event loop thread.
+
+use crate::platform::SlintLayerShell;
+use serde_json::{Value, json};
+use slint::{Model, ModelExt, ModelRc, PlatformError, VecModel};
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const SWAY_MAGIC: &[u8] = b"i3-ipc";
+const SWAY_RUN_COMMAND: u32 = 0;
+const SWAY_GET_WORKSPACES: u32 = 1;
+const SWAY_SUBSCRIBE: u32 = 2;
+const SWAY_GET_TREE: u32 = 4;
+const SWAY_GET_BINDING_STATE: u32 = 12;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    /// Connector name of the output showing the workspace.
+    pub output: String,
+    pub focused: bool,
+    /// Shown on its output, but not necessarily focused.
+    pub visible: bool,
+    pub urgent: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IpcWindow {
+    /// Container id on Sway, client address on Hyprland.
+    pub id: String,
+    pub title: String,
+    /// Wayland app id, or the X11 class for Xwayland windows.
+    pub app_id: String,
+    pub workspace: String,
+    pub focused: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Snapshot {
+    workspaces: Vec<Workspace>,
+    windows: Vec<IpcWindow>,
+    mode: String,
+}
+
+#[derive(Clone, Debug)]
+enum Backend {
+    Sway(PathBuf),
+    Hyprland(PathBuf),
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        if let Some(socket) = std::env::var_os("SWAYSOCK") {
+            return Some(Backend::Sway(socket.into()));
+        }
+        let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(|dir| PathBuf::from(dir).join("hypr"))
+            .filter(|dir| dir.join(&signature).exists())
+            .unwrap_or_else(|| PathBuf::from("/tmp/hypr"));
+        Some(Backend::Hyprland(runtime_dir.join(signature)))
+    }
+
+    fn snapshot(&self) -> std::io::Result<Snapshot> {
+        match self {
+            Backend::Sway(socket) => {
+                let mut stream = UnixStream::connect(socket)?;
+                let workspaces = sway_request(&mut stream, SWAY_GET_WORKSPACES, "")?;
+                let tree = sway_request(&mut stream, SWAY_GET_TREE, "")?;
+                let mode = sway_request(&mut stream, SWAY_GET_BINDING_STATE, "")?;
+
+                let mut windows = Vec::new();
+                collect_sway_windows(&tree, "", &mut windows);
+                Ok(Snapshot {
+                    workspaces: array(&workspaces)
+                        .map(|workspace| Workspace {
+                            id: workspace["id"].as_i64().unwrap_or_default(),
+                            name: string(&workspace["name"]),
+                            output: string(&workspace["output"]),
+                            focused: workspace["focused"].as_bool().unwrap_or(false),
+                            visible: workspace["visible"].as_bool().unwrap_or(false),
+                            urgent: workspace["urgent"].as_bool().unwrap_or(false),
+                        })
+                        .collect(),
+                    windows,
+                    mode: Some(string(&mode["name"]))
+                        .filter(|mode| mode != "default")
+                        .unwrap_or_default(),
+                })
+            }
+            Backend::Hyprland(dir) => {
+                let workspaces = hyprland_request(dir, "j/workspaces")?;
+                let monitors = hyprland_request(dir, "j/monitors")?;
+                let clients = hyprland_request(dir, "j/clients")?;
+                let active_window = hyprland_request(dir, "j/activewindow")?;
+
+                let shown = |id: i64| {
+                    array(&monitors)
+                        .find(|monitor| monitor["activeWorkspace"]["id"].as_i64() == Some(id))
+                };
+                let mut workspaces = array(&workspaces)
+                    .map(|workspace| {
+                        let id = workspace["id"].as_i64().unwrap_or_default();
+                        let monitor = shown(id);
+                        Workspace {
+                            id,
+                            name: string(&workspace["name"]),
+                            output: string(&workspace["monitor"]),
+                            focused: monitor
+                                .is_some_and(|monitor| monitor["focused"].as_bool() == Some(true)),
+                            visible: monitor.is_some(),
+                            urgent: false,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                workspaces.sort_by_key(|workspace| workspace.id);
+
+                Ok(Snapshot {
+                    workspaces,
+                    windows: array(&clients)
+                        .map(|client| IpcWindow {
+                            id: string(&client["address"]),
+                            title: string(&client["title"]),
+                            app_id: string(&client["class"]),
+                            workspace: string(&client["workspace"]["name"]),
+                            focused: client["address"] == active_window["address"],
+                        })
+                        .collect(),
+                    mode: String::new(),
+                })
+            }
+        }
+    }
+
+    /// Blocks until the compositor reports a change, calling `changed` with the new binding
+    /// mode where the event carries one. Returns when the connection is lost.
+    fn watch(&self, mut changed: impl FnMut(Option<String>) -> bool) -> std::io::Result<()> {
+        match self {
+            Backend::Sway(socket) => {
+                let mut stream = UnixStream::connect(socket)?;
+                sway_request(
+                    &mut stream,
+                    SWAY_SUBSCRIBE,
+                    &json!(["workspace", "window", "mode"]).to_string(),
+                )?;
+                loop {
+                    let (_, payload) = sway_read(&mut stream)?;
+                    let mode = payload["change"]
+                        .as_str()
+                        .filter(|_| payload.get("pango_markup").is_some())
+                        .map(str::to_string);
+                    if !changed(mode) {
+                        return Ok(());
+                    }
+                }
+            }
+            Backend::Hyprland(dir) => {
+                let stream = UnixStream::connect(dir.join(".socket2.sock"))?;
+                for line in BufReader::new(stream).lines() {
+                    let line = line?;
+                    let mode = line.strip_prefix("submap>>").map(str::to_string);
+                    if !changed(mode) {
+                        return Ok(());
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn command(&self, command: &str) -> std::io::Result<()> {
+        match self {
+            Backend::Sway(socket) => {
+                let mut stream = UnixStream::connect(socket)?;
+                sway_command_result(&sway_request(&mut stream, SWAY_RUN_COMMAND, command)?)
+            }
+            Backend::Hyprland(dir) => hyprland_command(dir, command).map(|_| ()),
+        }
+    }
+}
+
+/// Live workspace, window and binding mode data of Sway or Hyprland.
+///
+/// ```ignore
+/// let ipc = CompositorIpc::connect(&platform)?;
+/// ui.set_workspaces(ipc.workspaces_model(|ws| WorkspaceData {
+///     name: ws.name.as_str().into(),
+///     focused: ws.focused,
+/// }));
+/// ui.on_workspace_clicked(move |name| { let _ = ipc.switch_workspace(&name); });
+/// ```
+pub struct CompositorIpc {
+    backend: Backend,
+    workspaces: Rc<VecModel<Workspace>>,
+    windows: Rc<VecModel<IpcWindow>>,
+    mode: RefCell<String>,
+    on_mode_changed: RefCell<Option<Box<dyn Fn(&str)>>>,
+}
+
+impl CompositorIpc {
+    /// Connects to the compositor named by `SWAYSOCK` or `HYPRLAND_INSTANCE_SIGNATURE`.
+    pub fn connect(platform: &SlintLayerShell) -> Result<Rc<Self>, PlatformError> {
+        let backend = Backend::detect().ok_or_else(|| {
+            PlatformError::Other("neither Sway nor Hyprland IPC is available".into())
+        })?;
+        let snapshot = backend
+            .snapshot()
+            .map_err(|e| PlatformError::Other(format!("compositor IPC failed: {e}")))?;
+
+        let this = Rc::new(Self {
+            backend: backend.clone(),
+            workspaces: Rc::new(VecModel::from(snapshot.workspaces)),
+            windows: Rc::new(VecModel::from(snapshot.windows)),
+            mode: RefCell::new(snapshot.mode),
+            on_mode_changed: RefCell::default(),
+        });
+
+        let (sender, channel) = calloop::channel::channel::<Snapshot>();
+        platform.channel_to_event_loop(channel, &this, "compositor IPC", |this, snapshot| {
+            this.apply(snapshot);
+        })?;
+
+        std::thread::spawn(move || {
+            let mut mode = String::new();
+            let result = backend.watch(|new_mode| {
+                if let Some(new_mode) = new_mode {
+                    mode = if new_mode == "default" {
+                        String::new()
+                    } else {
+                        new_mode
+                    };
+                }
+                match backend.snapshot() {
+                    Ok(mut snapshot) => {
+                        // Hyprland can only report the submap through events.
+                        if matches!(backend, Backend::Hyprland(_)) {
+                            snapshot.mode = mode.clone();
+                        }
+                        sender.send(snapshot).is_ok()
+                    }
+                    Err(err) => {
+                        log::warn!("compositor IPC query failed: {err}");
+                        true
+                    }
+                }
+            });
+            if let Err(err) = result {
+                log::warn!("lost the compositor IPC connection: {err}");
+            }
+        });
+
+        Ok(this)
+    }
+
+    pub fn workspaces(&self) -> Vec<Workspace> {
+        self.workspaces.iter().collect()
+    }
+
+    pub fn windows(&self) -> Vec<IpcWindow> {
+        self.windows.iter().collect()
+    }
+
+    /// Returns the active binding mode (Sway) or submap (Hyprland), empty for the default.
+    pub fn mode(&self) -> String {
+        self.mode.borrow().clone()
+    }
+
+    pub fn on_mode_changed(&self, callback: impl Fn(&str) + 'static) {
+        *self.on_mode_changed.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Returns a model of the workspaces, sorted as the compositor lists them.
+    pub fn workspaces_model<T: Clone + 'static>(
+        &self,
+        map: impl Fn(&Workspace) -> T + 'static,
+    ) -> ModelRc<T> {
+        ModelRc::new(
+            self.workspaces
+                .clone()
+                .map(move |workspace| map(&workspace)),
+        )
+    }
+
+    pub fn windows_model<T: Clone + 'static>(
+        &self,
+        map: impl Fn(&IpcWindow) -> T + 'static,
+    ) -> ModelRc<T> {
+        ModelRc::new(self.windows.clone().map(move |window| map(&window)))
+    }
+
+    pub fn switch_workspace(&self, name: &str) -> Result<(), PlatformError> {
+        let command = match self.backend {
+            Backend::Sway(_) => format!("workspace {}", json!(name)),
+            Backend::Hyprland(_) => format!("dispatch workspace name:{name}"),
+        };
+        self.command(&command)
+    }
+
+    /// Runs a raw compositor command, e.g. `"focus left"` on Sway or `"dispatch killactive"`
+    /// on Hyprland.
+    pub fn command(&self, command: &str) -> Result<(), PlatformError> {
+        self.backend
+            .command(command)
+            .map_err(|e| PlatformError::Other(format!("compositor command failed: {e}")))
+    }
+
+    fn apply(&self, snapshot: Snapshot) {
+        if self
+            .workspaces
+            .iter()
+            .ne(snapshot.workspaces.iter().cloned())
+        {
+            self.workspaces.set_vec(snapshot.workspaces);
+        }
+        if self.windows.iter().ne(snapshot.windows.iter().cloned()) {
+            self.windows.set_vec(snapshot.windows);
+        }
+        if *self.mode.borrow() != snapshot.mode {
+            *self.mode.borrow_mut() = snapshot.mode.clone();
+            if let Some(callback) = self.on_mode_changed.borrow().as_ref() {
+                callback(&snapshot.mode);
+            }
+        }
+    }
+}
+
+fn sway_request(
+    stream: &mut (impl Read + Write),
+    kind: u32,
+    payload: &str,
+) -> std::io::Result<Value> {
+    stream.write_all(&sway_message(kind, payload))?;
+    sway_read(stream).map(|(_, reply)| reply)
+}
+
+fn sway_message(kind: u32, payload: &str) -> Vec<u8> {
+    let mut message = SWAY_MAGIC.to_vec();
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    message
+}
+
+fn sway_read(stream: &mut impl Read) -> std::io::Result<(u32, Value)> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != SWAY_MAGIC {
+        return Err(std::io::Error::other("invalid i3-ipc reply"));
+    }
+    let len = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+    let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok((kind, serde_json::from_slice(&payload)?))
+}
+
+/// Turns the reply to `RUN_COMMAND`, which has one result per command, into the first error.
+fn sway_command_result(reply: &Value) -> std::io::Result<()> {
+    match array(reply).find(|result| result["success"] == false) {
+        Some(failed) => Err(std::io::Error::other(
+            failed["error"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn collect_sway_windows(node: &Value, workspace: &str, windows: &mut Vec<IpcWindow>) {
+    let workspace = if node["type"] == "workspace" {
+        node["name"].as_str().unwrap_or(workspace)
+    } else {
+        workspace
+    };
+    let app_id = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str());
+    if let Some(app_id) = app_id.filter(|_| node["pid"].is_number()) {
+        windows.push(IpcWindow {
+            id: node["id"].to_string(),
+            title: string(&node["name"]),
+            app_id: app_id.to_string(),
+            workspace: workspace.to_string(),
+            focused: node["focused"].as_bool().unwrap_or(false),
+        });
+    }
+    for child in array(&node["nodes"]).chain(array(&node["floating_nodes"])) {
+        collect_sway_windows(child, workspace, windows);
+    }
+}
+
+fn hyprland_command(dir: &std::path::Path, command: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(dir.join(".socket.sock"))?;
+    stream.write_all(command.as_bytes())?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+fn hyprland_request(dir: &std::path::Path, command: &str) -> std::io::Result<Value> {
+    Ok(serde_json::from_str(&hyprland_command(dir, command)?)?)
+}
+
+fn array(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sway_read_splits_framed_messages() {
+        let mut bytes = sway_message(0x8000_0004, r#"{"change":"resize"}"#);
+        bytes.extend(sway_message(SWAY_RUN_COMMAND, r#"[{"success":true}]"#));
+        let mut stream = bytes.as_slice();
+
+        let (kind, payload) = sway_read(&mut stream).unwrap();
+        assert_eq!(kind, 0x8000_0004);
+        assert_eq!(payload["change"], "resize");
+        let (kind, payload) = sway_read(&mut stream).unwrap();
+        assert_eq!(kind, SWAY_RUN_COMMAND);
+        assert_eq!(payload[0]["success"], true);
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn sway_read_rejects_invalid_and_truncated_messages() {
+        let mut invalid = sway_message(SWAY_RUN_COMMAND, "[]");
+        invalid[0] = b'x';
+        assert!(sway_read(&mut invalid.as_slice()).is_err());
+
+        let message = sway_message(SWAY_RUN_COMMAND, r#"[{"success":true}]"#);
+        assert!(sway_read(&mut &message[..message.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn failed_sway_commands_are_errors() {
+        let reply = json!([
+            { "success": true },
+            { "success": false, "parse_error": true, "error": "Unknown/invalid command 'fcous'" },
+        ]);
+        let err = sway_command_result(&reply).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown/invalid command 'fcous'");
+        assert!(sway_command_result(&json!([{ "success": true }])).is_ok());
+    }
+}
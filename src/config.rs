@@ -0,0 +1,425 @@
+//! Window definitions loaded from configuration files, like the bar definitions of waybar.
+//!
+//! The structs only derive [`serde::Deserialize`], so any serde format can be used:
+//!
+//! ```toml
+//! [[windows]]
+//! name = "top-bar"
+//! component = "Bar"
+//! anchor = ["top", "left", "right"]
+//! auto-exclusive-zone = true
+//! output = "*"
+//! ```
+
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::output::{OutputInfo, PrimaryOutput};
+use crate::per_output::PerOutputWindows;
+use crate::platform::{SlintLayerShell, defer};
+use serde::Deserialize;
+use slint::{ComponentHandle, PlatformError};
+use std::rc::Rc;
+
+/// Output value placing a window on every output.
+pub const ALL_OUTPUTS: &str = "*";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LayerShellConfig {
+    pub windows: Vec<WindowDef>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayerDef {
+    Background,
+    Bottom,
+    #[default]
+    Top,
+    Overlay,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeDef {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyboardDef {
+    #[default]
+    None,
+    Exclusive,
+    OnDemand,
+}
+
+/// A window to create at startup.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WindowDef {
+    /// Identifies the window among the others of the configuration.
+    pub name: String,
+    /// Name of the Slint component to instantiate, interpreted by the factory.
+    pub component: String,
+    pub layer: LayerDef,
+    pub anchor: Vec<EdgeDef>,
+    /// Margins in logical pixels, in `[top, right, bottom, left]` order.
+    pub margins: [i32; 4],
+    /// Size in logical pixels, the preferred size of the component when not set.
+    pub size: Option<[u32; 2]>,
+    pub exclusive_zone: i32,
+    pub auto_exclusive_zone: bool,
+    pub keyboard_interactivity: KeyboardDef,
+    pub pass_through_input: bool,
+    pub click_through_transparent: bool,
+    pub namespace: Option<String>,
+    /// Connector name of the output such as `DP-1`, its index in the order the compositor
+    /// announced the outputs, or [`ALL_OUTPUTS`] for one window per output. The compositor
+    /// picks the output when not set.
+    pub output: Option<String>,
+}
+
+impl WindowDef {
+    pub fn layer_config(&self) -> LayerConfig {
+        LayerConfig {
+            layer: match self.layer {
+                LayerDef::Background => Layer::Background,
+                LayerDef::Bottom => Layer::Bottom,
+                LayerDef::Top => Layer::Top,
+                LayerDef::Overlay => Layer::Overlay,
+            },
+            anchor: self.anchor.iter().fold(Anchor::empty(), |anchor, edge| {
+                anchor
+                    | match edge {
+                        EdgeDef::Top => Anchor::TOP,
+                        EdgeDef::Bottom => Anchor::BOTTOM,
+                        EdgeDef::Left => Anchor::LEFT,
+                        EdgeDef::Right => Anchor::RIGHT,
+                    }
+            }),
+            size: self.size.map(|[width, height]| (width, height)),
+            margins: self.margins.into(),
+            exclusive_zone: self.exclusive_zone,
+            auto_exclusive_zone: self.auto_exclusive_zone,
+            keyboard_interactivity: match self.keyboard_interactivity {
+                KeyboardDef::None => KeyboardInteractivity::None,
+                KeyboardDef::Exclusive => KeyboardInteractivity::Exclusive,
+                KeyboardDef::OnDemand => KeyboardInteractivity::OnDemand,
+            },
+            pass_through_input: self.pass_through_input,
+            click_through_transparent: self.click_through_transparent,
+            namespace: self.namespace.clone().unwrap_or_else(|| self.name.clone()),
+            output: None,
+        }
+    }
+
+    pub fn is_per_output(&self) -> bool {
+        self.output.as_deref() == Some(ALL_OUTPUTS)
+    }
+}
+
+/// Creates the component of a window definition, on the given output if the definition
+/// names one.
+pub type WindowFactory<C> = Rc<dyn Fn(&WindowDef, Option<&OutputInfo>) -> Result<C, PlatformError>>;
+
+/// The components created for a window definition.
+pub enum ConfiguredWindow<C: ComponentHandle + 'static> {
+    /// A window on the output picked by the compositor.
+    Single(C),
+    /// The windows of a definition with an output, following output hotplug: one per output
+    /// for [`ALL_OUTPUTS`], else at most one on the named output while it is connected.
+    PerOutput(Rc<PerOutputWindows<C>>),
+}
+
+/// The windows created by [`LayerShellConfig::instantiate`].
+pub struct ConfiguredWindows<C: ComponentHandle + 'static> {
+    pub windows: Vec<(WindowDef, ConfiguredWindow<C>)>,
+}
+
+impl<C: ComponentHandle + 'static> ConfiguredWindows<C> {
+    pub fn get(&self, name: &str) -> Option<&ConfiguredWindow<C>> {
+        self.windows
+            .iter()
+            .find(|(def, _)| def.name == name)
+            .map(|(_, window)| window)
+    }
+}
+
+impl LayerShellConfig {
+    /// Creates and shows all windows of the configuration.
+    ///
+    /// To instantiate components by name, the factory can match on [`WindowDef::component`]
+    /// or use `slint-interpreter`, whose `ComponentInstance` is a [`ComponentHandle`] too.
+    pub fn instantiate<C: ComponentHandle + 'static>(
+        &self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&WindowDef, Option<&OutputInfo>) -> Result<C, PlatformError> + 'static,
+    ) -> Result<ConfiguredWindows<C>, PlatformError> {
+        let factory: WindowFactory<C> = Rc::new(factory);
+        let windows = self
+            .windows
+            .iter()
+            .map(|def| Ok((def.clone(), instantiate_window(platform, def, &factory)?)))
+            .collect::<Result<Vec<_>, PlatformError>>()?;
+        Ok(ConfiguredWindows { windows })
+    }
+}
+
+pub(crate) fn instantiate_window<C: ComponentHandle + 'static>(
+    platform: &SlintLayerShell,
+    def: &WindowDef,
+    factory: &WindowFactory<C>,
+) -> Result<ConfiguredWindow<C>, PlatformError> {
+    let config = def.layer_config();
+    let output = match def.output.as_deref() {
+        Some(ALL_OUTPUTS) => None,
+        Some(name) => Some(PrimaryOutput::parse(name)),
+        None => {
+            let component = platform.with_layer_config(config, || factory(def, None))?;
+            component.show()?;
+            return Ok(ConfiguredWindow::Single(component));
+        }
+    };
+
+    let factory = factory.clone();
+    let def_clone = def.clone();
+    let factory = move |info: &OutputInfo| factory(&def_clone, Some(info));
+    let windows = match output {
+        Some(output) => {
+            if platform.find_output(&output).is_none() {
+                log::info!(
+                    "output {output:?} of window {} is not connected, waiting for it",
+                    def.name
+                );
+            }
+            PerOutputWindows::on_output(platform, output, config, factory)?
+        }
+        None => PerOutputWindows::new(platform, config, factory)?,
+    };
+    Ok(ConfiguredWindow::PerOutput(windows))
+}
+
+/// Parses the contents of a configuration file.
+#[cfg(feature = "config-reload")]
+pub type ConfigParser = Box<dyn Fn(&str) -> Result<LayerShellConfig, String>>;
+
+/// Windows defined by a configuration file, updated whenever the file changes.
+///
+/// Windows are matched by [`WindowDef::name`]: new definitions are instantiated, removed ones
+/// are closed, and changed ones are updated in place when only their layer surface properties
+/// changed, or re-created otherwise.
+///
+/// ```ignore
+/// let config = LiveConfig::watch(
+///     &platform,
+///     "~/.config/bar/config.toml",
+///     |text| toml::from_str(text).map_err(|e| e.to_string()),
+///     |def, _output| Bar::new(),
+/// )?;
+/// ```
+#[cfg(feature = "config-reload")]
+pub struct LiveConfig<C: ComponentHandle + 'static> {
+    platform: SlintLayerShell,
+    path: std::path::PathBuf,
+    parse: ConfigParser,
+    factory: WindowFactory<C>,
+    windows: std::cell::RefCell<ConfiguredWindows<C>>,
+}
+
+#[cfg(feature = "config-reload")]
+impl<C: ComponentHandle + 'static> LiveConfig<C> {
+    /// Loads the file at `path`, instantiates its windows and watches it with inotify.
+    pub fn watch(
+        platform: &SlintLayerShell,
+        path: impl Into<std::path::PathBuf>,
+        parse: impl Fn(&str) -> Result<LayerShellConfig, String> + 'static,
+        factory: impl Fn(&WindowDef, Option<&OutputInfo>) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<Self>, PlatformError> {
+        use calloop::generic::Generic;
+        use calloop::{Interest, Mode, PostAction};
+        use inotify::{Inotify, WatchMask};
+        use std::os::fd::AsFd;
+
+        let path = path.into();
+        let parse: ConfigParser = Box::new(parse);
+        let config = read_config(&path, &parse)?;
+        let factory: WindowFactory<C> = Rc::new(factory);
+        let windows = config
+            .windows
+            .iter()
+            .map(|def| Ok((def.clone(), instantiate_window(platform, def, &factory)?)))
+            .collect::<Result<Vec<_>, PlatformError>>()?;
+
+        let this = Rc::new(Self {
+            platform: platform.clone(),
+            path: path.clone(),
+            parse,
+            factory,
+            windows: std::cell::RefCell::new(ConfiguredWindows { windows }),
+        });
+
+        // Watch the directory, as editors often replace the file instead of writing to it.
+        let inotify_error = |e: std::io::Error| {
+            PlatformError::Other(format!("failed to watch {}: {e}", path.display()))
+        };
+        let mut inotify = Inotify::init().map_err(inotify_error)?;
+        let directory = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        inotify
+            .watches()
+            .add(
+                directory,
+                WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+            )
+            .map_err(inotify_error)?;
+        let fd = inotify
+            .as_fd()
+            .try_clone_to_owned()
+            .map_err(inotify_error)?;
+
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        let weak = Rc::downgrade(&this);
+        let mut buffer = [0; 4096];
+        platform
+            .loop_handle()
+            .insert_source(
+                Generic::new(fd, Interest::READ, Mode::Level),
+                move |_, _, _| {
+                    let Ok(events) = inotify.read_events(&mut buffer) else {
+                        return Ok(PostAction::Continue);
+                    };
+                    let changed = events
+                        .into_iter()
+                        .any(|event| event.name.map(|name| name.to_os_string()) == file_name);
+                    if changed {
+                        defer(&weak, |this| {
+                            if let Err(err) = this.reload() {
+                                log::warn!("failed to reload {}: {err}", this.path.display());
+                            }
+                        });
+                    }
+                    Ok(PostAction::Continue)
+                },
+            )
+            .map_err(|e| PlatformError::Other(format!("failed to watch the config: {e}")))?;
+
+        Ok(this)
+    }
+
+    /// Reads the file again and applies the changes.
+    pub fn reload(&self) -> Result<(), PlatformError> {
+        let config = read_config(&self.path, &self.parse)?;
+        self.apply(&config)
+    }
+
+    /// Applies `config`, as if the file had changed to it.
+    ///
+    /// On failure, the windows whose definitions were not applied yet are kept as they were,
+    /// so that the next successful reload picks up from there.
+    pub fn apply(&self, config: &LayerShellConfig) -> Result<(), PlatformError> {
+        // Invalid layer configurations are rejected before any window is touched.
+        for def in &config.windows {
+            def.layer_config().validate().map_err(|err| {
+                PlatformError::Other(format!("invalid window {}: {err}", def.name))
+            })?;
+        }
+
+        let previous = std::mem::take(&mut self.windows.borrow_mut().windows);
+        let mut previous = previous.into_iter().map(Some).collect::<Vec<_>>();
+
+        let mut windows = Vec::new();
+        let result = config.windows.iter().try_for_each(|def| {
+            let existing = previous
+                .iter_mut()
+                .find(|entry| entry.as_ref().is_some_and(|(old, _)| old.name == def.name))
+                .and_then(Option::take);
+            let window = match existing {
+                Some((old, window)) if old == *def => window,
+                Some((old, window)) if updatable_in_place(&old, def) => {
+                    let config = def.layer_config();
+                    let updated = match &window {
+                        ConfiguredWindow::Single(component) => {
+                            self.platform.set_layer_config(component.window(), config)
+                        }
+                        ConfiguredWindow::PerOutput(per_output) => {
+                            per_output.set_layer_config(config)
+                        }
+                    };
+                    if let Err(err) = updated {
+                        windows.push((old, window));
+                        return Err(err);
+                    }
+                    window
+                }
+                // The new window is created before the old one is closed, which is kept if
+                // that fails.
+                Some((old, window)) => match instantiate_window(&self.platform, def, &self.factory)
+                {
+                    Ok(new_window) => {
+                        close(window);
+                        new_window
+                    }
+                    Err(err) => {
+                        windows.push((old, window));
+                        return Err(err);
+                    }
+                },
+                None => instantiate_window(&self.platform, def, &self.factory)?,
+            };
+            windows.push((def.clone(), window));
+            Ok(())
+        });
+
+        let remaining = previous.into_iter().flatten();
+        if result.is_ok() {
+            for (_, window) in remaining {
+                close(window);
+            }
+        } else {
+            windows.extend(remaining);
+        }
+        self.windows.borrow_mut().windows = windows;
+        result
+    }
+
+    pub fn windows(&self) -> std::cell::Ref<'_, ConfiguredWindows<C>> {
+        self.windows.borrow()
+    }
+}
+
+#[cfg(feature = "config-reload")]
+fn read_config(
+    path: &std::path::Path,
+    parse: &ConfigParser,
+) -> Result<LayerShellConfig, PlatformError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| PlatformError::Other(format!("failed to read {}: {e}", path.display())))?;
+    parse(&text).map_err(|e| PlatformError::Other(format!("invalid {}: {e}", path.display())))
+}
+
+/// Whether a window can go from `old` to `new` by updating its layer surface.
+#[cfg(feature = "config-reload")]
+fn updatable_in_place(old: &WindowDef, new: &WindowDef) -> bool {
+    old.component == new.component
+        && old.output == new.output
+        && old.layer_config().namespace == new.layer_config().namespace
+}
+
+#[cfg(feature = "config-reload")]
+fn close<C: ComponentHandle + 'static>(window: ConfiguredWindow<C>) {
+    match window {
+        ConfiguredWindow::Single(component) => {
+            let _ = component.hide();
+        }
+        ConfiguredWindow::PerOutput(windows) => {
+            for (_, component) in windows.components() {
+                let _ = component.hide();
+            }
+        }
+    }
+}
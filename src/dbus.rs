@@ -0,0 +1,11 @@
+//! Helpers shared by the D-Bus integrations.
+
+use zbus::zvariant::Value;
+
+/// Strips the variant wrappers around a value, which D-Bus services nest freely.
+pub(crate) fn unwrap_variant<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(inner) => unwrap_variant(inner),
+        value => value,
+    }
+}
@@ -1,10 +1,16 @@
-use crate::platform::LayerShellState;
+use crate::event::{BackendEvent, RawSurfaceEvent, VisibilityChange, log_dropped};
+use crate::layer::{Anchor, OutputRemovalPolicy};
+use crate::layer_shell::{LayerSurface, LayerSurfaceConfigure};
+use crate::platform::{LayerShellState, SharedState};
+use crate::window_adapter::LayerShellWindowAdapter;
 use i_slint_core::SharedString;
-use i_slint_core::api::{LogicalPosition, PhysicalSize};
+use i_slint_core::api::{LogicalPosition, LogicalSize, PhysicalSize};
 use i_slint_core::input::PointerEventButton;
 use i_slint_core::platform::WindowEvent;
+use slint::platform::Key;
 use smithay_client_toolkit::compositor::CompositorHandler;
 use smithay_client_toolkit::output::{OutputHandler, OutputState};
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
 use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryHandler, RegistryState};
 use smithay_client_toolkit::seat::keyboard::{
     KeyEvent, KeyboardHandler, Keysym, Modifiers, RawModifiers,
@@ -12,18 +18,26 @@ use smithay_client_toolkit::seat::keyboard::{
 use smithay_client_toolkit::seat::pointer::{
     BTN_LEFT, BTN_MIDDLE, BTN_RIGHT, PointerEvent, PointerEventKind, PointerHandler,
 };
+#[cfg(feature = "touch")]
 use smithay_client_toolkit::seat::touch::TouchHandler;
 use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
+#[cfg(feature = "session-lock")]
+use smithay_client_toolkit::session_lock::{
+    SessionLock, SessionLockHandler, SessionLockSurface, SessionLockSurfaceConfigure,
+};
 use smithay_client_toolkit::shell::WaylandSurface;
 use smithay_client_toolkit::shell::xdg::window::{Window, WindowConfigure, WindowHandler};
 use smithay_client_toolkit::{
-    delegate_compositor, delegate_keyboard, delegate_output, delegate_pointer, delegate_registry,
-    delegate_seat, delegate_touch, delegate_xdg_shell, delegate_xdg_window,
+    delegate_compositor, delegate_keyboard, delegate_output, delegate_pointer,
+    delegate_registry, delegate_seat, delegate_xdg_shell, delegate_xdg_window,
 };
+use wayland_client::backend::ObjectId;
 use wayland_client::protocol::wl_output::WlOutput;
 use wayland_client::protocol::wl_seat::WlSeat;
 use wayland_client::protocol::wl_surface::WlSurface;
-use wayland_client::protocol::{wl_keyboard, wl_pointer, wl_touch};
+#[cfg(feature = "touch")]
+use wayland_client::protocol::wl_touch;
+use wayland_client::protocol::{wl_keyboard, wl_pointer};
 use wayland_client::{Connection, Proxy, QueueHandle};
 
 impl ProvidesRegistryState for LayerShellState {
@@ -77,18 +91,52 @@ impl CompositorHandler for LayerShellState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _new_factor: i32,
+        surface: &WlSurface,
+        new_factor: i32,
     ) {
+        let id = surface.id();
+        let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
+            return;
+        };
+        let Some(window_adapter) = window_adapter_weak.upgrade() else {
+            self.window_adapters.remove(&id);
+            return;
+        };
+        // sctk reports `wl_surface.preferred_buffer_scale` from version 6 on, and the largest
+        // scale of the entered outputs before, following their scale changes itself.
+        if surface.version() >= 6 {
+            window_adapter.preferred_buffer_scale.set(Some(new_factor));
+        }
+        self.apply_scale_factor(&window_adapter, new_factor);
     }
 
     fn transform_changed(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _new_transform: wayland_client::protocol::wl_output::Transform,
+        surface: &WlSurface,
+        new_transform: wayland_client::protocol::wl_output::Transform,
     ) {
+        let id = surface.id();
+        let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
+            return;
+        };
+        let Some(window_adapter) = window_adapter_weak.upgrade() else {
+            self.window_adapters.remove(&id);
+            return;
+        };
+        window_adapter.observe_raw_event(RawSurfaceEvent::TransformChanged {
+            transform: new_transform,
+        });
+        if window_adapter.transform.replace(new_transform) == new_transform {
+            return;
+        }
+
+        // The renderer draws upright content, so the buffer transform stays `Normal` and the
+        // compositor rotates the surface. The output's logical size changed though, so layer
+        // surfaces sized by their anchors need a new configure.
+        window_adapter.reconfigure();
+        window_adapter.pending_redraw.set(true);
     }
 
     fn frame(
@@ -96,11 +144,12 @@ impl CompositorHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         surface: &WlSurface,
-        _time: u32,
+        time: u32,
     ) {
         let id = surface.id();
         if let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() {
             if let Some(window_adapter) = window_adapter_weak.upgrade() {
+                window_adapter.observe_raw_event(RawSurfaceEvent::Frame { time });
                 window_adapter.frame_callback_pending.set(false);
                 return;
             }
@@ -123,16 +172,14 @@ impl CompositorHandler for LayerShellState {
             self.window_adapters.remove(&id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::Enter { output });
 
-        if let Some(output_info) = self.output_state.info(output) {
-            let scale = output_info.scale_factor.max(1) as f32;
-            let _ = window_adapter
-                .window
-                .try_dispatch_event(WindowEvent::ScaleFactorChanged {
-                    scale_factor: scale,
-                });
-            window_adapter.pending_redraw.set(true);
-        }
+        window_adapter
+            .entered_outputs
+            .borrow_mut()
+            .push(output.clone());
+        self.update_effective_scale(&window_adapter);
+        window_adapter.notify_visibility(VisibilityChange::EnteredOutput(output.clone()));
     }
 
     fn surface_leave(
@@ -140,7 +187,7 @@ impl CompositorHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         surface: &WlSurface,
-        _output: &WlOutput,
+        output: &WlOutput,
     ) {
         let id = surface.id();
         let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
@@ -150,7 +197,17 @@ impl CompositorHandler for LayerShellState {
             self.window_adapters.remove(&id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::Leave { output });
+
+        window_adapter
+            .entered_outputs
+            .borrow_mut()
+            .retain(|entered| entered != output);
+        self.update_effective_scale(&window_adapter);
         window_adapter.pending_redraw.set(true);
+        if window_adapter.entered_outputs.borrow().is_empty() {
+            window_adapter.notify_visibility(VisibilityChange::LeftAllOutputs);
+        }
     }
 }
 
@@ -159,11 +216,40 @@ impl OutputHandler for LayerShellState {
         &mut self.output_state
     }
 
-    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        self.shared.borrow_mut().outputs = self.outputs();
+        self.backend_events
+            .push_back(BackendEvent::OutputAdded { output });
+    }
+
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        self.shared.borrow_mut().outputs = self.outputs();
+        // The scale of an output can change at runtime, each window on it follows on its own.
+        let affected = self
+            .window_adapters
+            .values()
+            .filter_map(|window_adapter| window_adapter.upgrade())
+            .filter(|window_adapter| window_adapter.entered_outputs.borrow().contains(&output))
+            .collect::<Vec<_>>();
+        for window_adapter in affected {
+            self.update_effective_scale(&window_adapter);
+        }
+
+        self.backend_events
+            .push_back(BackendEvent::OutputUpdated { output });
+    }
 
-    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+    fn output_destroyed(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, output: WlOutput) {
+        self.shared
+            .borrow_mut()
+            .outputs
+            .retain(|info| info.output != output);
+        if self.output_removal_policy == OutputRemovalPolicy::Migrate {
+            self.migrate_surfaces_from(qh, &output);
+        }
 
-    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {
+        self.backend_events
+            .push_back(BackendEvent::OutputRemoved { output });
     }
 }
 
@@ -172,7 +258,13 @@ impl SeatHandler for LayerShellState {
         &mut self.seat_state
     }
 
-    fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: WlSeat) {}
+    fn new_seat(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, seat: WlSeat) {
+        #[cfg(feature = "clipboard")]
+        self.create_data_device(&seat, qh);
+        self.shared.borrow_mut().seats.push(seat.clone());
+        self.backend_events
+            .push_back(BackendEvent::SeatAdded { seat });
+    }
 
     fn new_capability(
         &mut self,
@@ -181,22 +273,36 @@ impl SeatHandler for LayerShellState {
         seat: WlSeat,
         capability: Capability,
     ) {
+        self.backend_events
+            .push_back(BackendEvent::SeatCapabilityAdded {
+                seat: seat.clone(),
+                capability,
+            });
         if capability == Capability::Keyboard && self.keyboard.is_none() {
             match self.seat_state.get_keyboard(qh, &seat, None) {
                 Ok(keyboard) => self.keyboard = Some(keyboard),
-                Err(err) => eprintln!("failed to create keyboard: {err}"),
+                Err(err) => log::warn!("failed to create keyboard: {err}"),
             }
+            #[cfg(feature = "text-input")]
+            self.create_text_input(&seat, qh);
         }
         if capability == Capability::Pointer && self.pointer.is_none() {
             match self.seat_state.get_pointer(qh, &seat) {
-                Ok(pointer) => self.pointer = Some(pointer),
-                Err(err) => eprintln!("failed to create pointer: {err}"),
+                Ok(pointer) => {
+                    self.shared.borrow_mut().cursor_shape_device = self
+                        .cursor_shape_manager
+                        .as_ref()
+                        .map(|manager| manager.get_shape_device(&pointer, qh));
+                    self.pointer = Some(pointer);
+                }
+                Err(err) => log::warn!("failed to create pointer: {err}"),
             }
         }
+        #[cfg(feature = "touch")]
         if capability == Capability::Touch && self.touch.is_none() {
             match self.seat_state.get_touch(qh, &seat) {
                 Ok(touch) => self.touch = Some(touch),
-                Err(err) => eprintln!("failed to create touch: {err}"),
+                Err(err) => log::warn!("failed to create touch: {err}"),
             }
         }
     }
@@ -205,9 +311,11 @@ impl SeatHandler for LayerShellState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _seat: WlSeat,
+        seat: WlSeat,
         capability: Capability,
     ) {
+        self.backend_events
+            .push_back(BackendEvent::SeatCapabilityRemoved { seat, capability });
         if capability == Capability::Keyboard {
             if let Some(keyboard) = self.keyboard.take() {
                 keyboard.release();
@@ -215,10 +323,17 @@ impl SeatHandler for LayerShellState {
             self.keyboard_focus_surface = None;
         }
         if capability == Capability::Pointer {
+            let mut shared = self.shared.borrow_mut();
+            if let Some(device) = shared.cursor_shape_device.take() {
+                device.destroy();
+            }
             if let Some(pointer) = self.pointer.take() {
                 pointer.release();
             }
+            shared.pointer_enter = None;
+            shared.cursor_hidden = false;
         }
+        #[cfg(feature = "touch")]
         if capability == Capability::Touch {
             if let Some(touch) = self.touch.take() {
                 touch.release();
@@ -227,7 +342,14 @@ impl SeatHandler for LayerShellState {
         }
     }
 
-    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: WlSeat) {}
+    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, seat: WlSeat) {
+        self.shared
+            .borrow_mut()
+            .seats
+            .retain(|known| known != &seat);
+        self.backend_events
+            .push_back(BackendEvent::SeatRemoved { seat });
+    }
 }
 
 impl KeyboardHandler for LayerShellState {
@@ -245,9 +367,8 @@ impl KeyboardHandler for LayerShellState {
         self.keyboard_focus_surface = Some(id.clone());
         if let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() {
             if let Some(window_adapter) = window_adapter_weak.upgrade() {
-                let _ = window_adapter
-                    .window
-                    .try_dispatch_event(WindowEvent::WindowActiveChanged(true));
+                window_adapter.observe_raw_event(RawSurfaceEvent::KeyboardEnter);
+                window_adapter.forward_event(WindowEvent::WindowActiveChanged(true));
                 window_adapter.pending_redraw.set(true);
             } else {
                 self.window_adapters.remove(&id);
@@ -267,9 +388,8 @@ impl KeyboardHandler for LayerShellState {
         self.keyboard_focus_surface = None;
         if let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() {
             if let Some(window_adapter) = window_adapter_weak.upgrade() {
-                let _ = window_adapter
-                    .window
-                    .try_dispatch_event(WindowEvent::WindowActiveChanged(false));
+                window_adapter.observe_raw_event(RawSurfaceEvent::KeyboardLeave);
+                window_adapter.forward_event(WindowEvent::WindowActiveChanged(false));
                 window_adapter.pending_redraw.set(true);
             } else {
                 self.window_adapters.remove(&id);
@@ -282,9 +402,13 @@ impl KeyboardHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         _keyboard: &wl_keyboard::WlKeyboard,
-        _serial: u32,
+        serial: u32,
         event: KeyEvent,
     ) {
+        #[cfg(feature = "clipboard")]
+        {
+            self.clipboard.borrow_mut().last_serial = serial;
+        }
         if let Some((window_adapter, text)) = self
             .keyboard_focus_surface
             .clone()
@@ -296,10 +420,15 @@ impl KeyboardHandler for LayerShellState {
             })
             .and_then(|window_adapter| key_event_text(&event).map(|text| (window_adapter, text)))
         {
-            let _ = window_adapter
-                .window
-                .try_dispatch_event(WindowEvent::KeyPressed { text });
+            window_adapter.observe_raw_event(RawSurfaceEvent::KeyPressed { event: &event });
+            window_adapter.record_input(event.time);
+            window_adapter.forward_event(WindowEvent::KeyPressed { text });
             window_adapter.pending_redraw.set(true);
+        } else {
+            log_dropped(
+                "key press",
+                "no window has keyboard focus, or the key has no text",
+            );
         }
     }
 
@@ -322,10 +451,15 @@ impl KeyboardHandler for LayerShellState {
             })
             .and_then(|window_adapter| key_event_text(&event).map(|text| (window_adapter, text)))
         {
-            let _ = window_adapter
-                .window
-                .try_dispatch_event(WindowEvent::KeyPressRepeated { text });
+            window_adapter.observe_raw_event(RawSurfaceEvent::KeyRepeated { event: &event });
+            window_adapter.record_input(event.time);
+            window_adapter.forward_event(WindowEvent::KeyPressRepeated { text });
             window_adapter.pending_redraw.set(true);
+        } else {
+            log_dropped(
+                "key repeat",
+                "no window has keyboard focus, or the key has no text",
+            );
         }
     }
 
@@ -348,10 +482,15 @@ impl KeyboardHandler for LayerShellState {
             })
             .and_then(|window_adapter| key_event_text(&event).map(|text| (window_adapter, text)))
         {
-            let _ = window_adapter
-                .window
-                .try_dispatch_event(WindowEvent::KeyReleased { text });
+            window_adapter.observe_raw_event(RawSurfaceEvent::KeyReleased { event: &event });
+            window_adapter.record_input(event.time);
+            window_adapter.forward_event(WindowEvent::KeyReleased { text });
             window_adapter.pending_redraw.set(true);
+        } else {
+            log_dropped(
+                "key release",
+                "no window has keyboard focus, or the key has no text",
+            );
         }
     }
 
@@ -373,53 +512,70 @@ impl PointerHandler for LayerShellState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _pointer: &wl_pointer::WlPointer,
+        pointer: &wl_pointer::WlPointer,
         events: &[PointerEvent],
     ) {
         for event in events {
             let id = event.surface.id();
             let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
+                log_dropped("pointer event", "the surface has no window");
                 continue;
             };
             let Some(window_adapter) = window_adapter_weak.upgrade() else {
                 self.window_adapters.remove(&id);
                 continue;
             };
+            match event.kind {
+                PointerEventKind::Enter { serial } => {
+                    self.shared.borrow_mut().pointer_enter = Some((pointer.clone(), serial));
+                }
+                PointerEventKind::Motion { .. } => {
+                    show_hidden_cursor(&mut self.shared.borrow_mut())
+                }
+                _ => {}
+            }
+            window_adapter.observe_raw_event(RawSurfaceEvent::Pointer { event });
+            if let PointerEventKind::Motion { time }
+            | PointerEventKind::Press { time, .. }
+            | PointerEventKind::Release { time, .. }
+            | PointerEventKind::Axis { time, .. } = event.kind
+            {
+                window_adapter.record_input(time);
+            }
 
             let position = LogicalPosition::new(event.position.0 as f32, event.position.1 as f32);
             match event.kind {
                 PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
-                    let _ = window_adapter
-                        .window
-                        .try_dispatch_event(WindowEvent::PointerMoved { position });
+                    window_adapter.forward_event(WindowEvent::PointerMoved { position });
                 }
                 PointerEventKind::Leave { .. } => {
-                    let _ = window_adapter
-                        .window
-                        .try_dispatch_event(WindowEvent::PointerExited);
+                    window_adapter.forward_event(WindowEvent::PointerExited);
                 }
-                PointerEventKind::Press { button, .. } => {
-                    let _ = window_adapter
-                        .window
-                        .try_dispatch_event(WindowEvent::PointerPressed {
-                            position,
-                            button: map_pointer_button(button),
-                        });
+                PointerEventKind::Press { button, serial, .. } => {
+                    #[cfg(feature = "clipboard")]
+                    {
+                        self.clipboard.borrow_mut().last_serial = serial;
+                    }
+                    window_adapter.pointer_button(button, true);
+                    window_adapter.forward_event(WindowEvent::PointerPressed {
+                        position,
+                        button: map_pointer_button(button),
+                    });
                 }
                 PointerEventKind::Release { button, .. } => {
-                    let _ =
-                        window_adapter
-                            .window
-                            .try_dispatch_event(WindowEvent::PointerReleased {
-                                position,
-                                button: map_pointer_button(button),
-                            });
+                    window_adapter.pointer_button(button, false);
+                    window_adapter.forward_event(WindowEvent::PointerReleased {
+                        position,
+                        button: map_pointer_button(button),
+                    });
                 }
                 PointerEventKind::Axis {
                     horizontal,
                     vertical,
                     ..
                 } => {
+                    // sctk fills `discrete` from `axis_value120` on wl_pointer version 8 and
+                    // from `axis_discrete` before, so wheels scroll the same on both.
                     let delta_x = if horizontal.absolute != 0.0 {
                         horizontal.absolute as f32
                     } else {
@@ -430,14 +586,11 @@ impl PointerHandler for LayerShellState {
                     } else {
                         vertical.discrete as f32 * 15.0
                     };
-                    let _ =
-                        window_adapter
-                            .window
-                            .try_dispatch_event(WindowEvent::PointerScrolled {
-                                position,
-                                delta_x,
-                                delta_y,
-                            });
+                    window_adapter.forward_event(WindowEvent::PointerScrolled {
+                        position,
+                        delta_x,
+                        delta_y,
+                    });
                 }
             }
             window_adapter.pending_redraw.set(true);
@@ -445,6 +598,7 @@ impl PointerHandler for LayerShellState {
     }
 }
 
+#[cfg(feature = "touch")]
 impl TouchHandler for LayerShellState {
     fn down(
         &mut self,
@@ -452,29 +606,30 @@ impl TouchHandler for LayerShellState {
         _qh: &QueueHandle<Self>,
         _touch: &wl_touch::WlTouch,
         _serial: u32,
-        _time: u32,
+        time: u32,
         surface: WlSurface,
         id: i32,
         position: (f64, f64),
     ) {
         let surface_id = surface.id();
         let Some(window_adapter_weak) = self.window_adapters.get(&surface_id).cloned() else {
+            log_dropped("touch down", "the surface has no window");
             return;
         };
         let Some(window_adapter) = window_adapter_weak.upgrade() else {
             self.window_adapters.remove(&surface_id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::TouchDown { id, position });
+        window_adapter.record_input(time);
 
         let position = (position.0 as f32, position.1 as f32);
         self.touch_points.insert(id, (surface_id, position));
 
-        let _ = window_adapter
-            .window
-            .try_dispatch_event(WindowEvent::PointerPressed {
-                position: LogicalPosition::new(position.0, position.1),
-                button: PointerEventButton::Left,
-            });
+        window_adapter.forward_event(WindowEvent::PointerPressed {
+            position: LogicalPosition::new(position.0, position.1),
+            button: PointerEventButton::Left,
+        });
         window_adapter.pending_redraw.set(true);
     }
 
@@ -484,7 +639,7 @@ impl TouchHandler for LayerShellState {
         _qh: &QueueHandle<Self>,
         _touch: &wl_touch::WlTouch,
         _serial: u32,
-        _time: u32,
+        time: u32,
         id: i32,
     ) {
         let Some((surface_id, position)) = self.touch_points.remove(&id) else {
@@ -497,13 +652,13 @@ impl TouchHandler for LayerShellState {
             self.window_adapters.remove(&surface_id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::TouchUp { id });
+        window_adapter.record_input(time);
 
-        let _ = window_adapter
-            .window
-            .try_dispatch_event(WindowEvent::PointerReleased {
-                position: LogicalPosition::new(position.0, position.1),
-                button: PointerEventButton::Left,
-            });
+        window_adapter.forward_event(WindowEvent::PointerReleased {
+            position: LogicalPosition::new(position.0, position.1),
+            button: PointerEventButton::Left,
+        });
         window_adapter.pending_redraw.set(true);
     }
 
@@ -512,13 +667,14 @@ impl TouchHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         _touch: &wl_touch::WlTouch,
-        _time: u32,
+        time: u32,
         id: i32,
         position: (f64, f64),
     ) {
         let Some((surface_id, _)) = self.touch_points.get(&id).cloned() else {
             return;
         };
+        let raw_position = position;
         let position = (position.0 as f32, position.1 as f32);
         if let Some((_, stored_position)) = self.touch_points.get_mut(&id) {
             *stored_position = position;
@@ -531,12 +687,15 @@ impl TouchHandler for LayerShellState {
             self.window_adapters.remove(&surface_id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::TouchMotion {
+            id,
+            position: raw_position,
+        });
+        window_adapter.record_input(time);
 
-        let _ = window_adapter
-            .window
-            .try_dispatch_event(WindowEvent::PointerMoved {
-                position: LogicalPosition::new(position.0, position.1),
-            });
+        window_adapter.forward_event(WindowEvent::PointerMoved {
+            position: LogicalPosition::new(position.0, position.1),
+        });
         window_adapter.pending_redraw.set(true);
     }
 
@@ -545,10 +704,14 @@ impl TouchHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         _touch: &wl_touch::WlTouch,
-        _id: i32,
-        _major: f64,
-        _minor: f64,
+        id: i32,
+        major: f64,
+        minor: f64,
     ) {
+        // Slint has no notion of contact areas, so they only reach raw event observers.
+        if let Some(window_adapter) = self.touch_point_window_adapter(id) {
+            window_adapter.observe_raw_event(RawSurfaceEvent::TouchShape { id, major, minor });
+        }
     }
 
     fn orientation(
@@ -556,9 +719,12 @@ impl TouchHandler for LayerShellState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
         _touch: &wl_touch::WlTouch,
-        _id: i32,
-        _orientation: f64,
+        id: i32,
+        orientation: f64,
     ) {
+        if let Some(window_adapter) = self.touch_point_window_adapter(id) {
+            window_adapter.observe_raw_event(RawSurfaceEvent::TouchOrientation { id, orientation });
+        }
     }
 
     fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _touch: &wl_touch::WlTouch) {
@@ -576,27 +742,57 @@ impl TouchHandler for LayerShellState {
                 continue;
             };
 
-            let _ = window_adapter
-                .window
-                .try_dispatch_event(WindowEvent::PointerReleased {
-                    position: LogicalPosition::new(position.0, position.1),
-                    button: PointerEventButton::Left,
-                });
+            window_adapter.forward_event(WindowEvent::PointerReleased {
+                position: LogicalPosition::new(position.0, position.1),
+                button: PointerEventButton::Left,
+            });
             window_adapter.pending_redraw.set(true);
         }
     }
 }
 
+#[cfg(feature = "touch")]
+impl LayerShellState {
+    /// Returns the window adapter of the surface the touch point `id` went down on.
+    fn touch_point_window_adapter(
+        &mut self,
+        id: i32,
+    ) -> Option<std::rc::Rc<LayerShellWindowAdapter>> {
+        let (surface_id, _) = self.touch_points.get(&id)?;
+        let surface_id = surface_id.clone();
+        let window_adapter = self.window_adapters.get(&surface_id)?.upgrade();
+        if window_adapter.is_none() {
+            self.window_adapters.remove(&surface_id);
+        }
+        window_adapter
+    }
+}
+
+/// Shows the cursor [`crate::platform::SlintLayerShell::hide_cursor`] hid, with the default shape.
+fn show_hidden_cursor(shared: &mut SharedState) {
+    if !std::mem::take(&mut shared.cursor_hidden) {
+        return;
+    }
+    if let (Some(device), Some((_, serial))) = (&shared.cursor_shape_device, &shared.pointer_enter)
+    {
+        device.set_shape(*serial, Shape::Default);
+    }
+}
+
 fn map_pointer_button(button: u32) -> PointerEventButton {
     match button {
         BTN_LEFT => PointerEventButton::Left,
         BTN_RIGHT => PointerEventButton::Right,
         BTN_MIDDLE => PointerEventButton::Middle,
+        // Back, forward and other extra buttons are reported by `on_pointer_button`.
         _ => PointerEventButton::Other,
     }
 }
 
 fn key_event_text(event: &KeyEvent) -> Option<SharedString> {
+    if let Some(key) = special_key(event.keysym) {
+        return Some(key.into());
+    }
     if let Some(text) = &event.utf8 {
         if !text.is_empty() {
             return Some(text.clone().into());
@@ -605,6 +801,30 @@ fn key_event_text(event: &KeyEvent) -> Option<SharedString> {
     event.keysym.key_char().map(Into::into)
 }
 
+/// Maps navigation and editing keysyms to Slint's key codes, as they have no text of their
+/// own or one Slint does not recognize.
+///
+/// xkb resolves keypad keys against NumLock before they get here: with NumLock on the digit
+/// keysyms arrive and are entered as text, with NumLock off the navigation keysyms below.
+fn special_key(keysym: Keysym) -> Option<Key> {
+    Some(match keysym {
+        // xkb produces "\r", while Slint expects '\n' for Return.
+        Keysym::Return | Keysym::KP_Enter => Key::Return,
+        Keysym::Home | Keysym::KP_Home => Key::Home,
+        Keysym::End | Keysym::KP_End => Key::End,
+        Keysym::Left | Keysym::KP_Left => Key::LeftArrow,
+        Keysym::Right | Keysym::KP_Right => Key::RightArrow,
+        Keysym::Up | Keysym::KP_Up => Key::UpArrow,
+        Keysym::Down | Keysym::KP_Down => Key::DownArrow,
+        Keysym::Page_Up | Keysym::KP_Page_Up => Key::PageUp,
+        Keysym::Page_Down | Keysym::KP_Page_Down => Key::PageDown,
+        Keysym::Insert | Keysym::KP_Insert => Key::Insert,
+        Keysym::Delete | Keysym::KP_Delete => Key::Delete,
+        Keysym::Tab | Keysym::KP_Tab => Key::Tab,
+        _ => return None,
+    })
+}
+
 impl WindowHandler for LayerShellState {
     fn request_close(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _window: &Window) {}
 
@@ -624,44 +844,377 @@ impl WindowHandler for LayerShellState {
             self.window_adapters.remove(&id);
             return;
         };
+        window_adapter.observe_raw_event(RawSurfaceEvent::XdgConfigure {
+            configure: &configure,
+        });
+        let suspended = configure.is_suspended();
+        if window_adapter.suspended.replace(suspended) != suspended {
+            window_adapter.notify_visibility(if suspended {
+                VisibilityChange::Suspended
+            } else {
+                VisibilityChange::Resumed
+            });
+        }
 
+        // Configure sizes are in surface-local (logical) coordinates.
+        let scale_factor = window_adapter.window.scale_factor();
         let pending_size = window_adapter.pending_size.get();
         let current_size = window_adapter.size.get();
-        let fallback_size = pending_size.unwrap_or(current_size);
-
-        let width =
-            configure
-                .new_size
-                .0
-                .map(|value| value.get())
-                .unwrap_or(if fallback_size.width > 0 {
-                    fallback_size.width
-                } else {
-                    100
-                });
-        let height =
-            configure
-                .new_size
-                .1
-                .map(|value| value.get())
-                .unwrap_or(if fallback_size.height > 0 {
-                    fallback_size.height
-                } else {
-                    100
-                });
-
-        let size = PhysicalSize::new(width, height);
+        let fallback_size = pending_size
+            .unwrap_or(current_size)
+            .to_logical(scale_factor);
+
+        let width = configure
+            .new_size
+            .0
+            .map(|value| value.get() as f32)
+            .unwrap_or(if fallback_size.width > 0.0 {
+                fallback_size.width
+            } else {
+                100.0
+            });
+        let height = configure
+            .new_size
+            .1
+            .map(|value| value.get() as f32)
+            .unwrap_or(if fallback_size.height > 0.0 {
+                fallback_size.height
+            } else {
+                100.0
+            });
+
+        let size = LogicalSize::new(width, height).to_physical(scale_factor);
+        self.apply_configured_size(&window_adapter, size);
+    }
+}
+
+impl LayerShellState {
+    fn migrate_surfaces_from(&mut self, qh: &QueueHandle<Self>, removed: &WlOutput) {
+        let pinned = self
+            .window_adapters
+            .values()
+            .filter_map(|window_adapter| window_adapter.upgrade())
+            .filter(|window_adapter| {
+                window_adapter
+                    .layer_config
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|config| config.output.as_ref() == Some(removed))
+            })
+            .collect::<Vec<_>>();
+        if pinned.is_empty() {
+            return;
+        }
+
+        let outputs = self
+            .outputs()
+            .into_iter()
+            .filter(|info| &info.output != removed)
+            .collect::<Vec<_>>();
+        let fallback = self
+            .output_chooser
+            .as_ref()
+            .and_then(|output_chooser| output_chooser(&outputs).first().cloned());
+        let Some(layer_shell) = &self.layer_shell else {
+            return;
+        };
+        for window_adapter in pinned {
+            window_adapter.recreate_layer_surface(layer_shell, qh, fallback.clone());
+        }
+    }
+
+    /// Drops the input state referring to a surface whose window adapter is gone, so that
+    /// key events are not sent to a stale focus.
+    ///
+    /// Focus is not handed to another window here: the compositor picks the surface that is
+    /// focused next and sends it `wl_keyboard.enter`, which sets the new focus.
+    pub(crate) fn forget_surface(&mut self, id: &ObjectId) {
+        self.window_adapters.remove(id);
+        if self.keyboard_focus_surface.as_ref() == Some(id) {
+            self.keyboard_focus_surface = None;
+        }
+        #[cfg(feature = "touch")]
+        self.touch_points
+            .retain(|_, (surface_id, _)| surface_id != id);
+    }
+
+    /// Picks the compositor's preferred scale, or else the largest scale of all outputs the
+    /// surface overlaps, so that windows spanning monitors do not flap between scales.
+    fn update_effective_scale(&mut self, window_adapter: &LayerShellWindowAdapter) {
+        // Before version 6, `scale_factor_changed` already carries the scale of the entered
+        // outputs. From version 6 on, the outputs only stand in until the compositor sends a
+        // preferred scale.
+        if window_adapter.surface.version() >= 6
+            && window_adapter.preferred_buffer_scale.get().is_none()
+        {
+            let scale = window_adapter
+                .entered_outputs
+                .borrow()
+                .iter()
+                .filter_map(|output| self.output_state.info(output))
+                .map(|info| info.scale_factor)
+                .max();
+            if let Some(scale) = scale {
+                self.apply_scale_factor(window_adapter, scale);
+            }
+        }
+        self.update_position(window_adapter);
+    }
+
+    /// Derives the position of a layer surface from its anchors and margins within the output
+    /// it is placed on, or the first output it entered.
+    fn update_position(&self, window_adapter: &LayerShellWindowAdapter) {
+        let position = window_adapter
+            .layer_config
+            .borrow()
+            .as_ref()
+            .and_then(|config| {
+                let output = config
+                    .output
+                    .clone()
+                    .or_else(|| window_adapter.entered_outputs.borrow().first().cloned())?;
+                let info = self.output_state.info(&output)?;
+                let (output_x, output_y) = info.logical_position.unwrap_or((0, 0));
+                let (output_width, output_height) = info.logical_size?;
+                let size = window_adapter
+                    .size
+                    .get()
+                    .to_logical(window_adapter.window.scale_factor());
+                let margins = config.margins;
+                let place = |start: bool,
+                             end: bool,
+                             margin_start: i32,
+                             margin_end: i32,
+                             extent: i32,
+                             size: f32| {
+                    match (start, end) {
+                        (true, _) => margin_start as f32,
+                        (false, true) => extent as f32 - size - margin_end as f32,
+                        // Unanchored surfaces are centered, ignoring the margins.
+                        (false, false) => (extent as f32 - size) / 2.0,
+                    }
+                };
+                let x = place(
+                    config.anchor.contains(Anchor::LEFT),
+                    config.anchor.contains(Anchor::RIGHT),
+                    margins.left,
+                    margins.right,
+                    output_width,
+                    size.width,
+                );
+                let y = place(
+                    config.anchor.contains(Anchor::TOP),
+                    config.anchor.contains(Anchor::BOTTOM),
+                    margins.top,
+                    margins.bottom,
+                    output_height,
+                    size.height,
+                );
+                Some(
+                    LogicalPosition::new(output_x as f32 + x, output_y as f32 + y)
+                        .to_physical(window_adapter.window.scale_factor()),
+                )
+            });
+        window_adapter.position.set(position);
+    }
+
+    /// Switches the window to an integer buffer scale, keeping its logical size.
+    pub(crate) fn apply_scale_factor(
+        &mut self,
+        window_adapter: &LayerShellWindowAdapter,
+        scale: i32,
+    ) {
+        // Buffer scales need wl_surface version 3, older compositors only get 1x buffers.
+        if window_adapter.surface.version() < 3 {
+            return;
+        }
+        let scale = scale.max(1);
+        let old_scale_factor = window_adapter.window.scale_factor();
+        if old_scale_factor == scale as f32 {
+            return;
+        }
+
+        let logical_size = window_adapter.size.get().to_logical(old_scale_factor);
+        window_adapter.surface.set_buffer_scale(scale);
+        window_adapter.forward_event(WindowEvent::ScaleFactorChanged {
+            scale_factor: scale as f32,
+        });
+
+        if window_adapter.window_state.get() == crate::window_adapter::WindowState::Configured {
+            window_adapter
+                .size
+                .set(logical_size.to_physical(scale as f32));
+            window_adapter.forward_event(WindowEvent::Resized { size: logical_size });
+        }
+        window_adapter.pending_redraw.set(true);
+    }
+
+    fn apply_configured_size(
+        &mut self,
+        window_adapter: &LayerShellWindowAdapter,
+        size: PhysicalSize,
+    ) {
+        // The role is configured and the configure acked by sctk, so buffers may be attached
+        // from now on.
+        if let Err(err) = window_adapter.attach_renderer(size) {
+            log::warn!("failed to set up rendering: {err}");
+            return;
+        }
         window_adapter.size.set(size);
         window_adapter.pending_size.set(None);
-        window_adapter
+        let mapped = window_adapter
             .window_state
-            .set(crate::window_adapter::WindowState::Configured);
+            .replace(crate::window_adapter::WindowState::Configured)
+            != crate::window_adapter::WindowState::Configured;
+        if mapped {
+            window_adapter.notify_visibility(VisibilityChange::Mapped);
+        }
 
         let logical_size = size.to_logical(window_adapter.window.scale_factor());
-        let _ = window_adapter
-            .window
-            .try_dispatch_event(WindowEvent::Resized { size: logical_size });
+        window_adapter.forward_event(WindowEvent::Resized { size: logical_size });
         window_adapter.pending_redraw.set(true);
+        self.update_position(window_adapter);
+
+        self.backend_events
+            .push_back(BackendEvent::WindowConfigured {
+                surface: window_adapter.surface.clone(),
+                size,
+            });
+    }
+}
+
+impl LayerShellState {
+    /// Handles the compositor closing a layer surface of either layer shell protocol.
+    pub(crate) fn layer_surface_closed(&mut self, layer: &LayerSurface) {
+        if let Some(window_adapter) = self
+            .window_adapters
+            .get(&layer.wl_surface().id())
+            .and_then(std::rc::Weak::upgrade)
+        {
+            // A surface re-created on another output is closed after it was replaced.
+            let current = window_adapter.layer_surface.borrow().as_ref() == Some(layer);
+            if current {
+                // The closed layer surface is destroyed, keeping the `wl_surface` to create a
+                // new one on if the window is shown again.
+                window_adapter.retire_layer_surface();
+                window_adapter
+                    .window_state
+                    .set(crate::window_adapter::WindowState::Pending);
+                window_adapter.notify_visibility(VisibilityChange::Unmapped);
+                window_adapter.request_close();
+            }
+        }
+        self.backend_events
+            .push_back(BackendEvent::LayerSurfaceClosed {
+                surface: layer.wl_surface().clone(),
+            });
+    }
+
+    /// Handles a configure of a layer surface of either layer shell protocol, which was
+    /// acked already.
+    pub(crate) fn layer_surface_configured(
+        &mut self,
+        layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+    ) {
+        let id = layer.wl_surface().id();
+        let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
+            return;
+        };
+        let Some(window_adapter) = window_adapter_weak.upgrade() else {
+            self.window_adapters.remove(&id);
+            return;
+        };
+        window_adapter.observe_raw_event(RawSurfaceEvent::LayerConfigure {
+            configure: &configure,
+        });
+        // The configure is acked before this is called. One answering a commit made before the
+        // window was hidden must not map it again, showing it commits and gets a new one.
+        if !window_adapter.visible.get() {
+            log_dropped("layer configure", "the window is hidden");
+            return;
+        }
+
+        // A zero size means the client picks, which is the preferred size of the Slint window.
+        let scale_factor = window_adapter.window.scale_factor();
+        let fallback_size = window_adapter.preferred_size.get().unwrap_or_else(|| {
+            window_adapter
+                .pending_size
+                .get()
+                .unwrap_or(window_adapter.size.get())
+                .to_logical(scale_factor)
+        });
+        let (mut width, mut height) = configure.new_size;
+        if self.quirks.get().prefer_requested_size {
+            let config = window_adapter.layer_config.borrow();
+            if let Some((config, (requested_width, requested_height))) = config
+                .as_ref()
+                .and_then(|config| config.size.map(|size| (config, size)))
+            {
+                let (stretch_width, stretch_height) = config.stretched();
+                if !stretch_width && requested_width > 0 {
+                    width = requested_width;
+                }
+                if !stretch_height && requested_height > 0 {
+                    height = requested_height;
+                }
+            }
+        }
+        let logical_size = LogicalSize::new(
+            if width > 0 {
+                width as f32
+            } else {
+                fallback_size.width.max(1.0)
+            },
+            if height > 0 {
+                height as f32
+            } else {
+                fallback_size.height.max(1.0)
+            },
+        );
+
+        self.apply_configured_size(&window_adapter, logical_size.to_physical(scale_factor));
+        window_adapter.update_auto_exclusive_zone();
+    }
+}
+
+#[cfg(feature = "session-lock")]
+impl SessionLockHandler for LayerShellState {
+    fn locked(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _session_lock: SessionLock) {
+        self.backend_events.push_back(BackendEvent::SessionLocked);
+    }
+
+    fn finished(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _session_lock: SessionLock,
+    ) {
+        self.backend_events
+            .push_back(BackendEvent::SessionLockFinished);
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        surface: SessionLockSurface,
+        configure: SessionLockSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let id = surface.wl_surface().id();
+        let Some(window_adapter_weak) = self.window_adapters.get(&id).cloned() else {
+            return;
+        };
+        let Some(window_adapter) = window_adapter_weak.upgrade() else {
+            self.window_adapters.remove(&id);
+            return;
+        };
+
+        let scale_factor = window_adapter.window.scale_factor();
+        let (width, height) = configure.new_size;
+        let logical_size = LogicalSize::new(width.max(1) as f32, height.max(1) as f32);
+        self.apply_configured_size(&window_adapter, logical_size.to_physical(scale_factor));
     }
 }
 
@@ -671,6 +1224,11 @@ delegate_output!(LayerShellState);
 delegate_seat!(LayerShellState);
 delegate_keyboard!(LayerShellState);
 delegate_pointer!(LayerShellState);
-delegate_touch!(LayerShellState);
+#[cfg(feature = "touch")]
+smithay_client_toolkit::delegate_touch!(LayerShellState);
+#[cfg(feature = "screencopy")]
+smithay_client_toolkit::delegate_shm!(LayerShellState);
+#[cfg(feature = "session-lock")]
+smithay_client_toolkit::delegate_session_lock!(LayerShellState);
 delegate_xdg_shell!(LayerShellState);
 delegate_xdg_window!(LayerShellState);
@@ -0,0 +1,45 @@
+use slint::PlatformError;
+use std::fmt;
+
+/// Misuse of the surface API that the compositor would answer with a protocol error, which
+/// terminates the connection.
+///
+/// Returned inside [`PlatformError::OtherError`], so it can be told apart with
+/// `downcast_ref::<SurfaceError>()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SurfaceError {
+    /// The window was resized before the compositor configured it. Buffers attached before
+    /// the first configure are a protocol error, so the size is only accepted afterwards.
+    NotConfigured,
+    /// A layer surface was given a width of zero without being anchored to the left and
+    /// right edges, so the compositor has no size to give it.
+    ZeroWidth,
+    /// A layer surface was given a height of zero without being anchored to the top and
+    /// bottom edges.
+    ZeroHeight,
+}
+
+impl fmt::Display for SurfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "the surface is not configured yet"),
+            Self::ZeroWidth => write!(
+                f,
+                "a layer surface with a width of 0 must be anchored to the left and right edges"
+            ),
+            Self::ZeroHeight => write!(
+                f,
+                "a layer surface with a height of 0 must be anchored to the top and bottom edges"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SurfaceError {}
+
+impl From<SurfaceError> for PlatformError {
+    fn from(error: SurfaceError) -> Self {
+        PlatformError::OtherError(Box::new(error))
+    }
+}
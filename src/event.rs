@@ -0,0 +1,273 @@
+use crate::layer_shell::LayerSurfaceConfigure;
+use slint::PhysicalSize;
+use smithay_client_toolkit::seat::Capability;
+use smithay_client_toolkit::seat::keyboard::KeyEvent;
+use smithay_client_toolkit::seat::pointer::PointerEvent;
+pub use smithay_client_toolkit::seat::pointer::{
+    BTN_BACK, BTN_EXTRA, BTN_FORWARD, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT, BTN_SIDE,
+};
+use smithay_client_toolkit::shell::xdg::window::WindowConfigure;
+use std::sync::OnceLock;
+use std::time::Instant;
+use wayland_client::protocol::wl_output::{Transform, WlOutput};
+use wayland_client::protocol::{wl_seat::WlSeat, wl_surface::WlSurface};
+#[cfg(feature = "foreign-toplevel")]
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1;
+
+/// High level events reported by [`SlintLayerShell::run_event_loop_with`].
+///
+/// [`SlintLayerShell::run_event_loop_with`]: crate::platform::SlintLayerShell::run_event_loop_with
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum BackendEvent {
+    OutputAdded {
+        output: WlOutput,
+    },
+    OutputUpdated {
+        output: WlOutput,
+    },
+    OutputRemoved {
+        output: WlOutput,
+    },
+    WindowConfigured {
+        surface: WlSurface,
+        size: PhysicalSize,
+    },
+    SeatAdded {
+        seat: WlSeat,
+    },
+    SeatRemoved {
+        seat: WlSeat,
+    },
+    SeatCapabilityAdded {
+        seat: WlSeat,
+        capability: Capability,
+    },
+    SeatCapabilityRemoved {
+        seat: WlSeat,
+        capability: Capability,
+    },
+    /// The compositor closed the layer surface of the window owning `surface`, e.g. because
+    /// its output was removed.
+    LayerSurfaceClosed {
+        surface: WlSurface,
+    },
+    /// The compositor confirmed that the session is locked.
+    #[cfg(feature = "session-lock")]
+    SessionLocked,
+    /// The session lock was denied or ended by the compositor.
+    #[cfg(feature = "session-lock")]
+    SessionLockFinished,
+    /// A signal registered with [`SlintLayerShell::watch_signals`] was received.
+    ///
+    /// [`SlintLayerShell::watch_signals`]: crate::platform::SlintLayerShell::watch_signals
+    #[cfg(feature = "signals")]
+    Signal {
+        signal: calloop::signals::Signal,
+    },
+    /// A window of another application appeared, see [`crate::foreign_toplevel`].
+    #[cfg(feature = "foreign-toplevel")]
+    ToplevelAdded {
+        handle: ZwlrForeignToplevelHandleV1,
+    },
+    #[cfg(feature = "foreign-toplevel")]
+    ToplevelUpdated {
+        handle: ZwlrForeignToplevelHandleV1,
+    },
+    #[cfg(feature = "foreign-toplevel")]
+    ToplevelRemoved {
+        handle: ZwlrForeignToplevelHandleV1,
+    },
+    /// A text field gained focus in the window owning `surface`, so an on-screen keyboard
+    /// should be shown.
+    TextInputEnabled {
+        surface: WlSurface,
+    },
+    /// The focused text field of the window owning `surface` lost focus.
+    TextInputDisabled {
+        surface: WlSurface,
+    },
+    /// Data was dropped onto the window owning `surface`.
+    #[cfg(feature = "clipboard")]
+    DataDropped {
+        surface: WlSurface,
+        content: crate::clipboard::ClipboardContent,
+    },
+    /// Emitted right before the window owning `surface` is rendered.
+    AboutToRender {
+        surface: WlSurface,
+    },
+}
+
+/// When the latest input event of a window happened, see [`WindowExt::last_input`].
+///
+/// [`WindowExt::last_input`]: crate::window_ext::WindowExt::last_input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputTimestamp {
+    /// Timestamp of the Wayland event in milliseconds. Its base is undefined, so only
+    /// differences between timestamps are meaningful.
+    pub time: u32,
+    /// When the event was received by this process, to measure inactivity or the latency
+    /// until the next frame.
+    pub received: Instant,
+}
+
+/// A pointer button, as passed to [`SlintLayerShell::on_pointer_button`].
+///
+/// Slint receives the buttons other than left, right and middle as
+/// `PointerEventButton::Other`, so e.g. back and forward are only told apart here.
+///
+/// [`SlintLayerShell::on_pointer_button`]: crate::platform::SlintLayerShell::on_pointer_button
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerButton {
+    Left,
+    Right,
+    Middle,
+    /// `BTN_SIDE` or `BTN_BACK`, the thumb button most mice use to navigate back.
+    Back,
+    /// `BTN_EXTRA` or `BTN_FORWARD`.
+    Forward,
+    /// Any other button, with its Linux input event code.
+    Other(u32),
+}
+
+impl PointerButton {
+    /// Names the Linux input event code of a button, as in [`BTN_LEFT`].
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            BTN_LEFT => Self::Left,
+            BTN_RIGHT => Self::Right,
+            BTN_MIDDLE => Self::Middle,
+            BTN_SIDE | BTN_BACK => Self::Back,
+            BTN_EXTRA | BTN_FORWARD => Self::Forward,
+            code => Self::Other(code),
+        }
+    }
+}
+
+/// A change of whether a window can be seen, as passed to
+/// [`SlintLayerShell::on_visibility_changed`], e.g. to pause polling data while it is not.
+///
+/// [`SlintLayerShell::on_visibility_changed`]: crate::platform::SlintLayerShell::on_visibility_changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VisibilityChange {
+    /// The surface was configured and shows the window's content.
+    Mapped,
+    /// The window was hidden, or its layer surface was closed by the compositor.
+    Unmapped,
+    /// The surface entered `output`, possibly in addition to others.
+    EnteredOutput(WlOutput),
+    /// The surface left the last output it was shown on.
+    LeftAllOutputs,
+    /// The compositor suspended the xdg toplevel, e.g. because it is fully occluded or on
+    /// another workspace.
+    Suspended,
+    /// The compositor no longer suspends the xdg toplevel.
+    Resumed,
+}
+
+/// A change of a seat, as passed to [`SlintLayerShell::on_seat_changed`].
+///
+/// [`SlintLayerShell::on_seat_changed`]: crate::platform::SlintLayerShell::on_seat_changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeatChange {
+    Added,
+    Removed,
+    CapabilityAdded(Capability),
+    CapabilityRemoved(Capability),
+}
+
+/// Wayland events received for a single surface, as passed to a raw event observer.
+///
+/// Observers see the events before they are translated into Slint window events.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RawSurfaceEvent<'a> {
+    Enter {
+        output: &'a WlOutput,
+    },
+    Leave {
+        output: &'a WlOutput,
+    },
+    Frame {
+        time: u32,
+    },
+    TransformChanged {
+        transform: Transform,
+    },
+    XdgConfigure {
+        configure: &'a WindowConfigure,
+    },
+    LayerConfigure {
+        configure: &'a LayerSurfaceConfigure,
+    },
+    KeyboardEnter,
+    KeyboardLeave,
+    KeyPressed {
+        event: &'a KeyEvent,
+    },
+    KeyRepeated {
+        event: &'a KeyEvent,
+    },
+    KeyReleased {
+        event: &'a KeyEvent,
+    },
+    Pointer {
+        event: &'a PointerEvent,
+    },
+    #[cfg(feature = "touch")]
+    TouchDown {
+        id: i32,
+        position: (f64, f64),
+    },
+    #[cfg(feature = "touch")]
+    TouchUp {
+        id: i32,
+    },
+    #[cfg(feature = "touch")]
+    TouchMotion {
+        id: i32,
+        position: (f64, f64),
+    },
+    /// The contact area of a touch point as an ellipse, with the axis lengths in logical
+    /// pixels, e.g. for palm rejection. Sent after `TouchDown` or `TouchMotion` by
+    /// touchscreens that report it.
+    #[cfg(feature = "touch")]
+    TouchShape {
+        id: i32,
+        major: f64,
+        minor: f64,
+    },
+    /// The angle in degrees of the major axis of the contact area to the surface's y-axis,
+    /// clockwise.
+    #[cfg(feature = "touch")]
+    TouchOrientation {
+        id: i32,
+        orientation: f64,
+    },
+}
+
+/// Environment variable enabling debug output, as a comma separated list of modes.
+///
+/// `SLINT_LAYER_SHELL_DEBUG=events` logs the Wayland events received for each surface, the
+/// Slint window events they are translated into, and input that is dropped because no window
+/// takes it, at info level with the [`EVENTS_LOG_TARGET`] target.
+pub const DEBUG_ENV: &str = "SLINT_LAYER_SHELL_DEBUG";
+
+pub const EVENTS_LOG_TARGET: &str = "slint_layer_shell::events";
+
+/// Returns whether the `events` mode of [`DEBUG_ENV`] is enabled.
+pub(crate) fn debug_events() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var(DEBUG_ENV)
+            .is_ok_and(|modes| modes.split(',').any(|mode| mode.trim() == "events"))
+    })
+}
+
+/// Logs input that no window receives, in the `events` debug mode.
+pub(crate) fn log_dropped(event: &str, reason: &str) {
+    if debug_events() {
+        log::info!(target: EVENTS_LOG_TARGET, "dropped {event}: {reason}");
+    }
+}
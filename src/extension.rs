@@ -0,0 +1,105 @@
+//! Handling protocols this crate does not support, on the same queue and event loop as the
+//! platform.
+//!
+//! The Wayland state type is [`LayerShellState`], so objects are dispatched to
+//! `impl Dispatch<I, U> for LayerShellState`. Such impls are allowed outside of this crate
+//! as long as the user data type `U` is defined by the application:
+//!
+//! ```ignore
+//! struct IdleData;
+//!
+//! #[derive(Default)]
+//! struct IdleState {
+//!     idle: bool,
+//! }
+//!
+//! impl Dispatch<ExtIdleNotificationV1, IdleData> for LayerShellState {
+//!     fn event(state: &mut Self, _: &ExtIdleNotificationV1, event: ext_idle_notification_v1::Event,
+//!              _: &IdleData, _: &Connection, _: &QueueHandle<Self>) {
+//!         if let Some(idle) = state.extension_mut::<IdleState>() {
+//!             idle.idle = matches!(event, ext_idle_notification_v1::Event::Idled);
+//!         }
+//!     }
+//! }
+//!
+//! platform.insert_extension(IdleState::default());
+//! let notifier: ExtIdleNotifierV1 = platform.bind_global(1..=1, IdleData)?;
+//! ```
+
+use crate::platform::{LayerShellState, SlintLayerShell};
+use slint::PlatformError;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use wayland_client::{Dispatch, Proxy};
+
+/// Application state stored alongside the platform state, one value per type.
+#[derive(Default)]
+pub(crate) struct Extensions(HashMap<TypeId, Box<dyn Any>>);
+
+impl LayerShellState {
+    /// Returns the extension state of type `T`, see [`SlintLayerShell::insert_extension`].
+    pub fn extension<T: Any>(&self) -> Option<&T> {
+        self.extensions.0.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn extension_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.extensions
+            .0
+            .get_mut(&TypeId::of::<T>())?
+            .downcast_mut()
+    }
+}
+
+impl SlintLayerShell {
+    /// Stores `extension` in the Wayland state, where `Dispatch` impls of the application can
+    /// reach it with [`LayerShellState::extension_mut`]. Replaces any previous value of the
+    /// same type.
+    pub fn insert_extension<T: Any>(&self, extension: T) {
+        self.state
+            .borrow_mut()
+            .extensions
+            .0
+            .insert(TypeId::of::<T>(), Box::new(extension));
+    }
+
+    pub fn remove_extension<T: Any>(&self) -> Option<T> {
+        let extension = self
+            .state
+            .borrow_mut()
+            .extensions
+            .0
+            .remove(&TypeId::of::<T>())?;
+        extension.downcast().ok().map(|extension| *extension)
+    }
+
+    /// Runs `f` with the extension state of type `T`.
+    ///
+    /// Returns `None` if there is no such extension, or if called during Wayland dispatch,
+    /// where the state is only reachable through the `Dispatch` impl.
+    pub fn with_extension<T: Any, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mut state = self.state.try_borrow_mut().ok()?;
+        state.extension_mut::<T>().map(f)
+    }
+
+    /// Binds a global advertised by the compositor, whose events are dispatched to
+    /// `LayerShellState` with `udata`.
+    pub fn bind_global<I, U>(
+        &self,
+        version: RangeInclusive<u32>,
+        udata: U,
+    ) -> Result<I, PlatformError>
+    where
+        I: Proxy + 'static,
+        U: Send + Sync + 'static,
+        LayerShellState: Dispatch<I, U>,
+    {
+        self.state
+            .borrow()
+            .registry_state
+            .bind_one(self.queue_handle(), version, udata)
+            .map_err(|e| {
+                PlatformError::Other(format!("failed to bind {}: {e}", I::interface().name))
+            })
+    }
+}
@@ -0,0 +1,269 @@
+//! C ABI for installing the platform from other languages, declared in
+//! `include/slint-layer-shell.h` and wrapped for C++ in `include/slint-layer-shell.hpp`.
+//!
+//! Slint's C++ API is implemented in Rust, and the platform must be installed into the same
+//! copy of Slint. Build a `staticlib` crate that depends on both `slint-cpp` and this crate
+//! with the `ffi` feature and re-exports this module, then link it instead of the library
+//! built by Slint's CMake package:
+//!
+//! ```ignore
+//! pub use slint_cpp;
+//! pub use slint_layer_shell::ffi::*;
+//! ```
+//!
+//! The same applies to other bindings, such as Slint's Python package: the functions must be
+//! exported from the library that contains Slint.
+
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig, Margins};
+use crate::platform::SlintLayerShell;
+use std::cell::RefCell;
+use std::ffi::{CStr, c_char};
+
+thread_local! {
+    static PLATFORM: RefCell<Option<SlintLayerShell>> = const { RefCell::new(None) };
+}
+
+/// Layer surface properties, mirroring [`LayerConfig`].
+#[repr(C)]
+pub struct SlintLayerShellConfig {
+    /// `zwlr_layer_shell_v1` layer, from 0 (background) to 3 (overlay).
+    pub layer: u32,
+    /// Bitmask of the anchored edges: 1 top, 2 bottom, 4 left, 8 right.
+    pub anchor: u32,
+    /// Requested size in logical pixels, 0 to use the preferred size of the window.
+    pub width: u32,
+    pub height: u32,
+    pub margin_top: i32,
+    pub margin_right: i32,
+    pub margin_bottom: i32,
+    pub margin_left: i32,
+    pub exclusive_zone: i32,
+    pub auto_exclusive_zone: bool,
+    /// 0 none, 1 exclusive, 2 on demand.
+    pub keyboard_interactivity: u32,
+    /// NUL-terminated namespace, or null for the default one.
+    pub namespace: *const c_char,
+}
+
+impl SlintLayerShellConfig {
+    /// # Safety
+    ///
+    /// `namespace` must be null or point to a NUL-terminated string.
+    unsafe fn to_layer_config(&self) -> LayerConfig {
+        let defaults = LayerConfig::default();
+        LayerConfig {
+            layer: layer_from_c(self.layer),
+            anchor: Anchor::from_bits_truncate(self.anchor),
+            size: (self.width != 0 || self.height != 0).then_some((self.width, self.height)),
+            margins: Margins::new(
+                self.margin_top,
+                self.margin_right,
+                self.margin_bottom,
+                self.margin_left,
+            ),
+            exclusive_zone: self.exclusive_zone,
+            auto_exclusive_zone: self.auto_exclusive_zone,
+            keyboard_interactivity: keyboard_interactivity_from_c(self.keyboard_interactivity),
+            namespace: match unsafe { c_str(self.namespace) } {
+                Some(namespace) => namespace,
+                None => defaults.namespace.clone(),
+            },
+            ..defaults
+        }
+    }
+}
+
+/// Connects to the Wayland display, or to `WAYLAND_DISPLAY` if `display` is null, and
+/// installs the platform. Must be called before any Slint component is created.
+///
+/// Returns `false` and logs the error if the platform could not be installed.
+///
+/// # Safety
+///
+/// `display` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slint_layer_shell_init(display: *const c_char) -> bool {
+    let mut builder = SlintLayerShell::builder();
+    if let Some(display) = unsafe { c_str(display) } {
+        builder = builder.display(display);
+    }
+    let result = builder.build().and_then(|platform| {
+        slint::platform::set_platform(Box::new(platform.clone()))
+            .map_err(|e| slint::PlatformError::Other(e.to_string()))?;
+        Ok(platform)
+    });
+    match result {
+        Ok(platform) => {
+            PLATFORM.with(|cell| *cell.borrow_mut() = Some(platform));
+            true
+        }
+        Err(err) => {
+            log::error!("failed to install the layer shell platform: {err}");
+            false
+        }
+    }
+}
+
+/// Sets the layer configuration of windows created from now on, or makes them regular
+/// toplevels if `config` is null.
+///
+/// Like the setters of single properties below, returns `false` and changes nothing if the
+/// platform is not installed, or if it is dispatching events, e.g. from a Slint callback.
+///
+/// # Safety
+///
+/// `config` must be null or point to a valid configuration.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slint_layer_shell_set_default_layer_config(
+    config: *const SlintLayerShellConfig,
+) -> bool {
+    let config = unsafe { config.as_ref() }.map(|config| unsafe { config.to_layer_config() });
+    with_default_layer_config(|default_config| *default_config = config)
+}
+
+/// Sets the layer of new windows, from 0 (background) to 3 (overlay).
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_layer(layer: u32) -> bool {
+    update_default_layer_config(|config| config.layer = layer_from_c(layer))
+}
+
+/// Sets the anchored edges of new windows: 1 top, 2 bottom, 4 left, 8 right.
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_anchor(anchor: u32) -> bool {
+    update_default_layer_config(|config| config.anchor = Anchor::from_bits_truncate(anchor))
+}
+
+/// Sets the size of new windows in logical pixels, 0 to use their preferred size.
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_size(width: u32, height: u32) -> bool {
+    update_default_layer_config(|config| {
+        config.size = (width != 0 || height != 0).then_some((width, height));
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_margins(
+    top: i32,
+    right: i32,
+    bottom: i32,
+    left: i32,
+) -> bool {
+    update_default_layer_config(|config| config.margins = Margins::new(top, right, bottom, left))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_exclusive_zone(exclusive_zone: i32) -> bool {
+    update_default_layer_config(|config| {
+        config.exclusive_zone = exclusive_zone;
+        config.auto_exclusive_zone = false;
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_auto_exclusive_zone(enabled: bool) -> bool {
+    update_default_layer_config(|config| config.auto_exclusive_zone = enabled)
+}
+
+/// 0 none, 1 exclusive, 2 on demand.
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_set_keyboard_interactivity(
+    keyboard_interactivity: u32,
+) -> bool {
+    update_default_layer_config(|config| {
+        config.keyboard_interactivity = keyboard_interactivity_from_c(keyboard_interactivity);
+    })
+}
+
+/// # Safety
+///
+/// `namespace` must point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slint_layer_shell_set_namespace(namespace: *const c_char) -> bool {
+    match unsafe { c_str(namespace) } {
+        Some(namespace) => update_default_layer_config(|config| config.namespace = namespace),
+        None => false,
+    }
+}
+
+/// Runs the event loop until it quits. Returns `false` if the platform is not installed or
+/// the loop failed.
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_run() -> bool {
+    let Some(platform) = with_platform(SlintLayerShell::clone) else {
+        return false;
+    };
+    match platform.run_event_loop_with(|_| {}) {
+        Ok(()) => true,
+        Err(err) => {
+            log::error!("event loop failed: {err}");
+            false
+        }
+    }
+}
+
+/// Stops the event loop, recording `code` as the exit status.
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_quit(code: i32) {
+    with_platform(|platform| platform.quit_with_code(code));
+}
+
+/// Returns the exit status recorded by [`slint_layer_shell_quit`].
+#[unsafe(no_mangle)]
+pub extern "C" fn slint_layer_shell_exit_code() -> i32 {
+    with_platform(SlintLayerShell::exit_code).unwrap_or(0)
+}
+
+/// Applies `update` to the default layer configuration, starting from the defaults if new
+/// windows are toplevels.
+fn update_default_layer_config(update: impl FnOnce(&mut LayerConfig)) -> bool {
+    with_default_layer_config(|config| update(config.get_or_insert_with(LayerConfig::default)))
+}
+
+/// Runs `f` with the default layer configuration. Returns `false` if the platform is not
+/// installed or its state is borrowed, as panicking across the C ABI would abort.
+fn with_default_layer_config(f: impl FnOnce(&mut Option<LayerConfig>)) -> bool {
+    with_platform(|platform| {
+        let Ok(mut state) = platform.state.try_borrow_mut() else {
+            log::warn!("the default layer configuration cannot be changed during event dispatch");
+            return false;
+        };
+        f(&mut state.default_layer_config);
+        true
+    })
+    .unwrap_or(false)
+}
+
+fn layer_from_c(layer: u32) -> Layer {
+    match layer {
+        0 => Layer::Background,
+        1 => Layer::Bottom,
+        3 => Layer::Overlay,
+        _ => Layer::Top,
+    }
+}
+
+fn keyboard_interactivity_from_c(keyboard_interactivity: u32) -> KeyboardInteractivity {
+    match keyboard_interactivity {
+        1 => KeyboardInteractivity::Exclusive,
+        2 => KeyboardInteractivity::OnDemand,
+        _ => KeyboardInteractivity::None,
+    }
+}
+
+fn with_platform<R>(f: impl FnOnce(&SlintLayerShell) -> R) -> Option<R> {
+    PLATFORM.with(|cell| cell.borrow().as_ref().map(f))
+}
+
+/// # Safety
+///
+/// `string` must be null or point to a NUL-terminated string.
+unsafe fn c_str(string: *const c_char) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(string) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
@@ -0,0 +1,324 @@
+//! Windows of other applications, from `zwlr_foreign_toplevel_manager_v1`, e.g. for docks
+//! and taskbars.
+
+use crate::event::BackendEvent;
+use crate::platform::{LayerShellState, SlintLayerShell};
+use slint::{Model, ModelRc, VecModel};
+use std::path::PathBuf;
+use std::rc::Rc;
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::{Connection, Dispatch, QueueHandle, event_created_child};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+/// A toplevel window of any application, including this one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToplevelInfo {
+    pub handle: ZwlrForeignToplevelHandleV1,
+    pub title: String,
+    pub app_id: String,
+    pub activated: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Outputs the window is visible on.
+    pub outputs: Vec<WlOutput>,
+}
+
+impl ToplevelInfo {
+    fn new(handle: ZwlrForeignToplevelHandleV1) -> Self {
+        Self {
+            handle,
+            title: String::new(),
+            app_id: String::new(),
+            activated: false,
+            minimized: false,
+            maximized: false,
+            fullscreen: false,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Looks up the icon of the application in the `hicolor` theme, using the `Icon=` entry
+    /// of its desktop file when there is one. The result can be loaded with
+    /// [`slint::Image::load_from_path`].
+    pub fn icon_path(&self, size: u32) -> Option<PathBuf> {
+        find_icon(&self.app_id, size)
+    }
+}
+
+/// Toplevels as they are being described by the compositor, published on `done`.
+#[derive(Default)]
+pub(crate) struct ToplevelList {
+    pub(crate) toplevels: Vec<ToplevelInfo>,
+    pending: Vec<ToplevelInfo>,
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } = event {
+            state
+                .foreign_toplevels
+                .pending
+                .push(ToplevelInfo::new(toplevel));
+        }
+    }
+
+    event_created_child!(LayerShellState, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Event;
+
+        let list = &mut state.foreign_toplevels;
+        match event {
+            Event::Closed => {
+                list.pending.retain(|info| &info.handle != handle);
+                let len = list.toplevels.len();
+                list.toplevels.retain(|info| &info.handle != handle);
+                if list.toplevels.len() != len {
+                    state
+                        .backend_events
+                        .push_back(BackendEvent::ToplevelRemoved {
+                            handle: handle.clone(),
+                        });
+                }
+                handle.destroy();
+                return;
+            }
+            Event::Done => {
+                let Some(index) = list.pending.iter().position(|info| &info.handle == handle)
+                else {
+                    return;
+                };
+                let info = list.pending.remove(index);
+                let event = match list
+                    .toplevels
+                    .iter_mut()
+                    .find(|existing| existing.handle == *handle)
+                {
+                    Some(existing) => {
+                        *existing = info;
+                        BackendEvent::ToplevelUpdated {
+                            handle: handle.clone(),
+                        }
+                    }
+                    None => {
+                        list.toplevels.push(info);
+                        BackendEvent::ToplevelAdded {
+                            handle: handle.clone(),
+                        }
+                    }
+                };
+                state.backend_events.push_back(event);
+                return;
+            }
+            _ => {}
+        }
+
+        // Changes are accumulated on a copy until the compositor sends `done`.
+        if !list.pending.iter().any(|info| &info.handle == handle) {
+            let current = list
+                .toplevels
+                .iter()
+                .find(|info| &info.handle == handle)
+                .cloned()
+                .unwrap_or_else(|| ToplevelInfo::new(handle.clone()));
+            list.pending.push(current);
+        }
+        let Some(info) = list.pending.iter_mut().find(|info| &info.handle == handle) else {
+            return;
+        };
+        match event {
+            Event::Title { title } => info.title = title,
+            Event::AppId { app_id } => info.app_id = app_id,
+            Event::OutputEnter { output } => info.outputs.push(output),
+            Event::OutputLeave { output } => info.outputs.retain(|o| o != &output),
+            Event::State { state } => {
+                let states = state
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .map(zwlr_foreign_toplevel_handle_v1::State::try_from)
+                    .filter_map(Result::ok)
+                    .collect::<Vec<_>>();
+                info.activated =
+                    states.contains(&zwlr_foreign_toplevel_handle_v1::State::Activated);
+                info.minimized =
+                    states.contains(&zwlr_foreign_toplevel_handle_v1::State::Minimized);
+                info.maximized =
+                    states.contains(&zwlr_foreign_toplevel_handle_v1::State::Maximized);
+                info.fullscreen =
+                    states.contains(&zwlr_foreign_toplevel_handle_v1::State::Fullscreen);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A live model of the toplevels, with the actions a taskbar needs, addressed by row.
+///
+/// ```ignore
+/// let taskbar = Taskbar::new(&platform)?;
+/// ui.set_tasks(taskbar.model(|info| Task { title: info.title.as_str().into(), .. }));
+/// let weak = Rc::downgrade(&taskbar);
+/// ui.on_task_clicked(move |row| weak.upgrade().map(|t| t.activate_or_minimize(row as usize)));
+/// ```
+pub struct Taskbar {
+    platform: SlintLayerShell,
+    toplevels: Rc<VecModel<ToplevelInfo>>,
+}
+
+impl Taskbar {
+    pub fn new(platform: &SlintLayerShell) -> Result<Rc<Self>, slint::PlatformError> {
+        if !platform.foreign_toplevels_supported() {
+            return Err(slint::PlatformError::Other(
+                "zwlr_foreign_toplevel_manager_v1 is not available".into(),
+            ));
+        }
+
+        let this = Rc::new(Self {
+            platform: platform.clone(),
+            toplevels: Rc::new(VecModel::from(platform.toplevels())),
+        });
+
+        let weak = Rc::downgrade(&this);
+        platform.add_event_listener(move |event| {
+            let Some(this) = weak.upgrade() else {
+                return false;
+            };
+            if matches!(
+                event,
+                BackendEvent::ToplevelAdded { .. }
+                    | BackendEvent::ToplevelUpdated { .. }
+                    | BackendEvent::ToplevelRemoved { .. }
+            ) {
+                this.toplevels.set_vec(this.platform.toplevels());
+            }
+            true
+        });
+
+        Ok(this)
+    }
+
+    /// Returns the model of the toplevels converted with `map`.
+    pub fn model<T: Clone + 'static>(
+        &self,
+        map: impl Fn(&ToplevelInfo) -> T + 'static,
+    ) -> ModelRc<T> {
+        ModelRc::new(slint::ModelExt::map(self.toplevels.clone(), move |info| {
+            map(&info)
+        }))
+    }
+
+    pub fn get(&self, row: usize) -> Option<ToplevelInfo> {
+        self.toplevels.row_data(row)
+    }
+
+    pub fn activate(&self, row: usize) {
+        if let Some(info) = self.get(row) {
+            self.platform.activate_toplevel(&info.handle);
+        }
+    }
+
+    pub fn close(&self, row: usize) {
+        if let Some(info) = self.get(row) {
+            info.handle.close();
+        }
+    }
+
+    pub fn set_minimized(&self, row: usize, minimized: bool) {
+        if let Some(info) = self.get(row) {
+            if minimized {
+                info.handle.set_minimized();
+            } else {
+                info.handle.unset_minimized();
+            }
+        }
+    }
+
+    /// The usual taskbar click: minimizes the active window, activates any other one.
+    pub fn activate_or_minimize(&self, row: usize) {
+        match self.get(row) {
+            Some(info) if info.activated && !info.minimized => self.set_minimized(row, true),
+            Some(_) => self.activate(row),
+            None => {}
+        }
+    }
+}
+
+fn find_icon(app_id: &str, size: u32) -> Option<PathBuf> {
+    if app_id.is_empty() {
+        return None;
+    }
+    let data_dirs = data_dirs();
+
+    let icon = data_dirs
+        .iter()
+        .map(|dir| dir.join("applications").join(format!("{app_id}.desktop")))
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .and_then(|desktop| {
+            desktop.lines().find_map(|line| {
+                line.strip_prefix("Icon=")
+                    .map(|icon| icon.trim().to_string())
+            })
+        })
+        .unwrap_or_else(|| app_id.to_string());
+    if icon.starts_with('/') {
+        return Some(PathBuf::from(icon));
+    }
+
+    let sizes = [size, 256, 128, 96, 64, 48, 32, 24, 16];
+    for dir in &data_dirs {
+        let theme = dir.join("icons").join("hicolor");
+        for size in sizes {
+            let path = theme.join(format!("{size}x{size}/apps/{icon}.png"));
+            if path.exists() {
+                return Some(path);
+            }
+        }
+        let path = theme.join(format!("scalable/apps/{icon}.svg"));
+        if path.exists() {
+            return Some(path);
+        }
+        for extension in ["png", "svg"] {
+            let path = dir.join("pixmaps").join(format!("{icon}.{extension}"));
+            if path.exists() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+fn data_dirs() -> Vec<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .collect()
+}
@@ -0,0 +1,103 @@
+//! Opt-in rendering statistics, collected by the event loop once enabled with
+//! [`SlintLayerShell::enable_frame_stats`](crate::platform::SlintLayerShell::enable_frame_stats)
+//! or by setting `SLINT_LAYER_SHELL_FRAME_STATS=1` in the environment.
+
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Environment variable that enables frame statistics and logs them every second.
+pub const FRAME_STATS_ENV: &str = "SLINT_LAYER_SHELL_FRAME_STATS";
+
+/// Frames rendered across all windows during one reporting interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub frames: u32,
+    pub interval: Duration,
+    /// Frames per second over `interval`.
+    pub fps: f64,
+    /// Time spent in the renderer per frame, excluding the wait for frame callbacks.
+    pub average_render_time: Duration,
+    pub max_render_time: Duration,
+}
+
+pub(crate) struct FrameStatsCollector {
+    /// Logs every report at info level, rather than only handing it to the callback.
+    pub log: bool,
+    on_report: Option<Rc<dyn Fn(&FrameStats)>>,
+    interval_start: Instant,
+    frames: u32,
+    total_render_time: Duration,
+    max_render_time: Duration,
+    last_report: Option<FrameStats>,
+}
+
+impl FrameStatsCollector {
+    pub fn new(log: bool) -> Self {
+        Self {
+            log,
+            on_report: None,
+            interval_start: Instant::now(),
+            frames: 0,
+            total_render_time: Duration::ZERO,
+            max_render_time: Duration::ZERO,
+            last_report: None,
+        }
+    }
+
+    /// Returns a collector that logs its reports if enabled through [`FRAME_STATS_ENV`].
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(FRAME_STATS_ENV).ok()?;
+        matches!(value.as_str(), "1" | "true" | "yes").then(|| Self::new(true))
+    }
+
+    pub fn set_on_report(&mut self, callback: impl Fn(&FrameStats) + 'static) {
+        self.on_report = Some(Rc::new(callback));
+    }
+
+    pub fn last_report(&self) -> Option<FrameStats> {
+        self.last_report
+    }
+
+    pub fn record_frame(&mut self, render_time: Duration) {
+        self.frames += 1;
+        self.total_render_time += render_time;
+        self.max_render_time = self.max_render_time.max(render_time);
+    }
+
+    /// Closes the interval once a second has passed since it started, returning its
+    /// statistics and the callback to report them to. Idle intervals are not reported.
+    ///
+    /// The callback is returned rather than called, so that it runs without the collector
+    /// borrowed.
+    pub fn report_if_due(&mut self) -> Option<(FrameStats, Option<Rc<dyn Fn(&FrameStats)>>)> {
+        let interval = self.interval_start.elapsed();
+        if interval < Duration::from_secs(1) {
+            return None;
+        }
+        let mut report = None;
+        if self.frames > 0 {
+            let stats = FrameStats {
+                frames: self.frames,
+                interval,
+                fps: self.frames as f64 / interval.as_secs_f64(),
+                average_render_time: self.total_render_time / self.frames,
+                max_render_time: self.max_render_time,
+            };
+            if self.log {
+                log::info!(
+                    "{:.2} fps, render time {:.2?} average, {:.2?} max",
+                    stats.fps,
+                    stats.average_render_time,
+                    stats.max_render_time
+                );
+            }
+            self.last_report = Some(stats);
+            report = Some((stats, self.on_report.clone()));
+        }
+        self.interval_start = Instant::now();
+        self.frames = 0;
+        self.total_render_time = Duration::ZERO;
+        self.max_render_time = Duration::ZERO;
+        report
+    }
+}
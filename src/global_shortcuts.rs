@@ -0,0 +1,238 @@
+//! Desktop-wide keyboard shortcuts through the `org.freedesktop.portal.GlobalShortcuts`
+//! interface of xdg-desktop-portal, which works on any compositor with a portal backend
+//! implementing it.
+//!
+//! The portal session is set up on a background thread, and activations are delivered on the
+//! event loop thread.
+
+use crate::platform::SlintLayerShell;
+use slint::PlatformError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+const SERVICE: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+const INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// A shortcut to register with the portal.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shortcut {
+    /// Identifies the shortcut in [`GlobalShortcuts::on_activated`].
+    pub id: String,
+    /// Shown to the user when confirming or changing the shortcut.
+    pub description: String,
+    /// Trigger suggested to the user, in the XDG shortcuts format, e.g. `LOGO+space`.
+    pub preferred_trigger: Option<String>,
+}
+
+impl Shortcut {
+    pub fn new(id: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            description: description.into(),
+            preferred_trigger: None,
+        }
+    }
+
+    pub fn preferred_trigger(mut self, trigger: impl Into<String>) -> Self {
+        self.preferred_trigger = Some(trigger.into());
+        self
+    }
+}
+
+enum ShortcutEvent {
+    Activated(String),
+    Deactivated(String),
+}
+
+/// Global shortcuts registered with the portal for the lifetime of the process.
+///
+/// The portal may ask the user to confirm or change the triggers the first time.
+///
+/// ```ignore
+/// let shortcuts = GlobalShortcuts::register(
+///     &platform,
+///     vec![Shortcut::new("toggle", "Toggle the launcher").preferred_trigger("LOGO+space")],
+/// )?;
+/// shortcuts.on_activated(move |id| {
+///     if id == "toggle" {
+///         let _ = launcher.toggle();
+///     }
+/// });
+/// ```
+pub struct GlobalShortcuts {
+    on_activated: RefCell<Option<Box<dyn Fn(&str)>>>,
+    on_deactivated: RefCell<Option<Box<dyn Fn(&str)>>>,
+}
+
+impl GlobalShortcuts {
+    /// Creates a portal session and binds `shortcuts` to it.
+    ///
+    /// Errors while talking to the portal after connecting are logged as warnings, as the
+    /// session is set up asynchronously.
+    pub fn register(
+        platform: &SlintLayerShell,
+        shortcuts: Vec<Shortcut>,
+    ) -> Result<Rc<Self>, PlatformError> {
+        let connection = Connection::session()
+            .map_err(|e| PlatformError::Other(format!("failed to connect to D-Bus: {e}")))?;
+
+        let this = Rc::new(Self {
+            on_activated: RefCell::new(None),
+            on_deactivated: RefCell::new(None),
+        });
+
+        let (sender, channel) = calloop::channel::channel();
+        platform.channel_to_event_loop(channel, &this, "global shortcuts", |this, event| {
+            this.handle(event);
+        })?;
+
+        std::thread::spawn(move || {
+            if let Err(err) = run_session(&connection, &shortcuts, &sender) {
+                log::warn!("failed to register global shortcuts: {err}");
+            }
+        });
+
+        Ok(this)
+    }
+
+    /// Sets a callback invoked with the shortcut id when a shortcut is pressed.
+    pub fn on_activated(&self, callback: impl Fn(&str) + 'static) {
+        *self.on_activated.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Sets a callback invoked with the shortcut id when a shortcut is released.
+    pub fn on_deactivated(&self, callback: impl Fn(&str) + 'static) {
+        *self.on_deactivated.borrow_mut() = Some(Box::new(callback));
+    }
+
+    fn handle(&self, event: ShortcutEvent) {
+        let (callback, id) = match &event {
+            ShortcutEvent::Activated(id) => (&self.on_activated, id),
+            ShortcutEvent::Deactivated(id) => (&self.on_deactivated, id),
+        };
+        if let Some(callback) = callback.borrow().as_ref() {
+            callback(id);
+        }
+    }
+}
+
+fn run_session(
+    connection: &Connection,
+    shortcuts: &[Shortcut],
+    sender: &calloop::channel::Sender<ShortcutEvent>,
+) -> zbus::Result<()> {
+    let portal = Proxy::new(connection, SERVICE, PATH, INTERFACE)?;
+    // Subscribe before binding, so no activation is missed.
+    let signals = MessageIterator::for_match_rule(
+        zbus::MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(INTERFACE)?
+            .build(),
+        connection,
+        None,
+    )?;
+
+    let token = format!("slint_layer_shell_{}", std::process::id());
+    let results = request(connection, &token, || {
+        let options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("session_handle_token", Value::from(token.as_str())),
+        ]);
+        portal.call::<_, _, OwnedObjectPath>("CreateSession", &(options,))
+    })?;
+    // Older portals send the session handle as a string rather than an object path.
+    let session = match results.get("session_handle").map(|handle| &**handle) {
+        Some(Value::ObjectPath(path)) => OwnedObjectPath::from(path.to_owned()),
+        Some(Value::Str(path)) => OwnedObjectPath::try_from(path.as_str())?,
+        _ => {
+            return Err(zbus::Error::Failure(
+                "no session handle in the response".into(),
+            ));
+        }
+    };
+
+    let bind_token = format!("{token}_bind");
+    request(connection, &bind_token, || {
+        let shortcuts = shortcuts
+            .iter()
+            .map(|shortcut| {
+                let mut properties =
+                    HashMap::from([("description", Value::from(shortcut.description.as_str()))]);
+                if let Some(trigger) = &shortcut.preferred_trigger {
+                    properties.insert("preferred_trigger", Value::from(trigger.as_str()));
+                }
+                (shortcut.id.as_str(), properties)
+            })
+            .collect::<Vec<_>>();
+        let options = HashMap::from([("handle_token", Value::from(bind_token.as_str()))]);
+        portal.call::<_, _, OwnedObjectPath>("BindShortcuts", &(&session, shortcuts, "", options))
+    })?;
+
+    for message in signals.flatten() {
+        let Ok((message_session, id, _timestamp, _options)) =
+            message
+                .body()
+                .deserialize::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>()
+        else {
+            continue;
+        };
+        if message_session.as_str() != session.as_str() {
+            continue;
+        }
+        let event = match message.header().member().map(|member| member.as_str()) {
+            Some("Activated") => ShortcutEvent::Activated(id),
+            Some("Deactivated") => ShortcutEvent::Deactivated(id),
+            _ => continue,
+        };
+        if sender.send(event).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Makes a portal request with `handle_token` set to `token`, and waits for its response.
+fn request(
+    connection: &Connection,
+    token: &str,
+    call: impl FnOnce() -> zbus::Result<OwnedObjectPath>,
+) -> zbus::Result<HashMap<String, OwnedValue>> {
+    // The request path is predictable, so the response can be subscribed to before the call.
+    let unique_name = connection
+        .unique_name()
+        .ok_or_else(|| zbus::Error::Failure("no unique bus name".into()))?;
+    let sender = unique_name
+        .as_str()
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let path = format!("{PATH}/request/{sender}/{token}");
+    let mut responses = MessageIterator::for_match_rule(
+        zbus::MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(REQUEST_INTERFACE)?
+            .member("Response")?
+            .path(path.as_str())?
+            .build(),
+        connection,
+        None,
+    )?;
+
+    call()?;
+
+    let message = responses
+        .next()
+        .ok_or_else(|| zbus::Error::Failure("the portal did not respond".into()))??;
+    let (response, results) = message
+        .body()
+        .deserialize::<(u32, HashMap<String, OwnedValue>)>()?;
+    match response {
+        0 => Ok(results),
+        1 => Err(zbus::Error::Failure("cancelled by the user".into())),
+        _ => Err(zbus::Error::Failure("the portal request failed".into())),
+    }
+}
@@ -0,0 +1,150 @@
+//! Reloading `.slint` files at runtime with `slint-interpreter`.
+
+use crate::interpreter::{apply_layer_shell_properties, block_on, log_diagnostics};
+use slint::{ComponentHandle, PlatformError, Timer, TimerMode};
+use slint_interpreter::{CompilationResult, Compiler, ComponentDefinition, ComponentInstance};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+/// How often the source files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+type ReloadCallback = Box<dyn Fn(&ComponentInstance)>;
+
+/// Shows a component from a `.slint` file and re-instantiates it whenever the file, or one
+/// of the files it imports, changes.
+///
+/// The new instance takes over the window of the previous one, so the layer surface and its
+/// configuration are kept. Property values and callbacks are not carried over, set them up
+/// again in [`Self::on_reload`]. Compile errors are logged and the previous instance stays.
+pub struct HotReload {
+    path: PathBuf,
+    component: Option<String>,
+    include_paths: Vec<PathBuf>,
+    instance: RefCell<ComponentInstance>,
+    sources: RefCell<Vec<(PathBuf, Option<SystemTime>)>>,
+    on_reload: RefCell<Option<ReloadCallback>>,
+    timer: Timer,
+}
+
+impl HotReload {
+    /// Compiles `path` and shows `component`, or the last exported component if `None`.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        component: Option<&str>,
+        include_paths: Vec<PathBuf>,
+    ) -> Result<Rc<Self>, PlatformError> {
+        let path = path.into();
+        let result = compile(&path, &include_paths);
+        let definition = definition(&result, component)?;
+        let instance = definition
+            .create()
+            .map_err(|e| PlatformError::Other(e.to_string()))?;
+        apply_layer_shell_properties(&instance)?;
+        instance.show()?;
+
+        let this = Rc::new(Self {
+            path: path.clone(),
+            component: component.map(str::to_string),
+            include_paths,
+            instance: RefCell::new(instance),
+            sources: RefCell::new(sources(&result, &path)),
+            on_reload: RefCell::default(),
+            timer: Timer::default(),
+        });
+
+        let weak = Rc::downgrade(&this);
+        this.timer
+            .start(TimerMode::Repeated, POLL_INTERVAL, move || {
+                let Some(this) = weak.upgrade() else {
+                    return;
+                };
+                let changed = this
+                    .sources
+                    .borrow()
+                    .iter()
+                    .any(|(path, modified)| modified_time(path) != *modified);
+                if changed {
+                    if let Err(err) = this.reload() {
+                        log::warn!("failed to reload {}: {err}", this.path.display());
+                    }
+                }
+            });
+
+        Ok(this)
+    }
+
+    /// Returns the current instance, which changes with every reload.
+    pub fn instance(&self) -> ComponentInstance {
+        self.instance.borrow().clone_strong()
+    }
+
+    /// Calls `callback` with the initial instance, and with every new instance after a reload.
+    pub fn on_reload(&self, callback: impl Fn(&ComponentInstance) + 'static) {
+        callback(&self.instance.borrow());
+        *self.on_reload.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Compiles the file again and replaces the instance, keeping its window.
+    pub fn reload(&self) -> Result<(), PlatformError> {
+        let result = compile(&self.path, &self.include_paths);
+        // Track the files of failed builds too, so that fixing the error triggers a reload.
+        *self.sources.borrow_mut() = sources(&result, &self.path);
+        let definition = definition(&result, self.component.as_deref())?;
+
+        let instance = {
+            let previous = self.instance.borrow();
+            definition
+                .create_with_existing_window(previous.window())
+                .map_err(|e| PlatformError::Other(e.to_string()))?
+        };
+        drop(self.instance.replace(instance.clone_strong()));
+        apply_layer_shell_properties(&instance)?;
+        instance.show()?;
+
+        if let Some(callback) = self.on_reload.borrow().as_ref() {
+            callback(&instance);
+        }
+        Ok(())
+    }
+}
+
+fn compile(path: &Path, include_paths: &[PathBuf]) -> CompilationResult {
+    let mut compiler = Compiler::default();
+    compiler.set_include_paths(include_paths.to_vec());
+    let result = block_on(compiler.build_from_path(path));
+    log_diagnostics(&result);
+    result
+}
+
+fn definition(
+    result: &CompilationResult,
+    component: Option<&str>,
+) -> Result<ComponentDefinition, PlatformError> {
+    if result.has_errors() {
+        return Err(PlatformError::Other("compilation failed".into()));
+    }
+    match component {
+        Some(name) => result.component(name),
+        None => result.components().last(),
+    }
+    .ok_or_else(|| PlatformError::Other("no component to show".into()))
+}
+
+fn sources(result: &CompilationResult, path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    std::iter::once(path.to_path_buf())
+        .chain(result.dependencies().map(PathBuf::from))
+        .map(|path| {
+            let modified = modified_time(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,112 @@
+//! Layer surfaces for components loaded at runtime with `slint-interpreter`.
+//!
+//! Interpreted components can declare their layer surface properties as root properties
+//! prefixed with `layer-shell-`, which take precedence over the configuration they are
+//! created with:
+//!
+//! ```slint
+//! export component Bar inherits Window {
+//!     in property <string> layer-shell-layer: "top";
+//!     in property <string> layer-shell-anchor: "tlr";
+//!     in property <string> layer-shell-margin: "4,8,0,8";
+//!     in property <bool> layer-shell-auto-exclusive-zone: true;
+//!     in property <string> layer-shell-keyboard: "none";
+//! }
+//! ```
+//!
+//! The values use the format of the title hints of `LayerShellWindow`, see
+//! [`LayerConfig::apply_title_hints`]. `layer-shell-exclusive-zone` is a number.
+
+use crate::layer::{LayerConfig, TITLE_HINTS_PREFIX};
+use crate::platform::SlintLayerShell;
+use crate::window_ext::WindowExt;
+use slint::{ComponentHandle, PlatformError};
+use slint_interpreter::{
+    CompilationResult, ComponentDefinition, ComponentInstance, DiagnosticLevel, Value,
+};
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+/// Prefix of the root properties mapped to the layer configuration.
+pub const PROPERTY_PREFIX: &str = "layer-shell-";
+
+/// Keys of the title hints that can be declared as `layer-shell-` properties.
+const KEYS: &[&str] = &[
+    "layer",
+    "anchor",
+    "margin",
+    "exclusive-zone",
+    "auto-exclusive-zone",
+    "keyboard",
+];
+
+/// Creates an instance of `definition` as a layer surface with `config`, overridden by the
+/// `layer-shell-` properties the component declares. The instance is not shown yet.
+pub fn instantiate(
+    platform: &SlintLayerShell,
+    definition: &ComponentDefinition,
+    config: LayerConfig,
+) -> Result<ComponentInstance, PlatformError> {
+    let instance = platform
+        .with_layer_config(config, || definition.create())
+        .map_err(|e| PlatformError::Other(e.to_string()))?;
+    apply_layer_shell_properties(&instance)?;
+    Ok(instance)
+}
+
+/// Applies the current values of the `layer-shell-` properties of `instance` to its layer
+/// surface, e.g. after changing them with `set_property`. Does nothing for windows that are
+/// not layer surfaces.
+pub fn apply_layer_shell_properties(instance: &ComponentInstance) -> Result<(), PlatformError> {
+    let window = instance.window();
+    let Some(mut config) = window.layer_config() else {
+        return Ok(());
+    };
+    if config.apply_title_hints(&title_hints(instance))? {
+        window.set_layer_config(config)?;
+    }
+    Ok(())
+}
+
+/// Encodes the declared `layer-shell-` properties like `LayerShellWindow` encodes its title.
+fn title_hints(instance: &ComponentInstance) -> String {
+    let hints = KEYS
+        .iter()
+        .filter_map(|key| {
+            let value = match instance
+                .get_property(&format!("{PROPERTY_PREFIX}{key}"))
+                .ok()?
+            {
+                Value::String(value) => value.to_string(),
+                Value::Number(value) => value.round().to_string(),
+                Value::Bool(value) => if value { "1" } else { "0" }.to_string(),
+                _ => return None,
+            };
+            Some(format!("{key}={value}"))
+        })
+        .collect::<Vec<_>>();
+    format!("{TITLE_HINTS_PREFIX}{}", hints.join(";"))
+}
+
+/// Drives the compiler future, which does not wait on anything with the default file loader.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Logs the diagnostics of a compilation, which `print_diagnostics` would write to stderr.
+pub(crate) fn log_diagnostics(result: &CompilationResult) {
+    for diagnostic in result.diagnostics() {
+        match diagnostic.level() {
+            DiagnosticLevel::Error => log::error!("{diagnostic}"),
+            DiagnosticLevel::Warning => log::warn!("{diagnostic}"),
+            _ => log::info!("{diagnostic}"),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::error::SurfaceError;
+use slint::PlatformError;
+pub use smithay_client_toolkit::shell::wlr_layer::{Anchor, KeyboardInteractivity, Layer};
+use wayland_client::protocol::wl_output::WlOutput;
+
+/// Window titles starting with this prefix carry layer surface properties, as set by the
+/// `LayerShellWindow` component in `ui/layer-shell.slint`.
+pub const TITLE_HINTS_PREFIX: &str = "layer-shell:";
+
+/// Describes how a window is mapped as a `zwlr_layer_surface_v1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerConfig {
+    pub layer: Layer,
+    pub anchor: Anchor,
+    /// Requested size in logical pixels. `None` uses the preferred size of the Slint window.
+    ///
+    /// A width or height of 0 lets the compositor pick it, which needs the surface to be
+    /// anchored to both opposite edges of that axis, see [`LayerConfig::validate`]. `None`
+    /// does the same for stretched axes. The size picked by the compositor reaches the
+    /// Slint window as a `Resized` event.
+    ///
+    /// Sizes, margins and exclusive zones are sent to the compositor in logical pixels, which
+    /// it scales with the output, so an 8 pixel margin has the same apparent size at every
+    /// scale.
+    pub size: Option<(u32, u32)>,
+    /// Margins from the anchored edges in logical pixels.
+    pub margins: Margins,
+    /// How fractional logical sizes, e.g. the preferred size of the content, are rounded to
+    /// the whole logical pixels layer surfaces are sized in.
+    pub rounding: Rounding,
+    /// Space reserved along the anchored edge in logical pixels, so other surfaces do not
+    /// overlap it. 0 moves the surface out of the zones reserved by others, and
+    /// [`LayerConfig::IGNORE_EXCLUSIVE_ZONES`] extends it below them.
+    pub exclusive_zone: i32,
+    /// Keeps the exclusive zone equal to the window's thickness along its anchored edge,
+    /// overriding `exclusive_zone`.
+    pub auto_exclusive_zone: bool,
+    pub keyboard_interactivity: KeyboardInteractivity,
+    /// Sets an empty input region, so pointer and touch input goes to the surfaces below.
+    pub pass_through_input: bool,
+    /// Limits the input region to the parts of each frame that are not fully transparent, so
+    /// clicks elsewhere go to the surfaces below. Frames with changed content are rendered a
+    /// second time to read their alpha channel, so this suits overlays that rarely change.
+    pub click_through_transparent: bool,
+    pub namespace: String,
+    /// Output to place the surface on. `None` lets the compositor decide.
+    pub output: Option<WlOutput>,
+}
+
+impl LayerConfig {
+    /// Exclusive zone extending the surface below the zones reserved by other surfaces, e.g.
+    /// for full screen overlays covering panels.
+    pub const IGNORE_EXCLUSIVE_ZONES: i32 = -1;
+
+    /// Returns whether the surface is stretched horizontally and vertically by being anchored
+    /// to both opposite edges.
+    pub fn stretched(&self) -> (bool, bool) {
+        (
+            self.anchor.contains(Anchor::LEFT | Anchor::RIGHT),
+            self.anchor.contains(Anchor::TOP | Anchor::BOTTOM),
+        )
+    }
+
+    /// Changes the anchors, turning a fixed size around when the surface is stretched along
+    /// the other axis afterwards, so a horizontal bar can move to a vertical edge.
+    pub fn set_anchor(&mut self, anchor: Anchor) {
+        let previous = self.stretched();
+        self.anchor = anchor;
+        let stretched = self.stretched();
+        if let Some((width, height)) = self.size {
+            if previous != stretched && stretched == (previous.1, previous.0) {
+                self.size = Some((height, width));
+            }
+        }
+    }
+
+    /// Checks that the compositor accepts the configuration. A size of 0 along an axis asks
+    /// the compositor to stretch the surface, which needs anchors on both edges.
+    pub fn validate(&self) -> Result<(), SurfaceError> {
+        let (stretch_width, stretch_height) = self.stretched();
+        match self.size {
+            Some((0, _)) if !stretch_width => Err(SurfaceError::ZeroWidth),
+            Some((_, 0)) if !stretch_height => Err(SurfaceError::ZeroHeight),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the `key=value;...` properties encoded in a window title by `LayerShellWindow`.
+    ///
+    /// Returns whether the configuration changed. Titles without [`TITLE_HINTS_PREFIX`] and
+    /// unknown keys are ignored. Malformed values fail without changing the configuration, as
+    /// do values containing the `;` separator, which cannot be escaped.
+    pub fn apply_title_hints(&mut self, title: &str) -> Result<bool, PlatformError> {
+        let Some(hints) = title.strip_prefix(TITLE_HINTS_PREFIX) else {
+            return Ok(false);
+        };
+        let mut config = self.clone();
+        for hint in hints.split(';').filter(|hint| !hint.is_empty()) {
+            let invalid = || PlatformError::Other(format!("invalid title hint {hint:?}"));
+            let Some((key, value)) = hint.split_once('=') else {
+                return Err(PlatformError::Other(format!(
+                    "title hint {hint:?} has no value, values cannot contain ';'"
+                )));
+            };
+            match key {
+                "layer" => {
+                    config.layer = match value {
+                        "background" => Layer::Background,
+                        "bottom" => Layer::Bottom,
+                        "top" => Layer::Top,
+                        "overlay" => Layer::Overlay,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "anchor" => {
+                    config.anchor = value.chars().try_fold(Anchor::empty(), |anchor, edge| {
+                        Ok(anchor
+                            | match edge {
+                                't' => Anchor::TOP,
+                                'b' => Anchor::BOTTOM,
+                                'l' => Anchor::LEFT,
+                                'r' => Anchor::RIGHT,
+                                _ => return Err(invalid()),
+                            })
+                    })?
+                }
+                "margin" => {
+                    let margins = value
+                        .split(',')
+                        .map(|margin| margin.parse::<f32>().map(|margin| margin.round() as i32))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid())?;
+                    let [top, right, bottom, left] = margins[..] else {
+                        return Err(invalid());
+                    };
+                    config.margins = Margins::new(top, right, bottom, left);
+                }
+                "namespace" if !value.is_empty() => config.namespace = value.into(),
+                "namespace" => {}
+                "exclusive-zone" => config.exclusive_zone = value.parse().map_err(|_| invalid())?,
+                "auto-exclusive-zone" => {
+                    config.auto_exclusive_zone = match value {
+                        "1" => true,
+                        "0" => false,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "keyboard" => {
+                    config.keyboard_interactivity = match value {
+                        "none" => KeyboardInteractivity::None,
+                        "exclusive" => KeyboardInteractivity::Exclusive,
+                        "on-demand" => KeyboardInteractivity::OnDemand,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        let changed = config != *self;
+        *self = config;
+        Ok(changed)
+    }
+
+    /// Returns the exclusive zone to request for a surface of `size` logical pixels.
+    pub fn effective_exclusive_zone(&self, size: (f32, f32)) -> i32 {
+        if !self.auto_exclusive_zone {
+            return self.exclusive_zone;
+        }
+        match self.stretched() {
+            (true, false) => self.rounding.apply(size.1) as i32,
+            (false, true) => self.rounding.apply(size.0) as i32,
+            _ => self.exclusive_zone,
+        }
+    }
+}
+
+impl Default for LayerConfig {
+    fn default() -> Self {
+        Self {
+            layer: Layer::Top,
+            anchor: Anchor::empty(),
+            size: None,
+            margins: Margins::ZERO,
+            rounding: Rounding::default(),
+            exclusive_zone: 0,
+            auto_exclusive_zone: false,
+            keyboard_interactivity: KeyboardInteractivity::None,
+            pass_through_input: false,
+            click_through_transparent: false,
+            namespace: "slint-layer-shell".into(),
+            output: None,
+        }
+    }
+}
+
+/// Rounding of fractional logical sizes, see [`LayerConfig::rounding`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Rounds up, so the content is never clipped.
+    #[default]
+    Ceil,
+    Round,
+    /// Rounds down, so the surface never takes more space than the content.
+    Floor,
+}
+
+impl Rounding {
+    pub fn apply(self, value: f32) -> f32 {
+        match self {
+            Self::Ceil => value.ceil(),
+            Self::Round => value.round(),
+            Self::Floor => value.floor(),
+        }
+    }
+}
+
+/// Distances of a layer surface from the edges it is anchored to, in logical pixels.
+///
+/// Margins along edges the surface is not anchored to are ignored by the compositor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Margins {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl Margins {
+    pub const ZERO: Self = Self::uniform(0);
+
+    pub const fn new(top: i32, right: i32, bottom: i32, left: i32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub const fn uniform(margin: i32) -> Self {
+        Self::new(margin, margin, margin, margin)
+    }
+
+    /// Margins on the left and right edges only.
+    pub const fn horizontal(margin: i32) -> Self {
+        Self::new(0, margin, 0, margin)
+    }
+
+    /// Margins on the top and bottom edges only.
+    pub const fn vertical(margin: i32) -> Self {
+        Self::new(margin, 0, margin, 0)
+    }
+
+    pub const fn symmetric(vertical: i32, horizontal: i32) -> Self {
+        Self::new(vertical, horizontal, vertical, horizontal)
+    }
+}
+
+/// Converts from `[top, right, bottom, left]`.
+impl From<[i32; 4]> for Margins {
+    fn from([top, right, bottom, left]: [i32; 4]) -> Self {
+        Self::new(top, right, bottom, left)
+    }
+}
+
+/// Converts from `(top, right, bottom, left)`.
+impl From<(i32, i32, i32, i32)> for Margins {
+    fn from((top, right, bottom, left): (i32, i32, i32, i32)) -> Self {
+        Self::new(top, right, bottom, left)
+    }
+}
+
+/// What happens to a layer surface whose output is unplugged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputRemovalPolicy {
+    /// Leave it to the compositor, which closes the surface.
+    #[default]
+    Close,
+    /// Re-create the surface on the output picked by the output chooser, or on the one the
+    /// compositor picks if no chooser is registered.
+    Migrate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar() -> LayerConfig {
+        LayerConfig {
+            anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+            size: Some((0, 32)),
+            ..LayerConfig::default()
+        }
+    }
+
+    #[test]
+    fn validate_needs_both_edges_for_a_zero_size() {
+        assert!(bar().validate().is_ok());
+        assert!(LayerConfig::default().validate().is_ok());
+        let config = LayerConfig {
+            anchor: Anchor::TOP,
+            ..bar()
+        };
+        assert_eq!(config.validate(), Err(SurfaceError::ZeroWidth));
+        let config = LayerConfig {
+            size: Some((32, 0)),
+            ..bar()
+        };
+        assert_eq!(config.validate(), Err(SurfaceError::ZeroHeight));
+    }
+
+    #[test]
+    fn set_anchor_turns_the_size_around_between_edges() {
+        let mut config = bar();
+        config.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM);
+        assert_eq!(config.size, Some((32, 0)));
+        config.set_anchor(Anchor::RIGHT | Anchor::TOP | Anchor::BOTTOM);
+        assert_eq!(config.size, Some((32, 0)));
+        config.set_anchor(Anchor::TOP);
+        assert_eq!(config.size, Some((32, 0)));
+    }
+
+    #[test]
+    fn apply_title_hints_reads_every_property() {
+        let mut config = LayerConfig::default();
+        let title = "layer-shell:layer=overlay;anchor=tlr;margin=1,2.4,3.6,4;exclusive-zone=-1;\
+                     auto-exclusive-zone=1;keyboard=on-demand;namespace=test-bar";
+        assert!(config.apply_title_hints(title).unwrap());
+        assert_eq!(
+            config,
+            LayerConfig {
+                layer: Layer::Overlay,
+                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                margins: Margins::new(1, 2, 4, 4),
+                exclusive_zone: -1,
+                auto_exclusive_zone: true,
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                namespace: "test-bar".into(),
+                ..LayerConfig::default()
+            }
+        );
+        assert!(!config.apply_title_hints(title).unwrap());
+
+        // An empty namespace keeps the current one, unknown keys are skipped.
+        assert!(
+            config
+                .apply_title_hints("layer-shell:namespace=;blur=1;layer=top")
+                .unwrap()
+        );
+        assert_eq!(config.namespace, "test-bar");
+        assert_eq!(config.layer, Layer::Top);
+        assert!(!config.apply_title_hints("Bar").unwrap());
+    }
+
+    #[test]
+    fn apply_title_hints_rejects_malformed_values() {
+        for hints in [
+            "layer=middle",
+            "anchor=tx",
+            "margin=1,2,3",
+            "margin=1,2,three,4",
+            "exclusive-zone=wide",
+            "auto-exclusive-zone=yes",
+            "keyboard=always",
+            "namespace=a;b",
+        ] {
+            let mut config = bar();
+            let title = format!("{TITLE_HINTS_PREFIX}layer=overlay;{hints}");
+            assert!(config.apply_title_hints(&title).is_err(), "{hints}");
+            assert_eq!(config, bar(), "{hints}");
+        }
+    }
+
+    #[test]
+    fn effective_exclusive_zone_follows_the_thickness() {
+        let mut config = LayerConfig {
+            exclusive_zone: 10,
+            ..bar()
+        };
+        assert_eq!(config.effective_exclusive_zone((800.0, 31.5)), 10);
+        config.auto_exclusive_zone = true;
+        assert_eq!(config.effective_exclusive_zone((800.0, 31.5)), 32);
+        config.rounding = Rounding::Floor;
+        assert_eq!(config.effective_exclusive_zone((800.0, 31.5)), 31);
+        config.set_anchor(Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM);
+        assert_eq!(config.effective_exclusive_zone((48.7, 600.0)), 48);
+        // Anchored to a corner, there is no edge to reserve space along.
+        config.anchor = Anchor::TOP | Anchor::LEFT;
+        assert_eq!(config.effective_exclusive_zone((48.7, 600.0)), 10);
+    }
+}
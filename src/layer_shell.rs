@@ -0,0 +1,367 @@
+//! The layer shell protocols: the proposed `ext_layer_shell_v1`, preferred when the
+//! compositor advertises it, and `zwlr_layer_shell_v1` otherwise.
+//!
+//! Both protocols offer the same requests and events, `ext_layer_shell_v1` version 1 being
+//! `zwlr_layer_shell_v1` version 5 under a new name, so windows use either through
+//! [`LayerSurface`].
+//!
+//! Unlike sctk's layer surfaces, which destroy their `wl_surface` along with themselves,
+//! a [`LayerSurface`] only owns its role object: windows replace it on the same `wl_surface`
+//! when it moves to another output.
+
+use crate::platform::LayerShellState;
+use smithay_client_toolkit::reexports::protocols_wlr::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::{self, ZwlrLayerShellV1},
+    zwlr_layer_surface_v1::{self, ZwlrLayerSurfaceV1},
+};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::{Anchor, KeyboardInteractivity, Layer};
+use std::sync::{Arc, Weak};
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::{wl_output::WlOutput, wl_surface::WlSurface};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+
+/// Client bindings of `ext_layer_shell_v1`, generated from the vendored
+/// `protocols/ext-layer-shell-v1.xml` until `wayland-protocols` ships the protocol.
+#[allow(missing_docs, clippy::all)]
+#[allow(dead_code, non_camel_case_types, unused_unsafe, unused_variables)]
+#[allow(non_upper_case_globals, non_snake_case, unused_imports)]
+pub mod ext {
+    use smithay_client_toolkit::reexports::protocols::xdg::shell::client::*;
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::protocols::xdg::shell::client::__interfaces::*;
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/ext-layer-shell-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/ext-layer-shell-v1.xml");
+}
+
+use ext::ext_layer_shell_v1::{self, ExtLayerShellV1};
+use ext::ext_layer_surface_v1::{self, ExtLayerSurfaceV1};
+
+/// The layer shell global layer surfaces are created with.
+#[derive(Debug)]
+pub enum LayerShell {
+    Ext(ExtLayerShellV1),
+    Wlr(ZwlrLayerShellV1),
+}
+
+impl LayerShell {
+    /// Binds `ext_layer_shell_v1` if the compositor advertises it, and `zwlr_layer_shell_v1`
+    /// otherwise.
+    pub(crate) fn bind(
+        globals: &GlobalList,
+        qh: &QueueHandle<LayerShellState>,
+    ) -> Result<Self, BindError> {
+        if let Ok(layer_shell) = globals.bind(qh, 1..=1, ()) {
+            return Ok(Self::Ext(layer_shell));
+        }
+        globals.bind(qh, 1..=4, ()).map(Self::Wlr)
+    }
+
+    /// Returns the name of the bound interface.
+    pub fn interface(&self) -> &'static str {
+        match self {
+            Self::Ext(_) => "ext_layer_shell_v1",
+            Self::Wlr(_) => "zwlr_layer_shell_v1",
+        }
+    }
+
+    /// Returns the version the global was bound at.
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::Ext(layer_shell) => layer_shell.version(),
+            Self::Wlr(layer_shell) => layer_shell.version(),
+        }
+    }
+
+    /// Returns the version of `zwlr_layer_shell_v1` offering the same requests, which the
+    /// checks for optional requests compare against.
+    pub(crate) fn wlr_version(&self) -> u32 {
+        match self {
+            Self::Ext(_) => 5,
+            Self::Wlr(layer_shell) => layer_shell.version(),
+        }
+    }
+
+    /// Gives `surface` the layer surface role. The surface outlives the returned layer
+    /// surface, its owner destroys it.
+    pub(crate) fn create_layer_surface(
+        &self,
+        qh: &QueueHandle<LayerShellState>,
+        surface: WlSurface,
+        layer: Layer,
+        namespace: String,
+        output: Option<&WlOutput>,
+    ) -> LayerSurface {
+        // Events of the new layer surface are only dispatched once it can be found through
+        // its data.
+        let freeze = qh.freeze();
+        let inner = Arc::new_cyclic(|weak| {
+            let data = LayerSurfaceData(weak.clone());
+            let role = match self {
+                Self::Ext(layer_shell) => LayerSurfaceRole::Ext(layer_shell.get_layer_surface(
+                    &surface,
+                    output,
+                    ext_layer(layer),
+                    namespace,
+                    qh,
+                    data,
+                )),
+                Self::Wlr(layer_shell) => LayerSurfaceRole::Wlr(layer_shell.get_layer_surface(
+                    &surface,
+                    output,
+                    layer.into(),
+                    namespace,
+                    qh,
+                    data,
+                )),
+            };
+            LayerSurfaceInner {
+                wl_surface: surface,
+                role,
+            }
+        });
+        drop(freeze);
+        LayerSurface(inner)
+    }
+}
+
+/// A layer surface of either protocol, with the requests windows make on it. The role
+/// object is destroyed along with the last clone.
+#[derive(Clone, Debug)]
+pub struct LayerSurface(Arc<LayerSurfaceInner>);
+
+/// The role object of a [`LayerSurface`], of the protocol it was created with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayerSurfaceRole {
+    Ext(ExtLayerSurfaceV1),
+    Wlr(ZwlrLayerSurfaceV1),
+}
+
+impl LayerSurface {
+    pub fn role(&self) -> &LayerSurfaceRole {
+        &self.0.role
+    }
+
+    pub fn set_size(&self, width: u32, height: u32) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => ext.set_size(width, height),
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_size(width, height),
+        }
+    }
+
+    pub fn set_anchor(&self, anchor: Anchor) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => ext.set_anchor(
+                ext_layer_surface_v1::Anchor::from_bits_truncate(anchor.bits()),
+            ),
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_anchor(
+                zwlr_layer_surface_v1::Anchor::from_bits_truncate(anchor.bits()),
+            ),
+        }
+    }
+
+    pub fn set_exclusive_zone(&self, zone: i32) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => ext.set_exclusive_zone(zone),
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_exclusive_zone(zone),
+        }
+    }
+
+    pub fn set_margin(&self, top: i32, right: i32, bottom: i32, left: i32) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => ext.set_margin(top, right, bottom, left),
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_margin(top, right, bottom, left),
+        }
+    }
+
+    pub fn set_keyboard_interactivity(&self, value: KeyboardInteractivity) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => {
+                ext.set_keyboard_interactivity(ext_keyboard_interactivity(value))
+            }
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_keyboard_interactivity(value.into()),
+        }
+    }
+
+    pub fn set_layer(&self, layer: Layer) {
+        match self.role() {
+            LayerSurfaceRole::Ext(ext) => ext.set_layer(ext_layer(layer)),
+            LayerSurfaceRole::Wlr(wlr) => wlr.set_layer(layer.into()),
+        }
+    }
+
+    /// Destroys the role object, which unmaps the surface, before the last clone of the
+    /// layer surface is dropped.
+    pub(crate) fn destroy_role(&self) {
+        self.0.role.destroy();
+    }
+}
+
+impl WaylandSurface for LayerSurface {
+    fn wl_surface(&self) -> &WlSurface {
+        &self.0.wl_surface
+    }
+}
+
+impl PartialEq for LayerSurface {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl LayerSurfaceRole {
+    fn destroy(&self) {
+        // Requests to an object destroyed already are ignored.
+        match self {
+            Self::Ext(ext) => ext.destroy(),
+            Self::Wlr(wlr) => wlr.destroy(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LayerSurfaceInner {
+    wl_surface: WlSurface,
+    role: LayerSurfaceRole,
+}
+
+impl Drop for LayerSurfaceInner {
+    fn drop(&mut self) {
+        self.role.destroy();
+    }
+}
+
+/// Finds the [`LayerSurface`] of a role object in its events.
+#[derive(Debug)]
+pub struct LayerSurfaceData(Weak<LayerSurfaceInner>);
+
+/// A request of the compositor to resize a layer surface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerSurfaceConfigure {
+    /// Suggested size in surface-local coordinates. A width or height of 0 lets the client
+    /// pick.
+    pub new_size: (u32, u32),
+}
+
+// sctk's enums are non-exhaustive, the wildcards stand for the remaining values of the
+// protocols.
+fn ext_layer(layer: Layer) -> ext_layer_shell_v1::Layer {
+    match layer {
+        Layer::Background => ext_layer_shell_v1::Layer::Background,
+        Layer::Bottom => ext_layer_shell_v1::Layer::Bottom,
+        Layer::Overlay => ext_layer_shell_v1::Layer::Overlay,
+        _ => ext_layer_shell_v1::Layer::Top,
+    }
+}
+
+fn ext_keyboard_interactivity(
+    value: KeyboardInteractivity,
+) -> ext_layer_surface_v1::KeyboardInteractivity {
+    match value {
+        KeyboardInteractivity::Exclusive => ext_layer_surface_v1::KeyboardInteractivity::Exclusive,
+        KeyboardInteractivity::OnDemand => ext_layer_surface_v1::KeyboardInteractivity::OnDemand,
+        _ => ext_layer_surface_v1::KeyboardInteractivity::None,
+    }
+}
+
+impl LayerShellState {
+    fn layer_surface_event(
+        &mut self,
+        data: &LayerSurfaceData,
+        configure: Option<LayerSurfaceConfigure>,
+    ) {
+        let Some(inner) = data.0.upgrade() else {
+            return;
+        };
+        let layer = LayerSurface(inner);
+        match configure {
+            Some(configure) => self.layer_surface_configured(&layer, configure),
+            None => self.layer_surface_closed(&layer),
+        }
+    }
+}
+
+impl Dispatch<ExtLayerShellV1, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _layer_shell: &ExtLayerShellV1,
+        _event: ext_layer_shell_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtLayerSurfaceV1, LayerSurfaceData> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        layer_surface: &ExtLayerSurfaceV1,
+        event: ext_layer_surface_v1::Event,
+        data: &LayerSurfaceData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_layer_surface_v1::Event::Configure {
+                serial,
+                width,
+                height,
+            } => {
+                layer_surface.ack_configure(serial);
+                let configure = LayerSurfaceConfigure {
+                    new_size: (width, height),
+                };
+                state.layer_surface_event(data, Some(configure));
+            }
+            ext_layer_surface_v1::Event::Closed => state.layer_surface_event(data, None),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrLayerShellV1, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _layer_shell: &ZwlrLayerShellV1,
+        _event: zwlr_layer_shell_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrLayerSurfaceV1, LayerSurfaceData> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        layer_surface: &ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        data: &LayerSurfaceData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_layer_surface_v1::Event::Configure {
+                serial,
+                width,
+                height,
+            } => {
+                layer_surface.ack_configure(serial);
+                let configure = LayerSurfaceConfigure {
+                    new_size: (width, height),
+                };
+                state.layer_surface_event(data, Some(configure));
+            }
+            zwlr_layer_surface_v1::Event::Closed => state.layer_surface_event(data, None),
+            _ => {}
+        }
+    }
+}
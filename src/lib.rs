@@ -1,3 +1,74 @@
+/// Enters a `tracing` span when the `tracing` feature is enabled, and does nothing otherwise.
+///
+/// The returned guard must be kept alive for the duration of the instrumented work.
+macro_rules! trace_span {
+    ($($args:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        let guard = tracing::trace_span!($($args)*).entered();
+        #[cfg(not(feature = "tracing"))]
+        let guard = ();
+        guard
+    }};
+}
+
+/// Directory containing the `.slint` files shipped with this crate, to be added to the
+/// include paths of `slint-build`.
+pub const SLINT_INCLUDE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/ui");
+
+pub mod animation;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod builder;
+pub mod capabilities;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+#[cfg(feature = "compositor-ipc")]
+pub mod compositor_ipc;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(any(feature = "notifications", feature = "portal", feature = "tray"))]
+mod dbus;
 mod delegates;
+pub mod error;
+pub mod event;
+pub mod extension;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "foreign-toplevel")]
+pub mod foreign_toplevel;
+pub mod frame_stats;
+#[cfg(feature = "global-shortcuts")]
+pub mod global_shortcuts;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+#[cfg(feature = "interpreter")]
+pub mod interpreter;
+pub mod layer;
+pub mod layer_shell;
+#[cfg(feature = "logind")]
+pub mod logind;
+#[cfg(feature = "notifications")]
+pub mod notifications;
+pub mod output;
+pub mod per_output;
 pub mod platform;
+#[cfg(feature = "portal")]
+pub mod portal;
+pub mod prelude;
+pub mod presets;
+pub mod quirks;
+#[cfg(feature = "recording")]
+pub mod recording;
+#[cfg(feature = "screencopy")]
+pub mod screencopy;
+mod systemd;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "text-input")]
+mod text_input;
+#[cfg(feature = "tray")]
+pub mod tray;
+#[cfg(feature = "virtual-keyboard")]
+pub mod virtual_keyboard;
 pub mod window_adapter;
+pub mod window_ext;
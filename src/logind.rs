@@ -0,0 +1,297 @@
+//! Integration with systemd-logind on the system bus: lock requests such as
+//! `loginctl lock-session`, and delaying suspend until the lock screen is shown.
+//!
+//! Signals are received on a background thread and handled on the event loop thread.
+
+#[cfg(feature = "session-lock")]
+use crate::event::BackendEvent;
+#[cfg(feature = "session-lock")]
+use crate::output::OutputInfo;
+use crate::platform::SlintLayerShell;
+#[cfg(feature = "session-lock")]
+use crate::presets::LockScreen;
+#[cfg(feature = "session-lock")]
+use slint::ComponentHandle;
+use slint::PlatformError;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::zvariant::{OwnedFd, OwnedObjectPath};
+
+const SERVICE: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogindEvent {
+    /// The session should be locked, e.g. through `loginctl lock-session`.
+    Lock,
+    /// The session should be unlocked, e.g. through `loginctl unlock-session`.
+    Unlock,
+    /// The system is about to suspend or hibernate. Suspending is delayed until the sleep
+    /// inhibitor is released, or logind's `InhibitDelayMaxSec` passes.
+    PrepareForSleep,
+    /// The system resumed from suspend, the sleep inhibitor has been taken again.
+    Resumed,
+}
+
+/// A connection to logind for the session this process runs in.
+///
+/// A delay inhibitor for sleep is held while connected. When the session-lock feature is
+/// enabled, the inhibitor is released as soon as the compositor confirms a session lock, so
+/// the lock screen is shown before the system suspends. Otherwise call
+/// [`Logind::release_sleep_inhibitor`] once ready to suspend.
+///
+/// ```ignore
+/// let logind = Logind::connect(&platform)?;
+/// let requests = logind.lock_on_request(&platform, |_output| Prompt::new());
+/// prompt.on_accepted(move || requests.unlock());
+/// ```
+pub struct Logind {
+    manager: Proxy<'static>,
+    session: Proxy<'static>,
+    sleep_inhibitor: RefCell<Option<OwnedFd>>,
+    preparing_for_sleep: Cell<bool>,
+    on_event: RefCell<Option<Box<dyn Fn(LogindEvent)>>>,
+    lock_handler: RefCell<Option<Box<dyn Fn(LogindEvent)>>>,
+}
+
+impl Logind {
+    /// Connects to logind, takes the sleep inhibitor and starts listening for its signals.
+    pub fn connect(platform: &SlintLayerShell) -> Result<Rc<Self>, PlatformError> {
+        let connection = Connection::system()
+            .map_err(|e| PlatformError::Other(format!("failed to connect to D-Bus: {e}")))?;
+        let manager = Proxy::new(&connection, SERVICE, MANAGER_PATH, MANAGER_INTERFACE)
+            .map_err(|e| PlatformError::Other(format!("failed to reach logind: {e}")))?;
+        let session_path = session_path(&manager)
+            .map_err(|e| PlatformError::Other(format!("failed to find the logind session: {e}")))?;
+        let session = Proxy::new(
+            &connection,
+            SERVICE,
+            session_path.clone().into_inner(),
+            SESSION_INTERFACE,
+        )
+        .map_err(|e| PlatformError::Other(format!("failed to reach the logind session: {e}")))?;
+
+        let this = Rc::new(Self {
+            manager,
+            session,
+            sleep_inhibitor: RefCell::new(None),
+            preparing_for_sleep: Cell::new(false),
+            on_event: RefCell::new(None),
+            lock_handler: RefCell::new(None),
+        });
+        this.inhibit_sleep()?;
+
+        let (sender, channel) = calloop::channel::channel();
+        platform.channel_to_event_loop(channel, &this, "logind signals", |this, event| {
+            this.handle(event);
+        })?;
+
+        #[cfg(feature = "session-lock")]
+        {
+            let weak = Rc::downgrade(&this);
+            platform.add_event_listener(move |event| {
+                let Some(this) = weak.upgrade() else {
+                    return false;
+                };
+                if matches!(event, BackendEvent::SessionLocked) && this.preparing_for_sleep.get() {
+                    this.release_sleep_inhibitor();
+                }
+                true
+            });
+        }
+
+        std::thread::spawn(move || watch_signals(&connection, &session_path, &sender));
+
+        Ok(this)
+    }
+
+    /// Sets a callback invoked for every lock request and sleep transition.
+    pub fn on_event(&self, callback: impl Fn(LogindEvent) + 'static) {
+        *self.on_event.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Shows a [`LockScreen`] with one component per output whenever logind asks to lock the
+    /// session or the system prepares to sleep, and unlocks it when logind asks to unlock.
+    ///
+    /// The returned handle unlocks the session once the user authenticated. The session's
+    /// `LockedHint` follows the lock screen, however the lock ends.
+    #[cfg(feature = "session-lock")]
+    pub fn lock_on_request<C: ComponentHandle + 'static>(
+        self: &Rc<Self>,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Rc<LockRequests<C>> {
+        let platform = platform.clone();
+        let factory = Rc::new(factory);
+        let requests = Rc::new(LockRequests {
+            lock_screen: RefCell::new(None),
+        });
+        let weak = Rc::downgrade(self);
+        *self.lock_handler.borrow_mut() = Some(Box::new({
+            let requests = requests.clone();
+            move |event| {
+                let Some(this) = weak.upgrade() else {
+                    return;
+                };
+                match event {
+                    LogindEvent::Lock | LogindEvent::PrepareForSleep => {
+                        if let Some(lock) = requests.lock_screen() {
+                            if lock.is_locked() && this.preparing_for_sleep.get() {
+                                this.release_sleep_inhibitor();
+                            }
+                            return;
+                        }
+                        let factory = factory.clone();
+                        match LockScreen::lock(&platform, move |output| factory(output)) {
+                            Ok(lock) => {
+                                let weak = Rc::downgrade(&this);
+                                lock.on_finished(move || {
+                                    if let Some(this) = weak.upgrade() {
+                                        let _ = this.set_locked_hint(false);
+                                    }
+                                });
+                                *requests.lock_screen.borrow_mut() = Some(lock);
+                                let _ = this.set_locked_hint(true);
+                            }
+                            Err(err) => {
+                                log::warn!("failed to lock the session: {err}");
+                                this.release_sleep_inhibitor();
+                            }
+                        }
+                    }
+                    LogindEvent::Unlock => {
+                        requests.unlock();
+                        let _ = this.set_locked_hint(false);
+                    }
+                    LogindEvent::Resumed => {}
+                }
+            }
+        }));
+        requests
+    }
+
+    /// Takes the delay inhibitor for sleep, unless it is already held.
+    pub fn inhibit_sleep(&self) -> Result<(), PlatformError> {
+        if self.sleep_inhibitor.borrow().is_some() {
+            return Ok(());
+        }
+        let fd = self
+            .manager
+            .call::<_, _, OwnedFd>(
+                "Inhibit",
+                &(
+                    "sleep",
+                    "slint-layer-shell",
+                    "Show the lock screen before suspending",
+                    "delay",
+                ),
+            )
+            .map_err(|e| PlatformError::Other(format!("failed to inhibit sleep: {e}")))?;
+        *self.sleep_inhibitor.borrow_mut() = Some(fd);
+        Ok(())
+    }
+
+    /// Releases the sleep inhibitor, letting a pending suspend proceed.
+    pub fn release_sleep_inhibitor(&self) {
+        self.sleep_inhibitor.borrow_mut().take();
+    }
+
+    /// Tells logind whether the session is locked, as shown by `loginctl show-session`.
+    pub fn set_locked_hint(&self, locked: bool) -> Result<(), PlatformError> {
+        self.session
+            .call::<_, _, ()>("SetLockedHint", &(locked,))
+            .map_err(|e| PlatformError::Other(format!("failed to set the locked hint: {e}")))
+    }
+
+    fn handle(&self, event: LogindEvent) {
+        match event {
+            LogindEvent::PrepareForSleep => self.preparing_for_sleep.set(true),
+            LogindEvent::Resumed => {
+                self.preparing_for_sleep.set(false);
+                if let Err(err) = self.inhibit_sleep() {
+                    log::warn!("{err}");
+                }
+            }
+            _ => {}
+        }
+        if let Some(lock_handler) = self.lock_handler.borrow().as_ref() {
+            lock_handler(event);
+        }
+        if let Some(on_event) = self.on_event.borrow().as_ref() {
+            on_event(event);
+        }
+    }
+}
+
+/// The lock screens shown by [`Logind::lock_on_request`].
+#[cfg(feature = "session-lock")]
+pub struct LockRequests<C: ComponentHandle + 'static> {
+    lock_screen: RefCell<Option<Rc<LockScreen<C>>>>,
+}
+
+#[cfg(feature = "session-lock")]
+impl<C: ComponentHandle + 'static> LockRequests<C> {
+    /// Returns the lock screen currently shown, if the session is locked.
+    pub fn lock_screen(&self) -> Option<Rc<LockScreen<C>>> {
+        self.lock_screen
+            .borrow()
+            .clone()
+            .filter(|lock| !lock.is_finished())
+    }
+
+    /// Unlocks the session, e.g. once the user entered their password.
+    pub fn unlock(&self) {
+        let lock = self.lock_screen.borrow_mut().take();
+        if let Some(lock) = lock {
+            lock.unlock();
+        }
+    }
+}
+
+/// Returns the session of this process, or the one named by `XDG_SESSION_ID` for processes
+/// started outside of it, such as systemd user services.
+fn session_path(manager: &Proxy<'_>) -> zbus::Result<OwnedObjectPath> {
+    manager
+        .call("GetSessionByPID", &(std::process::id(),))
+        .or_else(|err| match std::env::var("XDG_SESSION_ID") {
+            Ok(id) => manager.call("GetSession", &(id,)),
+            Err(_) => Err(err),
+        })
+}
+
+fn watch_signals(
+    connection: &Connection,
+    session_path: &OwnedObjectPath,
+    sender: &calloop::channel::Sender<LogindEvent>,
+) {
+    let messages = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(SERVICE)
+        .map(|rule| rule.build())
+        .and_then(|rule| MessageIterator::for_match_rule(rule, connection, None));
+    let Ok(messages) = messages else {
+        return;
+    };
+    for message in messages.flatten() {
+        let header = message.header();
+        let in_session = header
+            .path()
+            .is_some_and(|path| path.as_str() == session_path.as_str());
+        let event = match header.member().map(|member| member.as_str()) {
+            Some("Lock") if in_session => LogindEvent::Lock,
+            Some("Unlock") if in_session => LogindEvent::Unlock,
+            Some("PrepareForSleep") => match message.body().deserialize::<bool>() {
+                Ok(true) => LogindEvent::PrepareForSleep,
+                Ok(false) => LogindEvent::Resumed,
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+}
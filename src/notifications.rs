@@ -0,0 +1,372 @@
+//! An `org.freedesktop.Notifications` server, to build a notification daemon with Slint.
+//!
+//! D-Bus calls are answered on zbus' own thread, and notifications are applied to the model
+//! on the event loop thread.
+
+use crate::dbus::unwrap_variant;
+use crate::platform::SlintLayerShell;
+use slint::{
+    Image, Model, ModelExt, ModelRc, PlatformError, Rgba8Pixel, SharedPixelBuffer, Timer, VecModel,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Value};
+
+const PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Why a notification was closed, as reported to the sending application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    /// Closed by the application through `CloseNotification`.
+    Closed = 3,
+    Undefined = 4,
+}
+
+/// Raw image data sent with the `image-data` hint.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NotificationImage {
+    pub width: i32,
+    pub height: i32,
+    pub rowstride: i32,
+    pub has_alpha: bool,
+    pub data: Vec<u8>,
+}
+
+impl NotificationImage {
+    pub fn to_image(&self) -> Option<Image> {
+        let (width, height) = (
+            u32::try_from(self.width).ok()?,
+            u32::try_from(self.height).ok()?,
+        );
+        let channels = if self.has_alpha { 4 } else { 3 };
+        let rowstride = usize::try_from(self.rowstride).ok()?;
+        let mut buffer = SharedPixelBuffer::<Rgba8Pixel>::new(width, height);
+        let pixels = buffer.make_mut_slice();
+        for y in 0..height as usize {
+            let row = self.data.get(y * rowstride..)?;
+            for x in 0..width as usize {
+                let pixel = row.get(x * channels..x * channels + channels)?;
+                let alpha = if self.has_alpha { pixel[3] } else { 255 };
+                pixels[y * width as usize + x] =
+                    Rgba8Pixel::new(pixel[0], pixel[1], pixel[2], alpha);
+            }
+        }
+        Some(Image::from_rgba8(buffer))
+    }
+}
+
+/// A notification sent by an application.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// Icon name or `file://` URI.
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    /// `(key, label)` pairs, passed back to [`NotificationServer::invoke_action`].
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+    pub category: String,
+    pub image: Option<NotificationImage>,
+    /// Resident notifications stay open when one of their actions is invoked.
+    pub resident: bool,
+    /// `None` if the notification never expires, as critical ones do. The server default is
+    /// used when the application did not pick a timeout.
+    pub expire_timeout: Option<Duration>,
+}
+
+enum Request {
+    Notify(Notification),
+    Close(u32),
+}
+
+struct Interface {
+    sender: Mutex<calloop::channel::Sender<Request>>,
+    next_id: AtomicU32,
+    default_timeout: Duration,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl Interface {
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec!["actions", "body", "icon-static", "persistence"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        };
+        let hint = |name: &str| hints.get(name).map(|value| unwrap_variant(value));
+        let urgency = match hint("urgency") {
+            Some(Value::U8(0)) => Urgency::Low,
+            Some(Value::U8(2)) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+
+        let notification = Notification {
+            id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            actions: actions
+                .chunks_exact(2)
+                .map(|action| (action[0].clone(), action[1].clone()))
+                .collect(),
+            urgency,
+            category: match hint("category") {
+                Some(Value::Str(category)) => category.to_string(),
+                _ => String::new(),
+            },
+            image: ["image-data", "image_data", "icon_data"]
+                .into_iter()
+                .find_map(|name| hint(name).and_then(parse_image)),
+            resident: matches!(hint("resident"), Some(Value::Bool(true))),
+            // The specification has critical notifications stay until they are dismissed.
+            expire_timeout: match expire_timeout {
+                _ if urgency == Urgency::Critical => None,
+                0 => None,
+                timeout if timeout < 0 => Some(self.default_timeout),
+                timeout => Some(Duration::from_millis(timeout as u64)),
+            },
+        };
+        let _ = self
+            .sender
+            .lock()
+            .unwrap()
+            .send(Request::Notify(notification));
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        let _ = self.sender.lock().unwrap().send(Request::Close(id));
+    }
+
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            "slint-layer-shell".into(),
+            "slint-layer-shell".into(),
+            env!("CARGO_PKG_VERSION").into(),
+            "1.2".into(),
+        )
+    }
+}
+
+/// Owns the `org.freedesktop.Notifications` name on the session bus and keeps a model of
+/// the open notifications.
+///
+/// Notifications are closed when they expire, when [`Self::dismiss`] is called, or when one
+/// of their actions is invoked, and the sending application is told about it.
+///
+/// ```ignore
+/// let server = NotificationServer::start(&platform)?;
+/// ui.set_notifications(server.model(|n| NotificationData {
+///     id: n.id as i32,
+///     summary: n.summary.as_str().into(),
+///     body: n.body.as_str().into(),
+/// }));
+/// ui.on_dismissed(move |id| server.dismiss(id as u32));
+/// ```
+pub struct NotificationServer {
+    connection: Connection,
+    notifications: Rc<VecModel<Notification>>,
+    timers: RefCell<HashMap<u32, Timer>>,
+    on_notify: RefCell<Option<Box<dyn Fn(&Notification)>>>,
+}
+
+impl NotificationServer {
+    /// Takes over the notification name on the session bus, expiring notifications without
+    /// an explicit timeout after 5 seconds. Critical notifications never expire.
+    pub fn start(platform: &SlintLayerShell) -> Result<Rc<Self>, PlatformError> {
+        Self::start_with_default_timeout(platform, Duration::from_secs(5))
+    }
+
+    pub fn start_with_default_timeout(
+        platform: &SlintLayerShell,
+        default_timeout: Duration,
+    ) -> Result<Rc<Self>, PlatformError> {
+        let (sender, channel) = calloop::channel::channel();
+        let interface = Interface {
+            sender: Mutex::new(sender),
+            next_id: AtomicU32::new(1),
+            default_timeout,
+        };
+        let connection = zbus::blocking::connection::Builder::session()
+            .and_then(|builder| builder.name(INTERFACE))
+            .and_then(|builder| builder.serve_at(PATH, interface))
+            .and_then(|builder| builder.build())
+            .map_err(|e| {
+                PlatformError::Other(format!("failed to own the notifications D-Bus name: {e}"))
+            })?;
+
+        let this = Rc::new(Self {
+            connection,
+            notifications: Rc::new(VecModel::default()),
+            timers: RefCell::default(),
+            on_notify: RefCell::default(),
+        });
+
+        platform.channel_to_event_loop(channel, &this, "notifications", |this, request| {
+            this.handle(request, &Rc::downgrade(this));
+        })?;
+
+        Ok(this)
+    }
+
+    /// Returns the open notifications, oldest first.
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.notifications.iter().collect()
+    }
+
+    /// Returns a model of the open notifications, converted with `map` and kept up to date.
+    pub fn model<T: Clone + 'static>(
+        &self,
+        map: impl Fn(&Notification) -> T + 'static,
+    ) -> ModelRc<T> {
+        ModelRc::new(self.notifications.clone().map(move |item| map(&item)))
+    }
+
+    /// Calls `callback` for every new or replaced notification, e.g. to show the window of
+    /// the notification stack.
+    pub fn on_notify(&self, callback: impl Fn(&Notification) + 'static) {
+        *self.on_notify.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Closes the notification on behalf of the user.
+    pub fn dismiss(&self, id: u32) {
+        self.close(id, CloseReason::Dismissed);
+    }
+
+    /// Reports `action` to the sending application, and closes non-resident notifications.
+    pub fn invoke_action(&self, id: u32, action: &str) {
+        let _ = self.connection.emit_signal(
+            None::<&str>,
+            PATH,
+            INTERFACE,
+            "ActionInvoked",
+            &(id, action),
+        );
+        let resident = self
+            .notifications
+            .iter()
+            .any(|notification| notification.id == id && notification.resident);
+        if !resident {
+            self.dismiss(id);
+        }
+    }
+
+    /// Removes the notification and emits `NotificationClosed` with `reason`.
+    pub fn close(&self, id: u32, reason: CloseReason) {
+        self.timers.borrow_mut().remove(&id);
+        let Some(row) = self
+            .notifications
+            .iter()
+            .position(|notification| notification.id == id)
+        else {
+            return;
+        };
+        self.notifications.remove(row);
+        let _ = self.connection.emit_signal(
+            None::<&str>,
+            PATH,
+            INTERFACE,
+            "NotificationClosed",
+            &(id, reason as u32),
+        );
+    }
+
+    fn handle(&self, request: Request, weak: &Weak<Self>) {
+        match request {
+            Request::Notify(notification) => {
+                let id = notification.id;
+                if let Some(timeout) = notification.expire_timeout {
+                    let weak = weak.clone();
+                    let timer = Timer::default();
+                    timer.start(slint::TimerMode::SingleShot, timeout, move || {
+                        if let Some(this) = weak.upgrade() {
+                            this.close(id, CloseReason::Expired);
+                        }
+                    });
+                    self.timers.borrow_mut().insert(id, timer);
+                } else {
+                    self.timers.borrow_mut().remove(&id);
+                }
+
+                if let Some(callback) = self.on_notify.borrow().as_ref() {
+                    callback(&notification);
+                }
+                match self
+                    .notifications
+                    .iter()
+                    .position(|existing| existing.id == id)
+                {
+                    Some(row) => self.notifications.set_row_data(row, notification),
+                    None => self.notifications.push(notification),
+                }
+            }
+            Request::Close(id) => self.close(id, CloseReason::Closed),
+        }
+    }
+}
+
+fn parse_image(value: &Value) -> Option<NotificationImage> {
+    let Value::Structure(image) = value else {
+        return None;
+    };
+    let [
+        Value::I32(width),
+        Value::I32(height),
+        Value::I32(rowstride),
+        Value::Bool(has_alpha),
+        Value::I32(_bits_per_sample),
+        Value::I32(_channels),
+        Value::Array(data),
+    ] = image.fields()
+    else {
+        return None;
+    };
+    Some(NotificationImage {
+        width: *width,
+        height: *height,
+        rowstride: *rowstride,
+        has_alpha: *has_alpha,
+        data: data
+            .iter()
+            .filter_map(|byte| match byte {
+                Value::U8(byte) => Some(*byte),
+                _ => None,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,183 @@
+use slint::PhysicalSize;
+use smithay_client_toolkit::output::OutputInfo as SctkOutputInfo;
+use std::rc::Rc;
+use wayland_client::protocol::wl_output::{Transform, WlOutput};
+
+/// Description of a connected output, as advertised by the compositor.
+#[derive(Clone, Debug)]
+pub struct OutputInfo {
+    pub output: WlOutput,
+    /// Connector name such as `DP-1`, if the compositor supports `wl_output` v4.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub make: String,
+    pub model: String,
+    /// Size of the current mode in physical pixels.
+    pub resolution: Option<PhysicalSize>,
+    /// Refresh rate of the current mode in Hz.
+    pub refresh_rate: Option<f32>,
+    pub logical_position: Option<(i32, i32)>,
+    pub logical_size: Option<(i32, i32)>,
+    pub scale_factor: i32,
+    pub transform: Transform,
+}
+
+impl OutputInfo {
+    pub(crate) fn new(output: WlOutput, info: &SctkOutputInfo) -> Self {
+        let current_mode = info.modes.iter().find(|mode| mode.current);
+
+        Self {
+            output,
+            name: info.name.clone(),
+            description: info.description.clone(),
+            make: info.make.clone(),
+            model: info.model.clone(),
+            resolution: current_mode
+                .map(|mode| PhysicalSize::new(mode.dimensions.0 as u32, mode.dimensions.1 as u32)),
+            refresh_rate: current_mode.map(|mode| mode.refresh_rate as f32 / 1000.0),
+            logical_position: info.logical_position,
+            logical_size: info.logical_size,
+            scale_factor: info.scale_factor,
+            transform: info.transform,
+        }
+    }
+}
+
+/// Result of an output chooser, see [`SlintLayerShell::set_output_chooser`].
+///
+/// [`SlintLayerShell::set_output_chooser`]: crate::platform::SlintLayerShell::set_output_chooser
+#[derive(Clone, Debug)]
+pub enum OutputSelection {
+    /// Let the compositor pick the output, or use all outputs for per-output windows.
+    CompositorDefault,
+    Output(WlOutput),
+    /// Several outputs. Single windows are placed on the first one.
+    Outputs(Vec<WlOutput>),
+}
+
+impl OutputSelection {
+    /// Returns the output a single window should be placed on.
+    pub fn first(&self) -> Option<&WlOutput> {
+        match self {
+            OutputSelection::CompositorDefault => None,
+            OutputSelection::Output(output) => Some(output),
+            OutputSelection::Outputs(outputs) => outputs.first(),
+        }
+    }
+
+    /// Returns whether per-output windows should be created on `output`.
+    pub fn contains(&self, output: &WlOutput) -> bool {
+        match self {
+            OutputSelection::CompositorDefault => true,
+            OutputSelection::Output(selected) => selected == output,
+            OutputSelection::Outputs(selected) => selected.contains(output),
+        }
+    }
+}
+
+/// How the primary output is determined, see [`SlintLayerShell::set_primary_output`].
+///
+/// [`SlintLayerShell::set_primary_output`]: crate::platform::SlintLayerShell::set_primary_output
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrimaryOutput {
+    /// The output with the given connector name, such as `DP-1`.
+    Name(String),
+    /// The output with the largest current mode.
+    Largest,
+    /// The output with the smallest logical x position, the top-most one on ties.
+    Leftmost,
+    /// The first output announced by the compositor.
+    First,
+    /// The output at this position in the order the compositor announced them, starting at
+    /// 0.
+    Index(usize),
+}
+
+impl PrimaryOutput {
+    /// Parses an output setting such as `DP-1`, or a number for an index.
+    pub fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => PrimaryOutput::Index(index),
+            Err(_) => PrimaryOutput::Name(value.into()),
+        }
+    }
+
+    pub fn select<'a>(&self, outputs: &'a [OutputInfo]) -> Option<&'a OutputInfo> {
+        match self {
+            PrimaryOutput::Name(name) => outputs
+                .iter()
+                .find(|info| info.name.as_deref() == Some(name.as_str())),
+            PrimaryOutput::Largest => outputs.iter().max_by_key(|info| {
+                info.resolution
+                    .map(|size| size.width as u64 * size.height as u64)
+                    .unwrap_or(0)
+            }),
+            PrimaryOutput::Leftmost => outputs.iter().min_by_key(|info| {
+                let (x, y) = info.logical_position.unwrap_or((i32::MAX, i32::MAX));
+                (x, y)
+            }),
+            PrimaryOutput::First => outputs.first(),
+            PrimaryOutput::Index(index) => outputs.get(*index),
+        }
+    }
+}
+
+pub(crate) type OutputChooser = Rc<dyn Fn(&[OutputInfo]) -> OutputSelection>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use wayland_client::{Connection, Proxy};
+
+    /// An output at `position` with a `width`x`height` mode, whose `wl_output` is not backed
+    /// by a compositor.
+    fn output(name: &str, position: (i32, i32), (width, height): (u32, u32)) -> OutputInfo {
+        let (stream, _) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(stream).unwrap();
+        OutputInfo {
+            output: WlOutput::inert(connection.backend().downgrade()),
+            name: Some(name.into()),
+            description: None,
+            make: String::new(),
+            model: String::new(),
+            resolution: Some(PhysicalSize::new(width, height)),
+            refresh_rate: None,
+            logical_position: Some(position),
+            logical_size: Some((width as i32, height as i32)),
+            scale_factor: 1,
+            transform: Transform::Normal,
+        }
+    }
+
+    #[test]
+    fn parse_tells_indices_from_names() {
+        assert_eq!(PrimaryOutput::parse("1"), PrimaryOutput::Index(1));
+        assert_eq!(
+            PrimaryOutput::parse("DP-1"),
+            PrimaryOutput::Name("DP-1".into())
+        );
+        assert_eq!(PrimaryOutput::parse("-1"), PrimaryOutput::Name("-1".into()));
+    }
+
+    #[test]
+    fn select_picks_the_described_output() {
+        let outputs = [
+            output("DP-1", (1920, 0), (3840, 2160)),
+            output("eDP-1", (0, 0), (1920, 1080)),
+        ];
+        let name = |primary: PrimaryOutput| {
+            primary
+                .select(&outputs)
+                .and_then(|info| info.name.as_deref())
+        };
+        assert_eq!(name(PrimaryOutput::First), Some("DP-1"));
+        assert_eq!(name(PrimaryOutput::Largest), Some("DP-1"));
+        assert_eq!(name(PrimaryOutput::Leftmost), Some("eDP-1"));
+        assert_eq!(name(PrimaryOutput::Index(1)), Some("eDP-1"));
+        assert_eq!(name(PrimaryOutput::Index(2)), None);
+        assert_eq!(name(PrimaryOutput::parse("eDP-1")), Some("eDP-1"));
+        assert_eq!(name(PrimaryOutput::parse("HDMI-A-1")), None);
+        assert!(PrimaryOutput::First.select(&[]).is_none());
+    }
+}
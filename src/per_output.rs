@@ -0,0 +1,182 @@
+use crate::event::BackendEvent;
+use crate::layer::LayerConfig;
+use crate::output::{OutputInfo, PrimaryOutput};
+use crate::platform::SlintLayerShell;
+use slint::{ComponentHandle, PlatformError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wayland_client::protocol::wl_output::WlOutput;
+
+type ComponentFactory<C> = Box<dyn Fn(&OutputInfo) -> Result<C, PlatformError>>;
+
+/// One instance of a Slint component per output, as typically used by bars and wallpapers.
+///
+/// The factory receives the [`OutputInfo`] of the output the component is placed on, so it
+/// can forward it to a property or global of the component.
+pub struct PerOutputWindows<C: ComponentHandle + 'static> {
+    platform: SlintLayerShell,
+    config: RefCell<LayerConfig>,
+    /// Limits the components to this output, see [`Self::on_output`].
+    only: Option<PrimaryOutput>,
+    factory: ComponentFactory<C>,
+    windows: RefCell<Vec<(OutputInfo, C)>>,
+}
+
+impl<C: ComponentHandle + 'static> PerOutputWindows<C> {
+    /// Creates and shows one component for each output that is currently connected.
+    ///
+    /// Components are created and dropped automatically as outputs are hotplugged, for as
+    /// long as the returned handle is alive.
+    pub fn new(
+        platform: &SlintLayerShell,
+        config: LayerConfig,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<Self>, PlatformError> {
+        Self::create(platform, config, None, Box::new(factory))
+    }
+
+    /// Creates and shows the component on `output` whenever it is connected, e.g. a bar for
+    /// a monitor that is not always plugged in, and drops it when it is disconnected.
+    pub fn on_output(
+        platform: &SlintLayerShell,
+        output: PrimaryOutput,
+        config: LayerConfig,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<Self>, PlatformError> {
+        Self::create(platform, config, Some(output), Box::new(factory))
+    }
+
+    fn create(
+        platform: &SlintLayerShell,
+        config: LayerConfig,
+        only: Option<PrimaryOutput>,
+        factory: ComponentFactory<C>,
+    ) -> Result<Rc<Self>, PlatformError> {
+        let this = Rc::new(Self {
+            platform: platform.clone(),
+            config: RefCell::new(config),
+            only,
+            factory,
+            windows: RefCell::new(Vec::new()),
+        });
+        this.sync()?;
+
+        let weak = Rc::downgrade(&this);
+        platform.add_event_listener(move |event| {
+            let Some(this) = weak.upgrade() else {
+                return false;
+            };
+            if matches!(
+                event,
+                BackendEvent::OutputAdded { .. } | BackendEvent::OutputRemoved { .. }
+            ) {
+                if let Err(err) = this.sync() {
+                    log::warn!("failed to update per-output windows: {err}");
+                }
+            }
+            true
+        });
+
+        Ok(this)
+    }
+
+    /// Reconciles the components with the current outputs: components are created for new
+    /// outputs, and hidden and dropped for outputs that disappeared.
+    pub fn sync(&self) -> Result<(), PlatformError> {
+        let mut outputs = self.platform.outputs();
+        match &self.only {
+            Some(only) => outputs = only.select(&outputs).cloned().into_iter().collect(),
+            None => {
+                let selection = self.platform.choose_outputs(&outputs);
+                outputs.retain(|info| selection.contains(&info.output));
+            }
+        }
+
+        let removed = {
+            let mut windows = self.windows.borrow_mut();
+            let (kept, removed) = windows.drain(..).partition::<Vec<_>, _>(|(info, _)| {
+                outputs.iter().any(|output| output.output == info.output)
+            });
+            *windows = kept;
+            removed
+        };
+        for (_, component) in removed {
+            let _ = component.hide();
+        }
+
+        for info in outputs {
+            let exists = self
+                .windows
+                .borrow()
+                .iter()
+                .any(|(existing, _)| existing.output == info.output);
+            if exists {
+                continue;
+            }
+
+            let config = LayerConfig {
+                output: Some(info.output.clone()),
+                ..self.config.borrow().clone()
+            };
+            let component = self
+                .platform
+                .with_layer_config(config, || (self.factory)(&info))?;
+            component.show()?;
+            self.windows.borrow_mut().push((info, component));
+        }
+
+        Ok(())
+    }
+
+    /// Applies `config` to the existing components and to the ones created from now on.
+    pub fn set_layer_config(&self, config: LayerConfig) -> Result<(), PlatformError> {
+        *self.config.borrow_mut() = config.clone();
+        for (info, component) in self.windows.borrow().iter() {
+            let config = LayerConfig {
+                output: Some(info.output.clone()),
+                ..config.clone()
+            };
+            self.platform.set_layer_config(component.window(), config)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the component shown on `output`.
+    pub fn get(&self, output: &WlOutput) -> Option<C> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|(info, _)| &info.output == output)
+            .map(|(_, component)| component.clone_strong())
+    }
+
+    /// Returns the component shown on the output with the connector name `name`, such as
+    /// `DP-1`.
+    pub fn get_by_name(&self, name: &str) -> Option<C> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|(info, _)| info.name.as_deref() == Some(name))
+            .map(|(_, component)| component.clone_strong())
+    }
+
+    /// Returns the components keyed by the connector name of their output. Components on
+    /// outputs without a name, before `wl_output` version 4, are left out.
+    pub fn by_name(&self) -> HashMap<String, C> {
+        self.windows
+            .borrow()
+            .iter()
+            .filter_map(|(info, component)| Some((info.name.clone()?, component.clone_strong())))
+            .collect()
+    }
+
+    /// Returns all components together with the output they are shown on.
+    pub fn components(&self) -> Vec<(OutputInfo, C)> {
+        self.windows
+            .borrow()
+            .iter()
+            .map(|(info, component)| (info.clone(), component.clone_strong()))
+            .collect()
+    }
+}
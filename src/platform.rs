@@ -1,230 +1,1397 @@
-use crate::window_adapter::LayerShellWindowAdapter;
-use calloop::{EventLoop, LoopSignal};
+use crate::builder::{QuitPolicy, Renderer, SlintLayerShellBuilder};
+use crate::capabilities::Capabilities;
+#[cfg(feature = "clipboard")]
+use crate::clipboard::ClipboardState;
+use crate::event::{
+    BackendEvent, InputTimestamp, PointerButton, RawSurfaceEvent, SeatChange, VisibilityChange,
+};
+use crate::extension::Extensions;
+#[cfg(feature = "foreign-toplevel")]
+use crate::foreign_toplevel::{ToplevelInfo, ToplevelList};
+use crate::frame_stats::{FrameStats, FrameStatsCollector};
+use crate::layer::{LayerConfig, OutputRemovalPolicy};
+use crate::layer_shell::LayerShell;
+use crate::output::{OutputChooser, OutputInfo, OutputSelection, PrimaryOutput};
+use crate::per_output::PerOutputWindows;
+use crate::quirks::{Compositor, Quirks};
+#[cfg(feature = "screencopy")]
+use crate::screencopy::{Capture, Captures, PendingCapture};
+use crate::systemd::SystemdNotifier;
+#[cfg(feature = "text-input")]
+use crate::text_input::TextInputState;
+#[cfg(feature = "virtual-keyboard")]
+use crate::virtual_keyboard::VirtualKeyboard;
+use crate::window_adapter::{LayerShellWindowAdapter, SurfaceRole};
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{EventLoop, LoopHandle, LoopSignal};
 use i_slint_core::api::EventLoopError;
+use i_slint_core::items::ColorScheme;
 use i_slint_core::platform::{EventLoopProxy, update_timers_and_animations};
 use i_slint_renderer_skia::SkiaSharedContext;
+#[cfg(feature = "clipboard")]
+use slint::platform::Clipboard;
 use slint::platform::{Platform, PlatformError, WindowAdapter, duration_until_next_timer_update};
+use slint::{ComponentHandle, ModelRc, VecModel};
 use smithay_client_toolkit::compositor::CompositorState;
 use smithay_client_toolkit::output::OutputState;
 use smithay_client_toolkit::reexports::calloop_wayland_source::WaylandSource;
 use smithay_client_toolkit::registry::RegistryState;
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
 use smithay_client_toolkit::seat::SeatState;
+use smithay_client_toolkit::seat::pointer::cursor_shape::CursorShapeManager;
+#[cfg(feature = "session-lock")]
+use smithay_client_toolkit::session_lock::{SessionLock, SessionLockState};
 use smithay_client_toolkit::shell::xdg::XdgShell;
-use std::cell::RefCell;
+#[cfg(feature = "screencopy")]
+use smithay_client_toolkit::shm::Shm;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, VecDeque};
 use std::rc::{Rc, Weak};
-use std::time::Instant;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::time::{Duration, Instant};
 use wayland_backend::client::ObjectId;
 use wayland_client::globals::registry_queue_init;
-use wayland_client::protocol::{wl_keyboard, wl_pointer, wl_touch};
-use wayland_client::{Connection, QueueHandle};
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_surface::WlSurface;
+#[cfg(feature = "touch")]
+use wayland_client::protocol::wl_touch;
+use wayland_client::protocol::{wl_keyboard, wl_pointer};
+use wayland_client::{Connection, Proxy, QueueHandle};
+#[cfg(feature = "text-input")]
+use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3;
+#[cfg(feature = "virtual-keyboard")]
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+#[cfg(feature = "foreign-toplevel")]
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1,
+    zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1,
+};
+#[cfg(feature = "screencopy")]
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
 
 pub struct LayerShellState {
     pub registry_state: RegistryState,
     pub compositor_state: CompositorState,
     pub seat_state: SeatState,
     pub output_state: OutputState,
-    // pub layer_shell: LayerShell,
+    /// `None` when the compositor has no layer shell and the platform was built with
+    /// [`SlintLayerShellBuilder::fallback_to_xdg_shell`].
+    pub layer_shell: Option<LayerShell>,
     pub xdg_shell: XdgShell,
 
     pub skia_shard_context: SkiaSharedContext,
 
     pub proxied_event_queue: VecDeque<ProxyTask>,
+    pub backend_events: VecDeque<BackendEvent>,
 
     pub window_adapters: HashMap<ObjectId, Weak<LayerShellWindowAdapter>>,
-    pub window_factory_queue: VecDeque<LayerShellWindowAdapter>,
     pub keyboard: Option<wl_keyboard::WlKeyboard>,
     pub pointer: Option<wl_pointer::WlPointer>,
+    #[cfg(feature = "touch")]
     pub touch: Option<wl_touch::WlTouch>,
     pub keyboard_focus_surface: Option<ObjectId>,
+    #[cfg(feature = "touch")]
     pub touch_points: HashMap<i32, (ObjectId, (f32, f32))>,
+
+    /// Layer configuration of new windows. `None` creates regular xdg toplevels.
+    pub default_layer_config: Option<LayerConfig>,
+    /// Overrides `default_layer_config` within [`SlintLayerShell::with_layer_config`].
+    pub scoped_layer_config: Option<LayerConfig>,
+    /// Overrides `default_layer_config` with xdg toplevels within
+    /// [`SlintLayerShell::with_toplevel`].
+    pub scoped_toplevel: bool,
+    /// Makes new windows lock surfaces, see [`crate::presets::LockScreen`].
+    #[cfg(feature = "session-lock")]
+    pub(crate) scoped_lock_surface: Option<(SessionLock, WlOutput)>,
+    #[cfg(feature = "session-lock")]
+    pub session_lock_state: SessionLockState,
+    pub(crate) output_chooser: Option<OutputChooser>,
+    pub output_removal_policy: OutputRemovalPolicy,
+    pub primary_output: Option<PrimaryOutput>,
+    /// Color scheme reported to the windows, see [`SlintLayerShell::set_color_scheme`].
+    pub color_scheme: ColorScheme,
+    pub renderer: Renderer,
+    pub quit_policy: QuitPolicy,
+    #[cfg(feature = "virtual-keyboard")]
+    pub virtual_keyboard_manager: Option<ZwpVirtualKeyboardManagerV1>,
+    #[cfg(feature = "foreign-toplevel")]
+    pub foreign_toplevel_manager: Option<ZwlrForeignToplevelManagerV1>,
+    #[cfg(feature = "foreign-toplevel")]
+    pub(crate) foreign_toplevels: ToplevelList,
+    #[cfg(feature = "screencopy")]
+    pub shm: Shm,
+    #[cfg(feature = "screencopy")]
+    pub screencopy_manager: Option<ZwlrScreencopyManagerV1>,
+    #[cfg(feature = "screencopy")]
+    pub(crate) captures: Captures,
+    #[cfg(feature = "text-input")]
+    pub text_input_manager: Option<ZwpTextInputManagerV3>,
+    #[cfg(feature = "text-input")]
+    pub(crate) text_input: TextInputState,
+    /// Application state for protocols handled outside of this crate, see
+    /// [`crate::extension`].
+    pub(crate) extensions: Extensions,
+    /// Gives each pointer a cursor shape device, which [`SlintLayerShell::hide_cursor`] needs
+    /// to show the cursor again. `None` without `wp_cursor_shape_manager_v1`.
+    pub(crate) cursor_shape_manager: Option<CursorShapeManager>,
+    pub compositor: Compositor,
+    /// Shared with the window adapters, which apply them to their layer surfaces.
+    pub(crate) quirks: Rc<Cell<Quirks>>,
+    /// Shared with the window adapters, which record the events they forward.
+    #[cfg(feature = "recording")]
+    pub(crate) recorder: crate::recording::SharedRecorder,
+    #[cfg(feature = "clipboard")]
+    pub(crate) clipboard: Rc<RefCell<ClipboardState>>,
+    /// Shared with the platform, see [`SharedState`].
+    pub(crate) shared: Rc<RefCell<SharedState>>,
+}
+
+/// Parts of the state the platform's methods read, kept out of [`LayerShellState`] as those
+/// methods are called from Slint callbacks, which run while the Wayland dispatch borrows the
+/// state. The handlers update them as the compositor announces changes.
+#[derive(Default)]
+pub(crate) struct SharedState {
+    /// Seats in the order the compositor announced them.
+    pub(crate) seats: Vec<WlSeat>,
+    /// Connected outputs whose information is known.
+    pub(crate) outputs: Vec<OutputInfo>,
+    /// The pointer and the serial of its latest enter event, which setting the cursor
+    /// requires.
+    pub(crate) pointer_enter: Option<(wl_pointer::WlPointer, u32)>,
+    /// Sets the cursor of the pointer to a shape of the compositor's cursor theme.
+    pub(crate) cursor_shape_device: Option<WpCursorShapeDeviceV1>,
+    /// Whether [`SlintLayerShell::hide_cursor`] hid the cursor, which the next pointer motion
+    /// shows again.
+    pub(crate) cursor_hidden: bool,
+    /// Every window adapter, including dropped ones until the event loop forgets them.
+    pub(crate) window_adapters: Vec<Weak<LayerShellWindowAdapter>>,
 }
 
+impl LayerShellState {
+    /// Returns the version the layer shell was bound at, see [`LayerShell::version`].
+    pub fn layer_shell_version(&self) -> u32 {
+        self.layer_shell.as_ref().map_or(0, LayerShell::version)
+    }
+
+    pub fn outputs(&self) -> Vec<OutputInfo> {
+        self.output_state
+            .outputs()
+            .filter_map(|output| {
+                let info = self.output_state.info(&output)?;
+                Some(OutputInfo::new(output, &info))
+            })
+            .collect()
+    }
+}
+
+/// The layer shell platform.
+///
+/// Cloning is cheap and yields a handle to the same platform, so a clone can be kept
+/// around after the original has been passed to [`slint::platform::set_platform`].
+#[derive(Clone)]
 pub struct SlintLayerShell {
     connection: Connection,
     // event_queue: EventQueue<LayerShellState>,
     queue_handle: QueueHandle<LayerShellState>,
-    state: Rc<RefCell<LayerShellState>>,
-    event_loop: RefCell<EventLoop<'static, LayerShellState>>,
+    pub(crate) state: Rc<RefCell<LayerShellState>>,
+    event_loop: Rc<RefCell<EventLoop<'static, LayerShellState>>>,
     loop_signal: LoopSignal,
+    proxy_sender: calloop::channel::Sender<ProxyTask>,
 
-    should_close: bool,
+    should_close: Arc<AtomicBool>,
+    exit_code: Arc<AtomicI32>,
+    systemd_notifier: Option<Rc<SystemdNotifier>>,
+    event_listeners: Rc<RefCell<Vec<EventListener>>>,
+    frame_stats: Rc<RefCell<Option<FrameStatsCollector>>>,
+    /// Shared with the Wayland state, as Slint reads the clipboard during dispatch.
+    #[cfg(feature = "clipboard")]
+    pub(crate) clipboard: Rc<RefCell<ClipboardState>>,
+    shared: Rc<RefCell<SharedState>>,
+    pub(crate) compositor: Compositor,
+    /// Shared with the Wayland state, see [`LayerShellState::quirks`].
+    pub(crate) quirks: Rc<Cell<Quirks>>,
 }
 
+/// Internal observer of backend events, dropped once it returns `false`.
+pub(crate) type EventListener = Box<dyn FnMut(&BackendEvent) -> bool>;
+
 impl SlintLayerShell {
+    /// Connects to the compositor from the environment with the default options.
+    ///
+    /// Panics if the connection fails.
+    #[deprecated(note = "use `SlintLayerShell::builder().build()`, which returns errors")]
     pub fn new() -> Self {
-        let event_loop = EventLoop::try_new().unwrap();
+        Self::builder().build().unwrap()
+    }
+
+    pub fn builder() -> SlintLayerShellBuilder {
+        SlintLayerShellBuilder::default()
+    }
+
+    pub(crate) fn with_connection(
+        connection: Connection,
+        fallback_to_xdg_shell: bool,
+    ) -> Result<Self, PlatformError> {
+        let event_loop = EventLoop::try_new()
+            .map_err(|e| PlatformError::Other(format!("failed to create event loop: {e}")))?;
         let loop_signal = event_loop.get_signal();
 
-        let connection = Connection::connect_to_env().unwrap();
-        let (global, event_queue) = registry_queue_init(&connection).unwrap();
+        let (global, mut event_queue) = registry_queue_init(&connection)
+            .map_err(|e| PlatformError::Other(format!("failed to query Wayland globals: {e}")))?;
         let qh = event_queue.handle();
 
-        let event_source = WaylandSource::<LayerShellState>::new(connection.clone(), event_queue);
-
+        let (proxy_sender, proxy_channel) = calloop::channel::channel();
         let _ = event_loop
             .handle()
-            .insert_source(event_source, |_, queue, state| {
-                queue.dispatch_pending(state)
-            });
+            .insert_source(proxy_channel, handle_proxy_message);
 
         let registry_state = RegistryState::new(&global);
-        let compositor_state = CompositorState::bind(&global, &qh).unwrap();
+        let compositor_state = CompositorState::bind(&global, &qh)
+            .map_err(|e| PlatformError::Other(format!("wl_compositor is not available: {e}")))?;
         let seat_state = SeatState::new(&global, &qh);
         let output_state = OutputState::new(&global, &qh);
-        // let layer_shell = LayerShell::bind(&global, &qh).unwrap();
-        let xdg_shell = XdgShell::bind(&global, &qh).unwrap();
+        let layer_shell = match LayerShell::bind(&global, &qh) {
+            Ok(layer_shell) => {
+                log::debug!(
+                    "creating layer surfaces with {} version {}",
+                    layer_shell.interface(),
+                    layer_shell.version()
+                );
+                Some(layer_shell)
+            }
+            Err(err) if fallback_to_xdg_shell => {
+                log::warn!(
+                    "neither ext_layer_shell_v1 nor zwlr_layer_shell_v1 is available ({err}), \
+                     creating xdg toplevels instead"
+                );
+                None
+            }
+            Err(err) => {
+                return Err(PlatformError::Other(format!(
+                    "neither ext_layer_shell_v1 nor zwlr_layer_shell_v1 is available: {err}"
+                )));
+            }
+        };
+        let xdg_shell = XdgShell::bind(&global, &qh)
+            .map_err(|e| PlatformError::Other(format!("xdg_wm_base is not available: {e}")))?;
+
+        #[cfg(feature = "session-lock")]
+        let session_lock_state = SessionLockState::new(&global, &qh);
+        #[cfg(feature = "screencopy")]
+        let shm = Shm::bind(&global, &qh)
+            .map_err(|e| PlatformError::Other(format!("wl_shm is not available: {e}")))?;
 
         let skia_shard_context = SkiaSharedContext::default();
+        #[cfg(feature = "clipboard")]
+        let clipboard = ClipboardState::new(&global, &qh, &event_loop.handle());
 
-        let state = LayerShellState {
+        let mut state = LayerShellState {
             registry_state,
             compositor_state,
             seat_state,
             output_state,
-            // layer_shell,
+            layer_shell,
             xdg_shell,
 
             skia_shard_context,
 
             proxied_event_queue: VecDeque::new(),
+            backend_events: VecDeque::new(),
 
             window_adapters: HashMap::new(),
-            window_factory_queue: VecDeque::new(),
             keyboard: None,
             pointer: None,
+            #[cfg(feature = "touch")]
             touch: None,
             keyboard_focus_surface: None,
+            #[cfg(feature = "touch")]
             touch_points: HashMap::new(),
+
+            default_layer_config: None,
+            scoped_layer_config: None,
+            scoped_toplevel: false,
+            #[cfg(feature = "session-lock")]
+            scoped_lock_surface: None,
+            #[cfg(feature = "session-lock")]
+            session_lock_state,
+            output_chooser: None,
+            output_removal_policy: OutputRemovalPolicy::default(),
+            primary_output: None,
+            color_scheme: ColorScheme::Unknown,
+            renderer: Renderer::default(),
+            quit_policy: QuitPolicy::default(),
+            #[cfg(feature = "virtual-keyboard")]
+            virtual_keyboard_manager: global.bind(&qh, 1..=1, ()).ok(),
+            #[cfg(feature = "foreign-toplevel")]
+            foreign_toplevel_manager: global.bind(&qh, 1..=3, ()).ok(),
+            #[cfg(feature = "foreign-toplevel")]
+            foreign_toplevels: ToplevelList::default(),
+            #[cfg(feature = "screencopy")]
+            shm,
+            #[cfg(feature = "screencopy")]
+            screencopy_manager: global.bind(&qh, 1..=3, ()).ok(),
+            #[cfg(feature = "screencopy")]
+            captures: Captures::default(),
+            #[cfg(feature = "text-input")]
+            text_input_manager: global.bind(&qh, 1..=1, ()).ok(),
+            #[cfg(feature = "text-input")]
+            text_input: TextInputState::default(),
+            extensions: Extensions::default(),
+            compositor: Compositor::Other,
+            quirks: Rc::new(Cell::new(Quirks::NONE)),
+            #[cfg(feature = "recording")]
+            recorder: Rc::new(RefCell::new(crate::recording::EventRecorder::from_env())),
+            #[cfg(feature = "clipboard")]
+            clipboard: clipboard.clone(),
+            cursor_shape_manager: CursorShapeManager::bind(&global, &qh).ok(),
+            shared: Rc::default(),
         };
 
-        Self {
+        // Receive the initial output and seat information before any window is created.
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|e| PlatformError::Other(format!("Wayland roundtrip failed: {e}")))?;
+
+        state.compositor = Compositor::detect(&state);
+        state.quirks.set(Quirks::from_env(state.compositor));
+        log::debug!(
+            "running on {} with {:?}",
+            state.compositor,
+            state.quirks.get()
+        );
+
+        let shared = state.shared.clone();
+        let (compositor, quirks) = (state.compositor, state.quirks.clone());
+        let event_source = WaylandSource::<LayerShellState>::new(connection.clone(), event_queue);
+        let _ = event_loop
+            .handle()
+            .insert_source(event_source, |_, queue, state| {
+                queue.dispatch_pending(state)
+            });
+
+        Ok(Self {
             connection,
             queue_handle: qh,
             // event_queue: RefCell::new(event_queue),
             state: Rc::new(RefCell::new(state)),
-            event_loop: RefCell::new(event_loop),
+            event_loop: Rc::new(RefCell::new(event_loop)),
             loop_signal,
-            should_close: false,
+            proxy_sender,
+            should_close: Arc::new(AtomicBool::new(false)),
+            exit_code: Arc::new(AtomicI32::new(0)),
+            systemd_notifier: None,
+            event_listeners: Rc::new(RefCell::new(Vec::new())),
+            frame_stats: Rc::new(RefCell::new(FrameStatsCollector::from_env())),
+            #[cfg(feature = "clipboard")]
+            clipboard,
+            shared,
+            compositor,
+            quirks,
+        })
+    }
+
+    pub(crate) fn add_event_listener(&self, listener: impl FnMut(&BackendEvent) -> bool + 'static) {
+        self.event_listeners.borrow_mut().push(Box::new(listener));
+    }
+
+    /// Registers a callback invoked when an output is connected, once its information is
+    /// known.
+    pub fn on_output_added(&self, callback: impl Fn(&OutputInfo) + 'static) {
+        let state = Rc::downgrade(&self.state);
+        self.add_event_listener(move |event| {
+            let Some(state) = state.upgrade() else {
+                return false;
+            };
+            if let BackendEvent::OutputAdded { output } = event {
+                let info = state.borrow().output_state.info(output);
+                if let Some(info) = info {
+                    callback(&OutputInfo::new(output.clone(), &info));
+                }
+            }
+            true
+        });
+    }
+
+    /// Registers a callback invoked when an output is disconnected.
+    pub fn on_output_removed(&self, callback: impl Fn(&WlOutput) + 'static) {
+        self.add_event_listener(move |event| {
+            if let BackendEvent::OutputRemoved { output } = event {
+                callback(output);
+            }
+            true
+        });
+    }
+
+    /// Registers a callback invoked with the surface of a window whose layer surface was
+    /// closed by the compositor. The window's user data is still available through
+    /// [`Self::user_data`].
+    pub fn on_layer_closed(&self, callback: impl Fn(&WlSurface) + 'static) {
+        self.add_event_listener(move |event| {
+            if let BackendEvent::LayerSurfaceClosed { surface } = event {
+                callback(surface);
+            }
+            true
+        });
+    }
+
+    /// Registers a callback invoked when a seat is added or removed, or gains or loses a
+    /// capability.
+    pub fn on_seat_changed(&self, callback: impl Fn(&WlSeat, SeatChange) + 'static) {
+        self.add_event_listener(move |event| {
+            match event {
+                BackendEvent::SeatAdded { seat } => callback(seat, SeatChange::Added),
+                BackendEvent::SeatRemoved { seat } => callback(seat, SeatChange::Removed),
+                BackendEvent::SeatCapabilityAdded { seat, capability } => {
+                    callback(seat, SeatChange::CapabilityAdded(*capability))
+                }
+                BackendEvent::SeatCapabilityRemoved { seat, capability } => {
+                    callback(seat, SeatChange::CapabilityRemoved(*capability))
+                }
+                _ => {}
+            }
+            true
+        });
+    }
+
+    fn notify_event_listeners(&self, event: &BackendEvent) {
+        // Listeners may register new listeners, so they are called without holding the borrow.
+        let mut listeners = std::mem::take(&mut *self.event_listeners.borrow_mut());
+        listeners.retain_mut(|listener| listener(event));
+        let mut event_listeners = self.event_listeners.borrow_mut();
+        listeners.append(&mut event_listeners);
+        *event_listeners = listeners;
+    }
+
+    /// Sends `READY=1` to systemd once the first window has been configured and rendered,
+    /// and pings the systemd watchdog from the event loop if `WatchdogSec=` is set.
+    pub(crate) fn enable_systemd_notify(&mut self) {
+        let Some(notifier) = SystemdNotifier::from_env().map(Rc::new) else {
+            return;
+        };
+
+        if let Some(interval) = SystemdNotifier::watchdog_interval() {
+            let watchdog_notifier = notifier.clone();
+            let _ = self.event_loop.borrow().handle().insert_source(
+                Timer::from_duration(interval),
+                move |_, _, _| {
+                    let _ = watchdog_notifier.notify("WATCHDOG=1");
+                    TimeoutAction::ToDuration(interval)
+                },
+            );
         }
+
+        self.systemd_notifier = Some(notifier);
     }
-}
 
-impl Platform for SlintLayerShell {
-    fn create_window_adapter(&self) -> Result<Rc<dyn WindowAdapter>, PlatformError> {
-        let qh = self.queue_handle.clone();
+    /// Returns whether windows are created as layer surfaces, or as xdg toplevels as the
+    /// compositor has no layer shell, see [`SlintLayerShellBuilder::fallback_to_xdg_shell`].
+    pub fn uses_layer_shell(&self) -> bool {
+        self.state.borrow().layer_shell.is_some()
+    }
 
-        let surface = {
-            let state = self.state.borrow_mut();
-            state.compositor_state.create_surface(&qh)
+    /// Returns which protocols the compositor provides and the versions they were bound at.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::new(&self.state.borrow())
+    }
+
+    /// Starts collecting [`FrameStats`], logging them every second at info level if `log` is
+    /// set. Also enabled by the [`crate::frame_stats::FRAME_STATS_ENV`] environment variable.
+    pub fn enable_frame_stats(&self, log: bool) {
+        let mut frame_stats = self.frame_stats.borrow_mut();
+        match &mut *frame_stats {
+            Some(collector) => collector.log |= log,
+            None => *frame_stats = Some(FrameStatsCollector::new(log)),
+        }
+    }
+
+    /// Returns the statistics of the last second with rendered frames, if frame statistics
+    /// are enabled.
+    pub fn frame_stats(&self) -> Option<FrameStats> {
+        self.frame_stats
+            .borrow()
+            .as_ref()
+            .and_then(FrameStatsCollector::last_report)
+    }
+
+    /// Registers a callback invoked every second with new [`FrameStats`], enabling their
+    /// collection if needed.
+    pub fn on_frame_stats(&self, callback: impl Fn(&FrameStats) + 'static) {
+        self.enable_frame_stats(false);
+        if let Some(collector) = &mut *self.frame_stats.borrow_mut() {
+            collector.set_on_report(callback);
+        }
+    }
+
+    /// Blocks `signals` and reports them as [`BackendEvent::Signal`] from the event loop
+    /// instead, where it is safe to touch windows.
+    #[cfg(feature = "signals")]
+    pub fn watch_signals(&self, signals: &[calloop::signals::Signal]) -> Result<(), PlatformError> {
+        let source = calloop::signals::Signals::new(signals)
+            .map_err(|e| PlatformError::Other(format!("failed to watch signals: {e}")))?;
+        self.event_loop
+            .borrow()
+            .handle()
+            .insert_source(source, |event, _, state| {
+                state.backend_events.push_back(BackendEvent::Signal {
+                    signal: event.signal(),
+                });
+            })
+            .map_err(|e| PlatformError::Other(format!("failed to watch signals: {e}")))?;
+        Ok(())
+    }
+
+    /// Creates a virtual keyboard on the first seat, interpreting key codes with `keymap`.
+    #[cfg(feature = "virtual-keyboard")]
+    pub fn create_virtual_keyboard(&self, keymap: &str) -> Result<VirtualKeyboard, PlatformError> {
+        let state = self.state.borrow();
+        let manager = state.virtual_keyboard_manager.as_ref().ok_or_else(|| {
+            PlatformError::Other("zwp_virtual_keyboard_manager_v1 is not available".into())
+        })?;
+        VirtualKeyboard::new(
+            manager,
+            &state,
+            self.connection.clone(),
+            &self.queue_handle,
+            keymap,
+        )
+    }
+
+    /// Returns the windows of all applications, as reported by
+    /// `zwlr_foreign_toplevel_manager_v1`. Empty if the compositor lacks the protocol.
+    #[cfg(feature = "foreign-toplevel")]
+    pub fn toplevels(&self) -> Vec<ToplevelInfo> {
+        self.state.borrow().foreign_toplevels.toplevels.clone()
+    }
+
+    #[cfg(feature = "foreign-toplevel")]
+    pub(crate) fn foreign_toplevels_supported(&self) -> bool {
+        self.state.borrow().foreign_toplevel_manager.is_some()
+    }
+
+    /// Asks the compositor to focus the window of another application on the first seat.
+    ///
+    /// Can be called from Slint callbacks, e.g. when a task button is clicked.
+    #[cfg(feature = "foreign-toplevel")]
+    pub fn activate_toplevel(&self, handle: &ZwlrForeignToplevelHandleV1) {
+        let seat = self.shared.borrow().seats.first().cloned();
+        if let Some(seat) = seat {
+            handle.activate(&seat);
+        }
+    }
+
+    /// Captures the current contents of `output`, calling `callback` from the event loop
+    /// once the compositor has copied them.
+    ///
+    /// With `overlay_cursor`, the cursor is included in the capture.
+    #[cfg(feature = "screencopy")]
+    pub fn capture_output(
+        &self,
+        output: &WlOutput,
+        overlay_cursor: bool,
+        callback: impl FnOnce(Result<Capture, PlatformError>) + 'static,
+    ) {
+        let mut state = self.state.borrow_mut();
+        let Some(manager) = state.screencopy_manager.as_ref() else {
+            drop(state);
+            callback(Err(PlatformError::Other(
+                "zwlr_screencopy_manager_v1 is not available".into(),
+            )));
+            return;
         };
+        let frame = manager.capture_output(overlay_cursor as i32, output, &self.queue_handle, ());
+        state.captures.insert(
+            &frame,
+            PendingCapture::new(output.clone(), Box::new(callback)),
+        );
+        let _ = self.connection.flush();
+    }
 
-        match LayerShellWindowAdapter::new(surface, self.connection.clone(), self.state.clone(), qh)
-        {
-            Ok(adapter) => Ok(adapter),
-            Err(e) => Err(e),
+    /// Returns a handle to the calloop event loop, to add event sources that are dispatched
+    /// along with Wayland events.
+    pub fn loop_handle(&self) -> LoopHandle<'static, LayerShellState> {
+        self.event_loop.borrow().handle()
+    }
+
+    /// Calls `handler` with `target` for every message sent through `channel`, as long as
+    /// `target` is alive. Messages from background threads are handled from Slint's timers,
+    /// outside of the Wayland dispatch, so handlers may update models and create windows.
+    #[cfg(any(
+        feature = "compositor-ipc",
+        feature = "global-shortcuts",
+        feature = "logind",
+        feature = "notifications",
+        feature = "portal",
+        feature = "tray"
+    ))]
+    pub(crate) fn channel_to_event_loop<T: 'static, M: 'static>(
+        &self,
+        channel: calloop::channel::Channel<M>,
+        target: &Rc<T>,
+        what: &str,
+        handler: impl Fn(&Rc<T>, M) + 'static,
+    ) -> Result<(), PlatformError> {
+        let target = Rc::downgrade(target);
+        let handler = Rc::new(handler);
+        self.loop_handle()
+            .insert_source(channel, move |event, _, _| {
+                let calloop::channel::Event::Msg(message) = event else {
+                    return;
+                };
+                let handler = handler.clone();
+                defer(&target, move |target| handler(&target, message));
+            })
+            .map_err(|e| PlatformError::Other(format!("failed to watch {what}: {e}")))?;
+        Ok(())
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn queue_handle(&self) -> &QueueHandle<LayerShellState> {
+        &self.queue_handle
+    }
+
+    /// Sets the layer configuration used for windows created from now on.
+    ///
+    /// With `None`, which is the default, windows are created as regular xdg toplevels.
+    /// Single windows can be given another role with [`Self::with_layer_config`] and
+    /// [`Self::with_toplevel`], so panels and regular windows can be mixed.
+    pub fn set_default_layer_config(&self, config: Option<LayerConfig>) {
+        self.state.borrow_mut().default_layer_config = config;
+    }
+
+    /// Runs `f` with `config` applied to every window created inside of it.
+    ///
+    /// ```ignore
+    /// let bar = platform.with_layer_config(config, || Bar::new())?;
+    /// ```
+    pub fn with_layer_config<R>(&self, config: LayerConfig, f: impl FnOnce() -> R) -> R {
+        let (previous, previous_toplevel) = {
+            let mut state = self.state.borrow_mut();
+            (
+                state.scoped_layer_config.replace(config),
+                std::mem::replace(&mut state.scoped_toplevel, false),
+            )
+        };
+        let result = f();
+        let mut state = self.state.borrow_mut();
+        state.scoped_layer_config = previous;
+        state.scoped_toplevel = previous_toplevel;
+        result
+    }
+
+    /// Runs `f` with every window created inside of it being a regular xdg toplevel, e.g. for
+    /// a settings dialog of an application whose default layer configuration makes panels.
+    ///
+    /// ```ignore
+    /// let settings = platform.with_toplevel(|| Settings::new())?;
+    /// ```
+    pub fn with_toplevel<R>(&self, f: impl FnOnce() -> R) -> R {
+        let (previous, previous_toplevel) = {
+            let mut state = self.state.borrow_mut();
+            (
+                state.scoped_layer_config.take(),
+                std::mem::replace(&mut state.scoped_toplevel, true),
+            )
+        };
+        let result = f();
+        let mut state = self.state.borrow_mut();
+        state.scoped_layer_config = previous;
+        state.scoped_toplevel = previous_toplevel;
+        result
+    }
+
+    /// Creates one instance of a component per output, see [`PerOutputWindows`].
+    pub fn spawn_per_output<C: ComponentHandle + 'static>(
+        &self,
+        config: LayerConfig,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<PerOutputWindows<C>>, PlatformError> {
+        PerOutputWindows::new(self, config, factory)
+    }
+
+    /// Creates and shows every described window, then runs the event loop until it quits.
+    ///
+    /// The platform must already be installed with [`slint::platform::set_platform`]. The
+    /// components are kept alive until the event loop returns.
+    ///
+    /// ```ignore
+    /// platform.run_windows(vec![
+    ///     LayerWindow::new(Panel::new(Edge::Top).layer_config().clone(), Bar::new),
+    ///     LayerWindow::new(osd_config, || {
+    ///         let osd = Osd::new()?;
+    ///         osd.on_done(|| {});
+    ///         Ok(osd)
+    ///     }),
+    /// ])?;
+    /// ```
+    pub fn run_windows(&self, windows: Vec<LayerWindow>) -> Result<(), PlatformError> {
+        let _components = windows
+            .into_iter()
+            .map(|window| self.with_layer_config(window.config, window.factory))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.run_event_loop_with(|_| {})
+    }
+
+    /// Registers a policy deciding which output new layer surfaces are placed on, when their
+    /// configuration does not name an output. It is also used to filter per-output windows.
+    pub fn set_output_chooser(&self, chooser: impl Fn(&[OutputInfo]) -> OutputSelection + 'static) {
+        self.state.borrow_mut().output_chooser = Some(Rc::new(chooser));
+    }
+
+    /// Configures the primary output, which new layer surfaces are placed on when neither their
+    /// configuration nor an output chooser picks an output.
+    pub fn set_primary_output(&self, primary_output: Option<PrimaryOutput>) {
+        self.state.borrow_mut().primary_output = primary_output;
+    }
+
+    /// Returns the primary output, if one is configured and currently connected.
+    pub fn primary_output(&self) -> Option<OutputInfo> {
+        let state = self.state.borrow();
+        let outputs = state.outputs();
+        state.primary_output.as_ref()?.select(&outputs).cloned()
+    }
+
+    /// Sets what happens to layer surfaces whose output is unplugged.
+    pub fn set_output_removal_policy(&self, policy: OutputRemovalPolicy) {
+        self.state.borrow_mut().output_removal_policy = policy;
+    }
+
+    pub fn set_quit_policy(&self, quit_policy: QuitPolicy) {
+        self.state.borrow_mut().quit_policy = quit_policy;
+    }
+
+    /// Sets the color scheme reported to all windows, which selects the light or dark palette
+    /// of Slint's styles. Wayland has no way to query it, the `portal` feature's
+    /// `PortalSettings` follows the desktop settings instead.
+    ///
+    /// Windows with a color scheme of their own, see [`WindowExt::set_color_scheme`], keep it.
+    ///
+    /// [`WindowExt::set_color_scheme`]: crate::window_ext::WindowExt::set_color_scheme
+    pub fn set_color_scheme(&self, color_scheme: slint::language::ColorScheme) {
+        self.state.borrow_mut().color_scheme = color_scheme;
+        let window_adapters = self
+            .state
+            .borrow()
+            .window_adapters
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for window_adapter in window_adapters {
+            window_adapter.set_color_scheme(color_scheme);
         }
     }
 
-    fn run_event_loop(&self) -> Result<(), PlatformError> {
-        let mut fps_frame_count: u128 = 0;
-        let mut fps_window_start = Instant::now();
+    /// Returns the output selection of the registered chooser, if any.
+    pub(crate) fn choose_outputs(&self, outputs: &[OutputInfo]) -> OutputSelection {
+        let output_chooser = self.state.borrow().output_chooser.clone();
+        match output_chooser {
+            Some(output_chooser) => output_chooser(outputs),
+            None => OutputSelection::CompositorDefault,
+        }
+    }
+
+    /// Returns a model of the connected outputs that is kept up to date as outputs are added,
+    /// changed or removed.
+    ///
+    /// `map` converts each output into the model's row type, typically the `OutputData` struct
+    /// generated from `outputs.slint` in [`crate::SLINT_INCLUDE_DIR`]:
+    ///
+    /// ```ignore
+    /// let outputs = platform.outputs_model(|info| OutputData {
+    ///     name: info.name.clone().unwrap_or_default().into(),
+    ///     scale: info.scale_factor,
+    ///     ..Default::default()
+    /// });
+    /// ui.global::<LayerShellOutputs>().set_outputs(outputs);
+    /// ```
+    pub fn outputs_model<T: Clone + 'static>(
+        &self,
+        map: impl Fn(&OutputInfo) -> T + 'static,
+    ) -> ModelRc<T> {
+        let model = Rc::new(VecModel::from(
+            self.outputs().iter().map(&map).collect::<Vec<_>>(),
+        ));
+
+        let weak_model = Rc::downgrade(&model);
+        let platform = self.clone();
+        self.add_event_listener(move |event| {
+            let Some(model) = weak_model.upgrade() else {
+                return false;
+            };
+            if matches!(
+                event,
+                BackendEvent::OutputAdded { .. }
+                    | BackendEvent::OutputUpdated { .. }
+                    | BackendEvent::OutputRemoved { .. }
+            ) {
+                model.set_vec(platform.outputs().iter().map(&map).collect::<Vec<_>>());
+            }
+            true
+        });
+
+        ModelRc::from(model)
+    }
+
+    /// Asks the compositor to lock the session. See [`crate::presets::LockScreen`] for a
+    /// complete lock screen built on top of this.
+    #[cfg(feature = "session-lock")]
+    pub fn lock_session(&self) -> Result<SessionLock, PlatformError> {
+        self.state
+            .borrow()
+            .session_lock_state
+            .lock(&self.queue_handle)
+            .map_err(|e| PlatformError::Other(format!("ext_session_lock_v1 is not available: {e}")))
+    }
+
+    /// Runs `f`, turning every window created inside of it into a lock surface on `output`.
+    #[cfg(feature = "session-lock")]
+    pub(crate) fn with_lock_surface<R>(
+        &self,
+        session_lock: SessionLock,
+        output: WlOutput,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let previous = self
+            .state
+            .borrow_mut()
+            .scoped_lock_surface
+            .replace((session_lock, output));
+        let result = f();
+        self.state.borrow_mut().scoped_lock_surface = previous;
+        result
+    }
+
+    /// Returns the outputs currently known to the compositor connection.
+    pub fn outputs(&self) -> Vec<OutputInfo> {
+        self.shared.borrow().outputs.clone()
+    }
+
+    /// Returns the connected output matching `output`, e.g. to set [`LayerConfig::output`]
+    /// from a connector name or an index given on the command line.
+    pub fn find_output(&self, output: &PrimaryOutput) -> Option<OutputInfo> {
+        output.select(&self.outputs()).cloned()
+    }
+
+    /// Changes the layer configuration of a window created as a layer surface, e.g. to move
+    /// its anchors or change its exclusive zone without re-creating it.
+    ///
+    /// The output and namespace are set when a layer surface is created, changing them
+    /// re-creates it, which unmaps the window until the new surface is configured. Without an
+    /// output, the window stays on its current one.
+    pub fn set_layer_config(
+        &self,
+        window: &slint::Window,
+        config: LayerConfig,
+    ) -> Result<(), PlatformError> {
+        LayerShellWindowAdapter::from_slint_window(window)
+            .ok_or_else(|| {
+                PlatformError::Other("the window is not backed by this platform".into())
+            })?
+            .set_layer_config(config)
+    }
+
+    /// Installs a raw Wayland event observer on the surface backing `window`, replacing the
+    /// previous one. Observers added by [`Self::add_raw_event_observer`], as the presets do,
+    /// are kept.
+    ///
+    /// Returns `false` if `window` is not backed by this platform.
+    pub fn set_raw_event_observer(
+        &self,
+        window: &slint::Window,
+        observer: impl Fn(&RawSurfaceEvent) + 'static,
+    ) -> bool {
+        match LayerShellWindowAdapter::from_slint_window(window) {
+            Some(window_adapter) => {
+                window_adapter.set_raw_event_observer(observer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a raw Wayland event observer on the surface backing `window`, alongside the
+    /// installed ones.
+    ///
+    /// Returns `false` if `window` is not backed by this platform.
+    pub fn add_raw_event_observer(
+        &self,
+        window: &slint::Window,
+        observer: impl Fn(&RawSurfaceEvent) + 'static,
+    ) -> bool {
+        match LayerShellWindowAdapter::from_slint_window(window) {
+            Some(window_adapter) => {
+                window_adapter.add_raw_event_observer(observer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Installs a callback receiving the pointer button presses (`true`) and releases
+    /// (`false`) on the surface backing `window`, e.g. to bind the back and forward buttons
+    /// of a mouse in a media widget.
+    ///
+    /// Returns `false` if `window` is not backed by this platform.
+    pub fn on_pointer_button(
+        &self,
+        window: &slint::Window,
+        callback: impl Fn(PointerButton, bool) + 'static,
+    ) -> bool {
+        match LayerShellWindowAdapter::from_slint_window(window) {
+            Some(window_adapter) => {
+                window_adapter.on_pointer_button(callback);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Installs a callback receiving the visibility changes of the window, e.g. to pause
+    /// polling data while it cannot be seen.
+    ///
+    /// Returns `false` if `window` is not backed by this platform.
+    pub fn on_visibility_changed(
+        &self,
+        window: &slint::Window,
+        callback: impl Fn(&VisibilityChange) + 'static,
+    ) -> bool {
+        match LayerShellWindowAdapter::from_slint_window(window) {
+            Some(window_adapter) => {
+                window_adapter.on_visibility_changed(callback);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the application state attached with [`WindowExt::set_user_data`] to the window
+    /// owning `surface`, e.g. for the surface of a [`BackendEvent::WindowConfigured`].
+    ///
+    /// [`WindowExt::set_user_data`]: crate::window_ext::WindowExt::set_user_data
+    pub fn user_data(&self, surface: &WlSurface) -> Option<Rc<dyn std::any::Any>> {
+        let window_adapter = self
+            .shared
+            .borrow()
+            .window_adapters
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|window_adapter| &window_adapter.surface == surface)?;
+        window_adapter.user_data.borrow().clone()
+    }
+
+    /// Hides the pointer cursor over the windows of the application until the pointer moves
+    /// again.
+    ///
+    /// Showing the cursor again sets the default shape of the compositor's cursor theme, so
+    /// this does nothing without `wp_cursor_shape_manager_v1`.
+    pub fn hide_cursor(&self) {
+        let mut shared = self.shared.borrow_mut();
+        if shared.cursor_shape_device.is_none() {
+            log::debug!("not hiding the cursor, as wp_cursor_shape_manager_v1 is not available");
+            return;
+        }
+        if let Some((pointer, serial)) = &shared.pointer_enter {
+            pointer.set_cursor(*serial, None, 0, 0);
+            shared.cursor_hidden = true;
+        }
+    }
+
+    /// Returns the latest input received by any window, for inactivity timeouts covering the
+    /// whole application.
+    pub fn last_input(&self) -> Option<InputTimestamp> {
+        self.shared
+            .borrow()
+            .window_adapters
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter_map(|window_adapter| window_adapter.last_input.get())
+            .max_by_key(|timestamp| timestamp.received)
+    }
+
+    /// Stops the event loop and records `code` as the exit status of the application.
+    pub fn quit_with_code(&self, code: i32) {
+        self.exit_code.store(code, Ordering::Relaxed);
+        self.should_close.store(true, Ordering::Relaxed);
+        self.loop_signal.wakeup();
+    }
+
+    /// Hides every window and tears down its surfaces, for applications hiding themselves
+    /// entirely, e.g. until a D-Bus call or a signal brings them back.
+    ///
+    /// The renderers release their surfaces and layer surfaces lose their role, so nothing is
+    /// left on screen or on the GPU. Windows whose components are still held can be shown
+    /// again, which sets them up from scratch, and windows created later use a new GPU
+    /// context. With [`QuitPolicy::AfterLastWindowHidden`], the event loop returns and can be
+    /// run again later.
+    ///
+    /// Fails when called during Wayland dispatch, e.g. from a raw event observer.
+    pub fn suspend(&self) -> Result<(), PlatformError> {
+        let window_adapters = self
+            .state
+            .try_borrow()
+            .map_err(|_| PlatformError::Other("cannot suspend during dispatch".into()))?
+            .window_adapters
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for window_adapter in window_adapters {
+            window_adapter.window.hide()?;
+            window_adapter.tear_down()?;
+        }
+        self.state.borrow_mut().skia_shard_context = SkiaSharedContext::default();
+        let _ = self.connection.flush();
+        Ok(())
+    }
+
+    /// Returns the exit status set by [`Self::quit_with_code`], or `0` if none was set.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code.load(Ordering::Relaxed)
+    }
+
+    /// Runs the event loop like [`Platform::run_event_loop`], reporting backend events to
+    /// `on_event` as they happen.
+    ///
+    /// The callback runs outside of Wayland dispatch, so it may freely use Slint APIs.
+    ///
+    /// The loop can be run again after it returned, starting over with an exit status of `0`.
+    pub fn run_event_loop_with(
+        &self,
+        mut on_event: impl FnMut(BackendEvent),
+    ) -> Result<(), PlatformError> {
+        if !self.should_close.load(Ordering::Relaxed) {
+            self.exit_code.store(0, Ordering::Relaxed);
+        }
+        let mut shown_window = false;
 
         loop {
-            if self.should_close {
-                self.loop_signal.stop();
+            // Time until a window waiting for a frame callback gives up on it.
+            let mut frame_callback_timeout = None::<Duration>;
+            let frame_callback_limit = self.quirks.get().frame_callback_timeout;
+
+            if self.should_close.swap(false, Ordering::Relaxed) {
                 break;
             }
 
-            let mut state = self.state.borrow_mut();
-            let mut event_loop = self.event_loop.borrow_mut();
-
-            while let Some(task) = state.proxied_event_queue.pop_front() {
-                task();
+            // Run queued work without holding the state borrow, as tasks, including the
+            // factories of `WindowFactorySender`, may create new window adapters.
+            let (tasks, backend_events) = {
+                let mut state = self.state.borrow_mut();
+                (
+                    state.proxied_event_queue.drain(..).collect::<Vec<_>>(),
+                    state.backend_events.drain(..).collect::<Vec<_>>(),
+                )
+            };
+            if !tasks.is_empty() {
+                let _span = trace_span!("proxy_tasks", count = tasks.len());
+                tasks.into_iter().for_each(|task| task());
+            }
+            for event in backend_events {
+                self.notify_event_listeners(&event);
+                on_event(event);
             }
 
             // Update slint's animate timer.
             update_timers_and_animations();
 
-            // TODO: Execute invoke function from channel.
-            state.window_adapters.values().for_each(|window_adapter| {
-                let Some(window_adapter) = window_adapter.upgrade() else {
-                    return;
-                };
+            let window_adapters = {
+                let mut state = self.state.borrow_mut();
+                // Forget dropped windows whose surfaces received no event since, so that
+                // long-running shells creating and dropping popups do not accumulate entries.
+                state
+                    .window_adapters
+                    .retain(|_, window_adapter| window_adapter.strong_count() > 0);
+                state
+                    .shared
+                    .borrow_mut()
+                    .window_adapters
+                    .retain(|window_adapter| window_adapter.strong_count() > 0);
+                state
+                    .window_adapters
+                    .values()
+                    .filter_map(Weak::upgrade)
+                    .collect::<Vec<_>>()
+            };
 
+            let any_visible = window_adapters
+                .iter()
+                .any(|window_adapter| window_adapter.visible.get());
+            shown_window |= any_visible;
+            let quit_policy = self.state.borrow().quit_policy;
+            if quit_policy == QuitPolicy::AfterLastWindowHidden && shown_window && !any_visible {
+                break;
+            }
+
+            for window_adapter in window_adapters {
                 if window_adapter.window_state.get()
                     != crate::window_adapter::WindowState::Configured
                 {
-                    return;
+                    continue;
                 }
 
                 if window_adapter.frame_callback_pending.get() {
-                    return;
+                    // Compositors may never send the callback, e.g. for surfaces they hide
+                    // without telling, so pending redraws are rendered after a timeout.
+                    if !window_adapter.pending_redraw.get() {
+                        continue;
+                    }
+                    let waited = window_adapter.frame_requested_at.get().elapsed();
+                    if let Some(remaining) = frame_callback_limit.checked_sub(waited) {
+                        frame_callback_timeout = Some(
+                            frame_callback_timeout
+                                .map_or(remaining, |timeout| timeout.min(remaining)),
+                        );
+                        continue;
+                    }
+                    log::debug!(
+                        "no frame callback for {:?} after {waited:?}, rendering anyway",
+                        window_adapter.surface.id()
+                    );
+                    window_adapter.frame_callback_pending.set(false);
                 }
 
                 if window_adapter.pending_redraw.get() {
-                    // {
-                    fps_frame_count += 1;
-
-                    let elapsed = fps_window_start.elapsed();
-                    if elapsed.as_secs_f64() >= 1.0 {
-                        let fps = fps_frame_count as f64 / elapsed.as_secs_f64();
-                        println!("FPS: {:.2}", fps);
-                        fps_frame_count = 0;
-                        fps_window_start = Instant::now();
-                    }
+                    on_event(BackendEvent::AboutToRender {
+                        surface: window_adapter.surface.clone(),
+                    });
 
+                    let _span = trace_span!("render", surface = ?window_adapter.surface);
                     window_adapter
                         .surface
                         .frame(&self.queue_handle, window_adapter.surface.clone());
-                    let _ = window_adapter.render.render();
+                    // Set before rendering, so that the region is applied with the frame.
+                    window_adapter.update_input_region_from_alpha();
+                    let render_start = Instant::now();
+                    let rendered = window_adapter.render.render();
+                    if let Some(collector) = &mut *self.frame_stats.borrow_mut() {
+                        collector.record_frame(render_start.elapsed());
+                    }
+                    if let (Ok(()), Some(notifier)) = (&rendered, &self.systemd_notifier) {
+                        notifier.notify_ready();
+                    }
                     window_adapter.frame_callback_pending.set(true);
+                    window_adapter.frame_requested_at.set(Instant::now());
                     window_adapter.pending_redraw.set(false);
                 }
-            });
+            }
 
-            // println!("Duration: {:?}", duration_until_next_timer_update());
-            let _ = event_loop.dispatch(duration_until_next_timer_update(), &mut state);
+            let report = self
+                .frame_stats
+                .borrow_mut()
+                .as_mut()
+                .and_then(FrameStatsCollector::report_if_due);
+            if let Some((stats, Some(on_report))) = report {
+                on_report(&stats);
+            }
+
+            let mut state = self.state.borrow_mut();
+            let mut event_loop = self.event_loop.borrow_mut();
+
+            let _span = trace_span!("wayland_dispatch");
+            let timeout = match (duration_until_next_timer_update(), frame_callback_timeout) {
+                (Some(timer), Some(frame_callback)) => Some(timer.min(frame_callback)),
+                (timer, frame_callback) => timer.or(frame_callback),
+            };
+            let _ = event_loop.dispatch(timeout, &mut state);
         }
 
         Ok(())
     }
 
+    /// Returns a sender that can be moved to other threads to request new windows.
+    ///
+    /// The factory is executed on the event loop thread, outside of any Wayland dispatch,
+    /// so it is safe to instantiate Slint components from it.
+    pub fn window_factory_sender(&self) -> WindowFactorySender {
+        WindowFactorySender {
+            tx: self.proxy_sender.clone(),
+        }
+    }
+}
+
+impl Platform for SlintLayerShell {
+    fn create_window_adapter(&self) -> Result<Rc<dyn WindowAdapter>, PlatformError> {
+        let qh = self.queue_handle.clone();
+
+        let surface = {
+            let state = self.state.try_borrow_mut().map_err(|_| {
+                PlatformError::Other(
+                    "cannot create a window while the Wayland state is in use, \
+                     use a WindowFactorySender or slint::invoke_from_event_loop instead"
+                        .into(),
+                )
+            })?;
+            state.compositor_state.create_surface(&qh)
+        };
+        let (mut layer_config, output_chooser) = {
+            let state = self.state.borrow();
+            (
+                state
+                    .scoped_layer_config
+                    .clone()
+                    .or_else(|| state.default_layer_config.clone())
+                    .filter(|_| !state.scoped_toplevel),
+                state.output_chooser.clone(),
+            )
+        };
+        if let Some(config) = &mut layer_config {
+            if config.output.is_none() {
+                config.output = match output_chooser {
+                    Some(output_chooser) => output_chooser(&self.outputs()).first().cloned(),
+                    None => self.primary_output().map(|info| info.output),
+                };
+            }
+        }
+
+        let role = match layer_config {
+            Some(config) if self.uses_layer_shell() => SurfaceRole::Layer(config),
+            _ => SurfaceRole::Toplevel,
+        };
+        #[cfg(feature = "session-lock")]
+        let role = match self.state.borrow().scoped_lock_surface.clone() {
+            Some((session_lock, output)) => SurfaceRole::Lock {
+                session_lock,
+                output,
+            },
+            None => role,
+        };
+
+        match LayerShellWindowAdapter::new(
+            surface,
+            self.connection.clone(),
+            self.state.clone(),
+            qh,
+            role,
+        ) {
+            Ok(adapter) => Ok(adapter),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn run_event_loop(&self) -> Result<(), PlatformError> {
+        self.run_event_loop_with(|_| {})
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn set_clipboard_text(&self, text: &str, clipboard: Clipboard) {
+        let result = match clipboard {
+            Clipboard::DefaultClipboard => {
+                self.set_clipboard_content(crate::clipboard::ClipboardContent::Text(text.into()))
+            }
+            // Slint's `TextInput` sets the selection clipboard whenever its selection changes.
+            Clipboard::SelectionClipboard => self.set_primary_selection(text),
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            log::warn!("failed to copy: {err}");
+        }
+    }
+
+    /// Slint's `TextInput` reads the selection clipboard when middle-clicked.
+    #[cfg(feature = "clipboard")]
+    fn clipboard_text(&self, clipboard: Clipboard) -> Option<String> {
+        match clipboard {
+            Clipboard::DefaultClipboard => self.read_clipboard_text(),
+            Clipboard::SelectionClipboard => self.primary_selection(),
+            _ => None,
+        }
+    }
+
     fn new_event_loop_proxy(&self) -> Option<Box<dyn EventLoopProxy>> {
-        let (event_loop_proxy, rx) = LayerShellEventLoopProxy::new(self.loop_signal.clone());
+        Some(Box::new(LayerShellEventLoopProxy {
+            loop_signal: self.loop_signal.clone(),
+            tx: self.proxy_sender.clone(),
+            should_close: self.should_close.clone(),
+        }))
+    }
+}
 
-        let _ = self
-            .event_loop
-            .borrow_mut()
-            .handle()
-            .insert_source(rx, |event, _, state| {
-                if let calloop::channel::Event::Msg(task) = event {
-                    state.proxied_event_queue.push_back(task);
-                }
-            });
+/// A window created by [`SlintLayerShell::run_windows`].
+pub struct LayerWindow {
+    config: LayerConfig,
+    factory: Box<dyn FnOnce() -> Result<Box<dyn std::any::Any>, PlatformError>>,
+}
 
-        Some(Box::new(event_loop_proxy))
+impl LayerWindow {
+    /// Describes a layer surface with `config`, showing the component returned by `factory`.
+    pub fn new<C: ComponentHandle + 'static>(
+        config: LayerConfig,
+        factory: impl FnOnce() -> Result<C, PlatformError> + 'static,
+    ) -> Self {
+        Self {
+            config,
+            factory: Box::new(move || {
+                let component = factory()?;
+                component.show()?;
+                Ok(Box::new(component))
+            }),
+        }
     }
 }
 
 pub type ProxyTask = Box<dyn FnOnce() + Send>;
 
-struct LayerShellEventLoopProxy {
-    loop_signal: LoopSignal,
-    tx: calloop::channel::Sender<ProxyTask>,
+/// Runs `f` with `target` from Slint's timers, outside of the Wayland dispatch, unless
+/// `target` is dropped before.
+#[cfg(any(
+    feature = "compositor-ipc",
+    feature = "config",
+    feature = "global-shortcuts",
+    feature = "logind",
+    feature = "notifications",
+    feature = "portal",
+    feature = "tray"
+))]
+pub(crate) fn defer<T: 'static>(target: &Weak<T>, f: impl FnOnce(Rc<T>) + 'static) {
+    let target = target.clone();
+    slint::Timer::single_shot(Duration::ZERO, move || {
+        if let Some(target) = target.upgrade() {
+            f(target);
+        }
+    });
 }
 
-impl LayerShellEventLoopProxy {
-    fn new(loop_signal: LoopSignal) -> (Self, calloop::channel::Channel<ProxyTask>) {
-        let (tx, rx) = calloop::channel::channel();
+fn handle_proxy_message(
+    event: calloop::channel::Event<ProxyTask>,
+    _: &mut (),
+    state: &mut LayerShellState,
+) {
+    if let calloop::channel::Event::Msg(task) = event {
+        state.proxied_event_queue.push_back(task);
+    }
+}
 
-        (Self { loop_signal, tx }, rx)
+/// Thread-safe handle used to request window creation on the event loop thread.
+#[derive(Clone)]
+pub struct WindowFactorySender {
+    tx: calloop::channel::Sender<ProxyTask>,
+}
+
+impl WindowFactorySender {
+    /// Queues `factory` to run on the event loop thread, where it may create Slint components.
+    pub fn request_window(
+        &self,
+        factory: impl FnOnce() + Send + 'static,
+    ) -> Result<(), EventLoopError> {
+        self.tx
+            .send(Box::new(factory))
+            .map_err(|_| EventLoopError::EventLoopTerminated)
     }
 }
 
+struct LayerShellEventLoopProxy {
+    loop_signal: LoopSignal,
+    tx: calloop::channel::Sender<ProxyTask>,
+    should_close: Arc<AtomicBool>,
+}
+
 impl EventLoopProxy for LayerShellEventLoopProxy {
     fn quit_event_loop(&self) -> Result<(), EventLoopError> {
-        self.loop_signal.stop();
+        self.should_close.store(true, Ordering::Relaxed);
+        self.loop_signal.wakeup();
         Ok(())
     }
 
@@ -0,0 +1,225 @@
+//! Appearance settings read from the `org.freedesktop.portal.Settings` interface of
+//! xdg-desktop-portal, such as the dark mode preference, accent color and interface font.
+//!
+//! Changes are received on a background thread and applied on the event loop thread.
+
+use crate::dbus::unwrap_variant;
+use crate::platform::SlintLayerShell;
+use slint::language::ColorScheme;
+use slint::{Color, PlatformError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::zvariant::{OwnedValue, Value};
+
+const SERVICE: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+const INTERFACE: &str = "org.freedesktop.portal.Settings";
+
+const APPEARANCE: &str = "org.freedesktop.appearance";
+const GNOME_INTERFACE: &str = "org.gnome.desktop.interface";
+
+/// Desktop appearance settings, with defaults for the ones the portal does not provide.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DesktopSettings {
+    /// `Unknown` if the user has no preference.
+    pub color_scheme: ColorScheme,
+    pub accent_color: Option<Color>,
+    /// Family of the interface font, e.g. `Cantarell`.
+    pub font_family: Option<String>,
+    /// Size of the interface font in points, before `text_scaling_factor` is applied.
+    pub font_size: Option<f32>,
+    pub text_scaling_factor: f32,
+    /// The user asked for animations to be reduced or disabled.
+    pub reduced_motion: bool,
+}
+
+impl Default for DesktopSettings {
+    fn default() -> Self {
+        Self {
+            color_scheme: ColorScheme::Unknown,
+            accent_color: None,
+            font_family: None,
+            font_size: None,
+            text_scaling_factor: 1.0,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl DesktopSettings {
+    /// Returns the size of the interface font in logical pixels, scaled by
+    /// `text_scaling_factor`, for use as a window's `default-font-size`.
+    pub fn font_pixel_size(&self) -> Option<f32> {
+        self.font_size
+            .map(|points| points * 4.0 / 3.0 * self.text_scaling_factor)
+    }
+
+    fn apply(&mut self, namespace: &str, key: &str, value: &Value) {
+        match (namespace, key, unwrap_variant(value)) {
+            (APPEARANCE, "color-scheme", Value::U32(scheme)) => {
+                self.color_scheme = match scheme {
+                    1 => ColorScheme::Dark,
+                    2 => ColorScheme::Light,
+                    _ => ColorScheme::Unknown,
+                }
+            }
+            (APPEARANCE, "accent-color", Value::Structure(color)) => {
+                // Out of range components mean that the user has no accent color.
+                let components = color
+                    .fields()
+                    .iter()
+                    .filter_map(|field| f64::try_from(field).ok())
+                    .filter(|component| (0.0..=1.0).contains(component))
+                    .map(|component| (component * 255.0).round() as u8)
+                    .collect::<Vec<_>>();
+                self.accent_color = match components[..] {
+                    [red, green, blue] => Some(Color::from_rgb_u8(red, green, blue)),
+                    _ => None,
+                };
+            }
+            (GNOME_INTERFACE, "font-name", Value::Str(font)) => {
+                // Pango font descriptions end with the size, e.g. `Cantarell Bold 11`.
+                let font = font.as_str();
+                let (family, size) = match font.rsplit_once(' ') {
+                    Some((family, size)) if size.parse::<f32>().is_ok() => {
+                        (family, size.parse().ok())
+                    }
+                    _ => (font, None),
+                };
+                self.font_family = Some(family.to_owned()).filter(|family| !family.is_empty());
+                self.font_size = size;
+            }
+            (APPEARANCE, "reduced-motion", Value::U32(preference)) => {
+                self.reduced_motion = *preference == 1;
+            }
+            // Desktops that predate `reduced-motion` only expose GNOME's animation switch.
+            (GNOME_INTERFACE, "enable-animations", Value::Bool(enabled)) => {
+                self.reduced_motion = !enabled;
+            }
+            (GNOME_INTERFACE, "text-scaling-factor", Value::F64(factor)) if *factor > 0.0 => {
+                self.text_scaling_factor = *factor as f32;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Follows the desktop appearance settings and applies the color scheme to all windows
+/// through [`SlintLayerShell::set_color_scheme`].
+///
+/// The font and motion settings cannot be applied to existing windows by the backend, and
+/// Slint offers no way for a backend to shorten animations. Bind them to the
+/// `DesktopSettings` global from `desktop-settings.slint` in [`crate::SLINT_INCLUDE_DIR`]:
+///
+/// ```ignore
+/// let portal = PortalSettings::watch(&platform)?;
+/// let apply = move |settings: &DesktopSettings| {
+///     let global = panel.global::<DesktopSettings>();
+///     global.set_accent_color(settings.accent_color.unwrap_or_default());
+///     global.set_font_family(settings.font_family.clone().unwrap_or_default().into());
+///     global.set_font_size(settings.font_pixel_size().unwrap_or(0.0));
+///     global.set_reduce_motion(settings.reduced_motion);
+/// };
+/// apply(&portal.settings());
+/// portal.on_changed(apply);
+/// ```
+pub struct PortalSettings {
+    platform: SlintLayerShell,
+    settings: RefCell<DesktopSettings>,
+    on_changed: RefCell<Option<Box<dyn Fn(&DesktopSettings)>>>,
+}
+
+impl PortalSettings {
+    /// Reads the current settings from the portal and starts following changes.
+    pub fn watch(platform: &SlintLayerShell) -> Result<Rc<Self>, PlatformError> {
+        let connection = Connection::session()
+            .map_err(|e| PlatformError::Other(format!("failed to connect to D-Bus: {e}")))?;
+        let proxy = Proxy::new(&connection, SERVICE, PATH, INTERFACE)
+            .map_err(|e| PlatformError::Other(format!("failed to reach the portal: {e}")))?;
+        let values = proxy
+            .call::<_, _, HashMap<String, HashMap<String, OwnedValue>>>(
+                "ReadAll",
+                &(vec![APPEARANCE, GNOME_INTERFACE],),
+            )
+            .map_err(|e| PlatformError::Other(format!("failed to read portal settings: {e}")))?;
+
+        let mut settings = DesktopSettings::default();
+        for (namespace, values) in &values {
+            for (key, value) in values {
+                settings.apply(namespace, key, value);
+            }
+        }
+        platform.set_color_scheme(settings.color_scheme);
+
+        let this = Rc::new(Self {
+            platform: platform.clone(),
+            settings: RefCell::new(settings),
+            on_changed: RefCell::new(None),
+        });
+
+        let (sender, channel) = calloop::channel::channel();
+        platform.channel_to_event_loop(
+            channel,
+            &this,
+            "portal settings",
+            |this, (namespace, key, value): (String, String, OwnedValue)| {
+                this.apply(&namespace, &key, &value);
+            },
+        )?;
+
+        std::thread::spawn(move || watch_settings(&connection, &sender));
+
+        Ok(this)
+    }
+
+    /// Returns the current settings.
+    pub fn settings(&self) -> DesktopSettings {
+        self.settings.borrow().clone()
+    }
+
+    /// Sets a callback invoked with the new settings whenever one of them changes.
+    pub fn on_changed(&self, callback: impl Fn(&DesktopSettings) + 'static) {
+        *self.on_changed.borrow_mut() = Some(Box::new(callback));
+    }
+
+    fn apply(&self, namespace: &str, key: &str, value: &OwnedValue) {
+        let settings = {
+            let mut settings = self.settings.borrow_mut();
+            let previous = settings.clone();
+            settings.apply(namespace, key, value);
+            if *settings == previous {
+                return;
+            }
+            settings.clone()
+        };
+        self.platform.set_color_scheme(settings.color_scheme);
+        if let Some(on_changed) = self.on_changed.borrow().as_ref() {
+            on_changed(&settings);
+        }
+    }
+}
+
+fn watch_settings(
+    connection: &Connection,
+    sender: &calloop::channel::Sender<(String, String, OwnedValue)>,
+) {
+    let messages = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(INTERFACE)
+        .and_then(|rule| rule.member("SettingChanged"))
+        .map(|rule| rule.build())
+        .and_then(|rule| MessageIterator::for_match_rule(rule, connection, None));
+    let Ok(messages) = messages else {
+        return;
+    };
+    for message in messages.flatten() {
+        let Ok(change) = message.body().deserialize::<(String, String, OwnedValue)>() else {
+            continue;
+        };
+        if sender.send(change).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Re-exports of the types needed by most layer shell applications, so that basic usage
+//! does not require depending on `wayland-client` or `smithay-client-toolkit` directly.
+//!
+//! ```ignore
+//! use slint_layer_shell::prelude::*;
+//! ```
+
+pub use crate::animation::{Easing, MarginAnimation};
+pub use crate::builder::SlintLayerShellBuilder;
+pub use crate::event::{BackendEvent, RawSurfaceEvent};
+pub use crate::layer::{
+    Anchor, KeyboardInteractivity, Layer, LayerConfig, Margins, OutputRemovalPolicy,
+};
+pub use crate::output::{OutputInfo, OutputSelection, PrimaryOutput};
+pub use crate::per_output::PerOutputWindows;
+pub use crate::platform::{LayerWindow, SlintLayerShell};
+pub use crate::presets::{Edge, Launcher, Panel, Wallpaper};
+pub use crate::window_ext::{WindowColorScheme, WindowExt};
+pub use slint::PlatformError;
+pub use wayland_client::protocol::{wl_output::WlOutput, wl_surface::WlSurface};
@@ -0,0 +1,226 @@
+use crate::event::RawSurfaceEvent;
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::output::OutputInfo;
+use crate::platform::SlintLayerShell;
+use crate::screencopy::Capture;
+use slint::{Color, ComponentHandle, Image, LogicalPosition, PlatformError, Timer};
+use smithay_client_toolkit::seat::keyboard::Keysym;
+use smithay_client_toolkit::seat::pointer::PointerEventKind;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// What is under the cursor of a [`ColorPicker`], passed to its update callback.
+#[derive(Clone, Debug)]
+pub struct PickerState {
+    /// Cursor position on the overlay, in logical pixels.
+    pub position: LogicalPosition,
+    pub color: Color,
+    /// The pixels around the cursor, scaled up, with the picked pixel in the center.
+    pub magnified: Image,
+}
+
+type UpdateCallback<C> = Box<dyn Fn(&C, &PickerState)>;
+
+/// Freezes every output and lets the user click a pixel, like `hyprpicker` or `wl-color-picker`.
+///
+/// The factory receives the screenshot of its output, which the component should show
+/// stretched over the whole surface, and `update` is called as the cursor moves so that the
+/// component can draw a magnifier. Escape cancels.
+///
+/// ```ignore
+/// ColorPicker::new().pick(
+///     &platform,
+///     |_output, screenshot| {
+///         let overlay = PickerOverlay::new()?;
+///         overlay.set_screenshot(screenshot);
+///         Ok(overlay)
+///     },
+///     |overlay, state| {
+///         overlay.set_cursor(state.position.x, state.position.y);
+///         overlay.set_magnified(state.magnified.clone());
+///     },
+///     |color| println!("{color:?}"),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct ColorPicker {
+    config: LayerConfig,
+    patch_size: u32,
+    zoom: u32,
+}
+
+impl Default for ColorPicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColorPicker {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Overlay,
+                anchor: Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+                size: Some((0, 0)),
+                exclusive_zone: LayerConfig::IGNORE_EXCLUSIVE_ZONES,
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                namespace: "color-picker".into(),
+                ..LayerConfig::default()
+            },
+            patch_size: 11,
+            zoom: 10,
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// Number of captured pixels shown by the magnifier in each direction, 11 by default.
+    pub fn patch_size(mut self, patch_size: u32) -> Self {
+        self.patch_size = patch_size.max(1);
+        self
+    }
+
+    /// How many times the magnifier scales up the pixels, 10 by default.
+    pub fn zoom(mut self, zoom: u32) -> Self {
+        self.zoom = zoom.max(1);
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Captures all outputs, covers them with the component and calls `on_picked` once,
+    /// with the clicked color or with `None` when cancelled or if capturing failed.
+    pub fn pick<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo, Image) -> Result<C, PlatformError> + 'static,
+        update: impl Fn(&C, &PickerState) + 'static,
+        on_picked: impl FnOnce(Option<Color>) + 'static,
+    ) {
+        let session = Rc::new(PickSession {
+            platform: platform.clone(),
+            picker: self,
+            update: Box::new(update),
+            on_picked: RefCell::new(Some(Box::new(on_picked))),
+            windows: RefCell::new(Vec::new()),
+        });
+        let factory = Rc::new(factory);
+
+        for info in platform.outputs() {
+            let session = session.clone();
+            let factory = factory.clone();
+            platform.capture_output(&info.output.clone(), false, move |result| {
+                let shown = result.and_then(|capture| session.show(&info, capture, &*factory));
+                if let Err(err) = shown {
+                    log::warn!("color picker failed: {err}");
+                    session.finish(None);
+                }
+            });
+        }
+    }
+}
+
+struct PickSession<C: ComponentHandle + 'static> {
+    platform: SlintLayerShell,
+    picker: ColorPicker,
+    update: UpdateCallback<C>,
+    on_picked: RefCell<Option<Box<dyn FnOnce(Option<Color>)>>>,
+    windows: RefCell<Vec<C>>,
+}
+
+impl<C: ComponentHandle + 'static> PickSession<C> {
+    fn show(
+        self: &Rc<Self>,
+        info: &OutputInfo,
+        capture: Capture,
+        factory: &dyn Fn(&OutputInfo, Image) -> Result<C, PlatformError>,
+    ) -> Result<(), PlatformError> {
+        if self.on_picked.borrow().is_none() {
+            return Ok(());
+        }
+        let config = LayerConfig {
+            output: Some(info.output.clone()),
+            ..self.picker.config.clone()
+        };
+        let component = self
+            .platform
+            .with_layer_config(config, || factory(info, capture.to_image()))?;
+
+        // The observer keeps the session alive until `finish` drops the components.
+        let session = self.clone();
+        let weak_component = component.as_weak();
+        self.platform
+            .add_raw_event_observer(component.window(), move |event| match event {
+                RawSurfaceEvent::Pointer { event } => {
+                    let Some(component) = weak_component.upgrade() else {
+                        return;
+                    };
+                    let window_size = component
+                        .window()
+                        .size()
+                        .to_logical(component.window().scale_factor());
+                    let scale = capture.width() as f64 / window_size.width.max(1.0) as f64;
+                    let (x, y) = (
+                        (event.position.0 * scale) as i32,
+                        (event.position.1 * scale) as i32,
+                    );
+                    let color = capture
+                        .pixel(x.max(0) as u32, y.max(0) as u32)
+                        .unwrap_or_default();
+                    match event.kind {
+                        PointerEventKind::Press { .. } => session.finish_later(Some(color)),
+                        PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                            let magnified = capture.magnify(
+                                x,
+                                y,
+                                session.picker.patch_size,
+                                session.picker.zoom,
+                            );
+                            (session.update)(
+                                &component,
+                                &PickerState {
+                                    position: LogicalPosition::new(
+                                        event.position.0 as f32,
+                                        event.position.1 as f32,
+                                    ),
+                                    color,
+                                    magnified: Image::from_rgba8(magnified),
+                                },
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                RawSurfaceEvent::KeyPressed { event } if event.keysym == Keysym::Escape => {
+                    session.finish_later(None)
+                }
+                _ => {}
+            });
+
+        component.show()?;
+        self.windows.borrow_mut().push(component);
+        Ok(())
+    }
+
+    /// Finishes from Slint's timers, as raw events are observed during the Wayland dispatch.
+    fn finish_later(self: &Rc<Self>, color: Option<Color>) {
+        let session = self.clone();
+        Timer::single_shot(Duration::ZERO, move || session.finish(color));
+    }
+
+    fn finish(&self, color: Option<Color>) {
+        let Some(on_picked) = self.on_picked.borrow_mut().take() else {
+            return;
+        };
+        for component in std::mem::take(&mut *self.windows.borrow_mut()) {
+            let _ = component.hide();
+        }
+        on_picked(color);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::event::RawSurfaceEvent;
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::output::OutputInfo;
+use crate::platform::SlintLayerShell;
+use crate::screencopy::Capture;
+use slint::{ComponentHandle, PlatformError};
+use smithay_client_toolkit::seat::pointer::PointerEventKind;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wayland_client::protocol::wl_output::WlOutput;
+
+/// A drawing input on a frozen output, as reported to [`FrozenOutputs::on_draw`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawEvent {
+    pub output: WlOutput,
+    pub kind: DrawEventKind,
+    /// Position in pixels of the capture, to composite annotations onto it.
+    pub position: (f64, f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawEventKind {
+    Press,
+    Motion,
+    Release,
+}
+
+type DrawCallback = Box<dyn Fn(&DrawEvent)>;
+
+/// Captures every output and shows the captures on full screen overlays, the base of
+/// `slurp` or `swappy` style screenshot tools.
+///
+/// The components draw the capture they are given and receive input as usual. Drawing input
+/// is additionally reported in capture pixels through [`FrozenOutputs::on_draw`].
+///
+/// ```ignore
+/// FreezeFrame::new().show(
+///     &platform,
+///     |_output, capture| {
+///         let overlay = Annotate::new()?;
+///         overlay.set_screenshot(capture.to_image());
+///         Ok(overlay)
+///     },
+///     |frozen| { let _ = frozen.map(|frozen| frozen.on_draw(|event| strokes.push(event))); },
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct FreezeFrame {
+    config: LayerConfig,
+    overlay_cursor: bool,
+}
+
+impl Default for FreezeFrame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FreezeFrame {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Overlay,
+                anchor: Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+                size: Some((0, 0)),
+                exclusive_zone: LayerConfig::IGNORE_EXCLUSIVE_ZONES,
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                namespace: "freeze-frame".into(),
+                ..LayerConfig::default()
+            },
+            overlay_cursor: false,
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// Includes the cursor in the captures.
+    pub fn overlay_cursor(mut self, enabled: bool) -> Self {
+        self.overlay_cursor = enabled;
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Captures all outputs, then shows one component per output and calls `on_ready`.
+    pub fn show<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo, &Capture) -> Result<C, PlatformError> + 'static,
+        on_ready: impl FnOnce(Result<Rc<FrozenOutputs<C>>, PlatformError>) + 'static,
+    ) {
+        let outputs = platform.outputs();
+        if outputs.is_empty() {
+            on_ready(Err(PlatformError::Other(
+                "there is no output to capture".into(),
+            )));
+            return;
+        }
+        let frozen = Rc::new(FrozenOutputs {
+            platform: platform.clone(),
+            config: self.config,
+            windows: RefCell::new(Vec::new()),
+            on_draw: RefCell::new(None),
+            remaining: Cell::new(outputs.len()),
+            closed: Cell::new(false),
+        });
+        let factory = Rc::new(factory);
+        let on_ready = Rc::new(RefCell::new(Some(on_ready)));
+
+        for info in outputs {
+            let frozen = frozen.clone();
+            let factory = factory.clone();
+            let on_ready = on_ready.clone();
+            platform.capture_output(&info.output.clone(), self.overlay_cursor, move |result| {
+                // Another capture failed, which closed the overlays and reported the error.
+                if frozen.closed.get() {
+                    return;
+                }
+                let shown = result.and_then(|capture| frozen.show(info, capture, &*factory));
+                let remaining = frozen.remaining.get() - 1;
+                frozen.remaining.set(remaining);
+                let report = match shown {
+                    Err(err) => Some(Err(err)),
+                    Ok(()) if remaining == 0 => Some(Ok(frozen.clone())),
+                    Ok(()) => None,
+                };
+                if let Some(report) = report {
+                    if report.is_err() {
+                        frozen.close();
+                    }
+                    if let Some(on_ready) = on_ready.borrow_mut().take() {
+                        on_ready(report);
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// The overlays shown by [`FreezeFrame::show`], until [`Self::close`] is called.
+pub struct FrozenOutputs<C: ComponentHandle + 'static> {
+    platform: SlintLayerShell,
+    config: LayerConfig,
+    windows: RefCell<Vec<(OutputInfo, Capture, C)>>,
+    on_draw: RefCell<Option<DrawCallback>>,
+    remaining: Cell<usize>,
+    /// Set by [`Self::close`], so that captures completing afterwards are not shown.
+    closed: Cell<bool>,
+}
+
+impl<C: ComponentHandle + 'static> FrozenOutputs<C> {
+    /// Returns the captured outputs with their capture and component.
+    pub fn outputs(&self) -> Vec<(OutputInfo, Capture, C)> {
+        self.windows
+            .borrow()
+            .iter()
+            .map(|(info, capture, component)| {
+                (info.clone(), capture.clone(), component.clone_strong())
+            })
+            .collect()
+    }
+
+    /// Calls `callback` for pointer and touch input on the overlays.
+    pub fn on_draw(&self, callback: impl Fn(&DrawEvent) + 'static) {
+        *self.on_draw.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Hides and drops the overlays.
+    pub fn close(&self) {
+        self.closed.set(true);
+        for (_, _, component) in std::mem::take(&mut *self.windows.borrow_mut()) {
+            let _ = component.hide();
+        }
+    }
+
+    fn show(
+        self: &Rc<Self>,
+        info: OutputInfo,
+        capture: Capture,
+        factory: &dyn Fn(&OutputInfo, &Capture) -> Result<C, PlatformError>,
+    ) -> Result<(), PlatformError> {
+        let config = LayerConfig {
+            output: Some(info.output.clone()),
+            ..self.config.clone()
+        };
+        let component = self
+            .platform
+            .with_layer_config(config, || factory(&info, &capture))?;
+
+        let weak = Rc::downgrade(self);
+        let weak_component = component.as_weak();
+        let output = info.output.clone();
+        let capture_width = capture.width() as f64;
+        // Touch points are released without a position, the last one is reported instead.
+        #[cfg(feature = "touch")]
+        let touch_position = Cell::new((0.0, 0.0));
+        self.platform
+            .add_raw_event_observer(component.window(), move |event| {
+                let (Some(this), Some(component)) = (weak.upgrade(), weak_component.upgrade())
+                else {
+                    return;
+                };
+                let (kind, position) = match event {
+                    RawSurfaceEvent::Pointer { event } => match event.kind {
+                        PointerEventKind::Press { .. } => (DrawEventKind::Press, event.position),
+                        PointerEventKind::Motion { .. } => (DrawEventKind::Motion, event.position),
+                        PointerEventKind::Release { .. } => {
+                            (DrawEventKind::Release, event.position)
+                        }
+                        _ => return,
+                    },
+                    #[cfg(feature = "touch")]
+                    RawSurfaceEvent::TouchDown { position, .. } => {
+                        touch_position.set(*position);
+                        (DrawEventKind::Press, *position)
+                    }
+                    #[cfg(feature = "touch")]
+                    RawSurfaceEvent::TouchMotion { position, .. } => {
+                        touch_position.set(*position);
+                        (DrawEventKind::Motion, *position)
+                    }
+                    #[cfg(feature = "touch")]
+                    RawSurfaceEvent::TouchUp { .. } => {
+                        (DrawEventKind::Release, touch_position.get())
+                    }
+                    _ => return,
+                };
+                let window_width = component
+                    .window()
+                    .size()
+                    .to_logical(component.window().scale_factor())
+                    .width
+                    .max(1.0) as f64;
+                let scale = capture_width / window_width;
+                if let Some(callback) = this.on_draw.borrow().as_ref() {
+                    callback(&DrawEvent {
+                        output: output.clone(),
+                        kind,
+                        position: (position.0 * scale, position.1 * scale),
+                    });
+                }
+            });
+
+        component.show()?;
+        self.windows.borrow_mut().push((info, capture, component));
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::output::OutputInfo;
+use crate::per_output::PerOutputWindows;
+use crate::platform::SlintLayerShell;
+use slint::{ComponentHandle, PlatformError, Timer, TimerMode};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// How often the inactivity of the pointer is checked.
+const CURSOR_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A full screen overlay on every output for signage and terminal deployments, which takes
+/// exclusive keyboard focus and all pointer and touch input, covering panels and other
+/// layer surfaces.
+///
+/// ```ignore
+/// let kiosk = Kiosk::new()
+///     .hide_cursor_after(Some(Duration::from_secs(3)))
+///     .spawn_per_output(&platform, |_output| Signage::new())?;
+/// slint::run_event_loop_until_quit()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Kiosk {
+    config: LayerConfig,
+    hide_cursor_after: Option<Duration>,
+}
+
+impl Default for Kiosk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Kiosk {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Overlay,
+                anchor: Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+                size: Some((0, 0)),
+                // Cover panels instead of being shrunk by their exclusive zones.
+                exclusive_zone: LayerConfig::IGNORE_EXCLUSIVE_ZONES,
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                namespace: "kiosk".into(),
+                ..LayerConfig::default()
+            },
+            hide_cursor_after: Some(Duration::from_secs(5)),
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// Hides the pointer cursor once no input was received for the given time, 5 seconds by
+    /// default, and shows it again when the pointer moves. `None` keeps it visible, as do
+    /// compositors without `wp_cursor_shape_manager_v1`, see [`SlintLayerShell::hide_cursor`].
+    pub fn hide_cursor_after(mut self, timeout: Option<Duration>) -> Self {
+        self.hide_cursor_after = timeout;
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Creates and shows the kiosk component on every output.
+    pub fn spawn_per_output<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<KioskWindows<C>, PlatformError> {
+        let windows = platform.spawn_per_output(self.config, factory)?;
+        let cursor_timer = self.hide_cursor_after.map(|timeout| {
+            let platform = platform.clone();
+            let hidden_at = Cell::new(None::<Instant>);
+            let timer = Timer::default();
+            timer.start(TimerMode::Repeated, CURSOR_CHECK_INTERVAL, move || {
+                let Some(last_input) = platform.last_input().map(|input| input.received) else {
+                    return;
+                };
+                let hidden = hidden_at.get().is_some_and(|hidden| hidden >= last_input);
+                if !hidden && last_input.elapsed() >= timeout {
+                    platform.hide_cursor();
+                    hidden_at.set(Some(Instant::now()));
+                }
+            });
+            timer
+        });
+        Ok(KioskWindows {
+            windows,
+            _cursor_timer: cursor_timer,
+        })
+    }
+}
+
+/// The windows created by [`Kiosk::spawn_per_output`], which stop following outputs and
+/// hiding the cursor when dropped.
+pub struct KioskWindows<C: ComponentHandle + 'static> {
+    windows: Rc<PerOutputWindows<C>>,
+    _cursor_timer: Option<Timer>,
+}
+
+impl<C: ComponentHandle + 'static> KioskWindows<C> {
+    pub fn windows(&self) -> &Rc<PerOutputWindows<C>> {
+        &self.windows
+    }
+}
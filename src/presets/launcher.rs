@@ -0,0 +1,171 @@
+use crate::event::RawSurfaceEvent;
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::platform::SlintLayerShell;
+use slint::{ComponentHandle, PlatformError};
+use smithay_client_toolkit::seat::keyboard::Keysym;
+use std::rc::Rc;
+
+/// A centered overlay taking exclusive keyboard focus while shown, as used by rofi or wofi
+/// style application launchers.
+///
+/// The launcher is created hidden and toggled with [`LauncherWindow::toggle`], typically from
+/// a Slint callback or a Unix signal. It hides itself again when it loses keyboard focus or
+/// when Escape is pressed. Since the launcher is often the only window of the application,
+/// run the event loop with [`slint::run_event_loop_until_quit`].
+///
+/// ```ignore
+/// let launcher = Launcher::new().create(&platform, || Search::new())?;
+/// launcher.toggle_on_signal(Signal::SIGUSR1)?;
+/// slint::run_event_loop_until_quit()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Launcher {
+    config: LayerConfig,
+    dismiss_on_focus_loss: bool,
+    dismiss_on_escape: bool,
+}
+
+impl Default for Launcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Launcher {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Top,
+                // Without anchors the compositor centers the surface on the output.
+                anchor: Anchor::empty(),
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                namespace: "launcher".into(),
+                ..LayerConfig::default()
+            },
+            dismiss_on_focus_loss: true,
+            dismiss_on_escape: true,
+        }
+    }
+
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.config.layer = layer;
+        self
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// Hides the launcher when the compositor moves keyboard focus elsewhere. Enabled by
+    /// default.
+    pub fn dismiss_on_focus_loss(mut self, enabled: bool) -> Self {
+        self.dismiss_on_focus_loss = enabled;
+        self
+    }
+
+    /// Hides the launcher when Escape is pressed. Enabled by default.
+    pub fn dismiss_on_escape(mut self, enabled: bool) -> Self {
+        self.dismiss_on_escape = enabled;
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Creates the launcher component without showing it.
+    pub fn create<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl FnOnce() -> Result<C, PlatformError>,
+    ) -> Result<Rc<LauncherWindow<C>>, PlatformError> {
+        let component = platform.with_layer_config(self.config, factory)?;
+        let launcher = Rc::new(LauncherWindow {
+            #[cfg(feature = "signals")]
+            platform: platform.clone(),
+            component,
+        });
+
+        let weak = Rc::downgrade(&launcher);
+        let (dismiss_on_focus_loss, dismiss_on_escape) =
+            (self.dismiss_on_focus_loss, self.dismiss_on_escape);
+        platform.add_raw_event_observer(launcher.component.window(), move |event| {
+            let Some(launcher) = weak.upgrade() else {
+                return;
+            };
+            let dismiss = match event {
+                RawSurfaceEvent::KeyboardLeave => dismiss_on_focus_loss,
+                RawSurfaceEvent::KeyPressed { event } => {
+                    dismiss_on_escape && event.keysym == Keysym::Escape
+                }
+                _ => false,
+            };
+            if dismiss {
+                let _ = launcher.hide();
+            }
+        });
+
+        Ok(launcher)
+    }
+}
+
+/// A launcher created by [`Launcher::create`].
+pub struct LauncherWindow<C: ComponentHandle + 'static> {
+    #[cfg(feature = "signals")]
+    platform: SlintLayerShell,
+    component: C,
+}
+
+impl<C: ComponentHandle + 'static> LauncherWindow<C> {
+    pub fn component(&self) -> &C {
+        &self.component
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.component.window().is_visible()
+    }
+
+    pub fn show(&self) -> Result<(), PlatformError> {
+        self.component.show()
+    }
+
+    pub fn hide(&self) -> Result<(), PlatformError> {
+        self.component.hide()
+    }
+
+    /// Shows the launcher if it is hidden, and hides it otherwise.
+    pub fn toggle(&self) -> Result<(), PlatformError> {
+        if self.is_visible() {
+            self.hide()
+        } else {
+            self.show()
+        }
+    }
+
+    /// Toggles the launcher whenever the process receives `signal`, so that a compositor
+    /// key binding can run e.g. `pkill -USR1 my-launcher`.
+    #[cfg(feature = "signals")]
+    pub fn toggle_on_signal(
+        self: &Rc<Self>,
+        signal: calloop::signals::Signal,
+    ) -> Result<(), PlatformError> {
+        self.platform.watch_signals(&[signal])?;
+
+        let weak = Rc::downgrade(self);
+        self.platform.add_event_listener(move |event| {
+            let Some(launcher) = weak.upgrade() else {
+                return false;
+            };
+            if let crate::event::BackendEvent::Signal { signal: received } = event {
+                if *received == signal {
+                    if let Err(err) = launcher.toggle() {
+                        log::warn!("failed to toggle launcher: {err}");
+                    }
+                }
+            }
+            true
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::event::BackendEvent;
+use crate::output::OutputInfo;
+use crate::platform::SlintLayerShell;
+use slint::{ComponentHandle, PlatformError};
+use smithay_client_toolkit::session_lock::SessionLock;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+type ComponentFactory<C> = Box<dyn Fn(&OutputInfo) -> Result<C, PlatformError>>;
+
+/// Locks the session with `ext_session_lock_v1` and covers every output with an instance of
+/// a Slint component until [`LockScreen::unlock`] is called.
+///
+/// Keyboard input goes to the lock surface the compositor focuses, so a password field in
+/// the component works as usual. Outputs connected while locked get a component too.
+///
+/// ```ignore
+/// let lock = LockScreen::lock(&platform, |_output| Prompt::new())?;
+/// let weak = Rc::downgrade(&lock);
+/// prompt.on_accepted(move || weak.upgrade().map(|lock| lock.unlock()));
+/// ```
+pub struct LockScreen<C: ComponentHandle + 'static> {
+    platform: SlintLayerShell,
+    session_lock: SessionLock,
+    factory: ComponentFactory<C>,
+    windows: RefCell<Vec<(OutputInfo, C)>>,
+    locked: Cell<bool>,
+    finished: Cell<bool>,
+    on_finished: RefCell<Option<Box<dyn FnOnce()>>>,
+}
+
+impl<C: ComponentHandle + 'static> LockScreen<C> {
+    /// Requests the lock and shows one component per output.
+    ///
+    /// The compositor only confirms the lock once every output has a lock surface, see
+    /// [`Self::is_locked`].
+    pub fn lock(
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<Self>, PlatformError> {
+        let this = Rc::new(Self {
+            platform: platform.clone(),
+            session_lock: platform.lock_session()?,
+            factory: Box::new(factory),
+            windows: RefCell::new(Vec::new()),
+            locked: Cell::new(false),
+            finished: Cell::new(false),
+            on_finished: RefCell::new(None),
+        });
+        this.sync()?;
+
+        let weak = Rc::downgrade(&this);
+        platform.add_event_listener(move |event| {
+            let Some(this) = weak.upgrade() else {
+                return false;
+            };
+            match event {
+                BackendEvent::SessionLocked => this.locked.set(true),
+                BackendEvent::SessionLockFinished => {
+                    this.finish();
+                    return false;
+                }
+                BackendEvent::OutputAdded { .. } | BackendEvent::OutputRemoved { .. } => {
+                    if let Err(err) = this.sync() {
+                        log::warn!("failed to update lock surfaces: {err}");
+                    }
+                }
+                _ => {}
+            }
+            true
+        });
+
+        Ok(this)
+    }
+
+    /// Returns whether the compositor confirmed the lock, and it has not ended yet.
+    pub fn is_locked(&self) -> bool {
+        self.locked.get() && !self.finished.get()
+    }
+
+    /// Returns whether the lock ended, through [`Self::unlock`] or by the compositor.
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+
+    /// Unlocks the session and drops the lock screen components.
+    pub fn unlock(&self) {
+        if self.finished.get() {
+            return;
+        }
+        // Unlocking a lock that was never confirmed is a protocol error, destroying it is not.
+        if self.locked.get() {
+            self.session_lock.unlock();
+        }
+        self.finish();
+        let _ = self.platform.connection().flush();
+    }
+
+    /// Sets a callback invoked once the lock ended, however it ended.
+    pub fn on_finished(&self, callback: impl FnOnce() + 'static) {
+        *self.on_finished.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Returns the components together with the output they cover.
+    pub fn components(&self) -> Vec<(OutputInfo, C)> {
+        self.windows
+            .borrow()
+            .iter()
+            .map(|(info, component)| (info.clone(), component.clone_strong()))
+            .collect()
+    }
+
+    fn finish(&self) {
+        self.finished.set(true);
+        let windows = std::mem::take(&mut *self.windows.borrow_mut());
+        // Shown windows are kept alive by Slint until hidden. Hiding destroys the lock surfaces
+        // instead of unmapping them, which the protocol forbids.
+        for (_, component) in windows {
+            let _ = component.hide();
+        }
+        let on_finished = self.on_finished.borrow_mut().take();
+        if let Some(on_finished) = on_finished {
+            on_finished();
+        }
+    }
+
+    fn sync(&self) -> Result<(), PlatformError> {
+        if self.finished.get() {
+            return Ok(());
+        }
+        let outputs = self.platform.outputs();
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .windows
+            .take()
+            .into_iter()
+            .partition(|(info, _)| outputs.iter().any(|output| output.output == info.output));
+        *self.windows.borrow_mut() = kept;
+        // Dropped without hiding, Slint would keep the windows of removed outputs alive.
+        for (_, component) in removed {
+            let _ = component.hide();
+        }
+
+        for info in outputs {
+            let exists = self
+                .windows
+                .borrow()
+                .iter()
+                .any(|(existing, _)| existing.output == info.output);
+            if exists {
+                continue;
+            }
+
+            let component = self.platform.with_lock_surface(
+                self.session_lock.clone(),
+                info.output.clone(),
+                || (self.factory)(&info),
+            )?;
+            component.show()?;
+            self.windows.borrow_mut().push((info, component));
+        }
+
+        Ok(())
+    }
+}
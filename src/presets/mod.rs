@@ -0,0 +1,27 @@
+//! Ready-made layer configurations for common kinds of shell components.
+
+#[cfg(feature = "screencopy")]
+mod color_picker;
+#[cfg(feature = "screencopy")]
+mod freeze_frame;
+mod kiosk;
+mod launcher;
+#[cfg(feature = "session-lock")]
+mod lock_screen;
+#[cfg(feature = "virtual-keyboard")]
+mod osk;
+mod panel;
+mod wallpaper;
+
+#[cfg(feature = "screencopy")]
+pub use color_picker::{ColorPicker, PickerState};
+#[cfg(feature = "screencopy")]
+pub use freeze_frame::{DrawEvent, DrawEventKind, FreezeFrame, FrozenOutputs};
+pub use kiosk::{Kiosk, KioskWindows};
+pub use launcher::{Launcher, LauncherWindow};
+#[cfg(feature = "session-lock")]
+pub use lock_screen::LockScreen;
+#[cfg(feature = "virtual-keyboard")]
+pub use osk::{Osk, OskWindow};
+pub use panel::{Edge, Panel};
+pub use wallpaper::Wallpaper;
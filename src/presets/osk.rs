@@ -0,0 +1,130 @@
+use crate::event::BackendEvent;
+use crate::layer::{KeyboardInteractivity, Layer, LayerConfig};
+use crate::platform::SlintLayerShell;
+use crate::presets::Edge;
+use crate::virtual_keyboard::{DEFAULT_KEYMAP, VirtualKeyboard};
+use slint::{ComponentHandle, PlatformError, Timer};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// An on-screen keyboard docked to the bottom edge, typing into other applications.
+///
+/// The surface never takes keyboard focus, so the focused application keeps receiving the
+/// keys sent through [`OskWindow::keyboard`], and it reserves its height so that windows
+/// are not covered.
+///
+/// ```ignore
+/// let osk = Osk::new().show(&platform, || Layout::new())?;
+/// let keyboard = osk.keyboard().clone();
+/// osk.component().on_key_tapped(move |code| keyboard.tap(code as u32));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Osk {
+    config: LayerConfig,
+    keymap: String,
+}
+
+impl Default for Osk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Osk {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Overlay,
+                anchor: Edge::Bottom.anchor(),
+                auto_exclusive_zone: true,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                namespace: "osk".into(),
+                ..LayerConfig::default()
+            },
+            keymap: DEFAULT_KEYMAP.into(),
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// XKB keymap, in text form, used to interpret the key codes sent by the keyboard.
+    pub fn keymap(mut self, keymap: impl Into<String>) -> Self {
+        self.keymap = keymap.into();
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Shows the keyboard layout component and creates the virtual keyboard it types with.
+    pub fn show<C: ComponentHandle>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl FnOnce() -> Result<C, PlatformError>,
+    ) -> Result<OskWindow<C>, PlatformError> {
+        let keyboard = Rc::new(platform.create_virtual_keyboard(&self.keymap)?);
+        let component = platform.with_layer_config(self.config, factory)?;
+        component.show()?;
+        Ok(OskWindow {
+            component,
+            keyboard,
+        })
+    }
+}
+
+/// An on-screen keyboard shown with [`Osk::show`].
+pub struct OskWindow<C: ComponentHandle> {
+    component: C,
+    keyboard: Rc<VirtualKeyboard>,
+}
+
+impl<C: ComponentHandle> OskWindow<C> {
+    pub fn component(&self) -> &C {
+        &self.component
+    }
+
+    pub fn keyboard(&self) -> &Rc<VirtualKeyboard> {
+        &self.keyboard
+    }
+
+    /// Hides the keyboard, and shows it only while a text field has focus in one of the
+    /// windows of this application.
+    ///
+    /// Moving focus between text fields does not hide and re-show the keyboard.
+    pub fn follow_text_input(&self, platform: &SlintLayerShell) -> Result<(), PlatformError> {
+        self.component.hide()?;
+        let weak = self.component.as_weak();
+        let wanted = Rc::new(Cell::new(false));
+        platform.add_event_listener(move |event| {
+            let visible = match event {
+                BackendEvent::TextInputEnabled { .. } => true,
+                BackendEvent::TextInputDisabled { .. } => false,
+                _ => return weak.upgrade().is_some(),
+            };
+            wanted.set(visible);
+            let weak = weak.clone();
+            let wanted = wanted.clone();
+            // Let a focus change settle before showing or hiding the surface.
+            Timer::single_shot(Duration::ZERO, move || {
+                let Some(component) = weak.upgrade() else {
+                    return;
+                };
+                let result = if wanted.get() {
+                    component.show()
+                } else {
+                    component.hide()
+                };
+                if let Err(err) = result {
+                    log::warn!("failed to update the on-screen keyboard: {err}");
+                }
+            });
+            true
+        });
+        Ok(())
+    }
+}
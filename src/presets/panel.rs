@@ -0,0 +1,218 @@
+use crate::event::RawSurfaceEvent;
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig, Margins};
+use crate::output::OutputInfo;
+use crate::per_output::PerOutputWindows;
+use crate::platform::SlintLayerShell;
+use crate::window_adapter::LayerShellWindowAdapter;
+use crate::window_ext::WindowExt;
+use slint::platform::WindowAdapter;
+use slint::{ComponentHandle, PlatformError};
+use smithay_client_toolkit::seat::pointer::{BTN_LEFT, PointerEventKind};
+use std::cell::Cell;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// Screen edge a [`Panel`] is attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    /// Anchors spanning the whole edge.
+    pub fn anchor(self) -> Anchor {
+        match self {
+            Edge::Top => Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+            Edge::Bottom => Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+            Edge::Left => Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM,
+            Edge::Right => Anchor::RIGHT | Anchor::TOP | Anchor::BOTTOM,
+        }
+    }
+
+    /// Returns the thickness a panel on this edge takes when its inner edge is dragged to
+    /// `position`, in surface coordinates of a panel that is `thickness` thick.
+    fn dragged_thickness(self, position: (f64, f64), thickness: f64) -> f64 {
+        match self {
+            Edge::Top => position.1,
+            Edge::Left => position.0,
+            Edge::Bottom => thickness - position.1,
+            Edge::Right => thickness - position.0,
+        }
+    }
+}
+
+/// Thickness limits and grab area of a panel resized by dragging, see [`Panel::resizable`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ResizeHandle {
+    range: RangeInclusive<u32>,
+    width: u32,
+}
+
+/// A bar spanning a screen edge, reserving as much space as its content is thick.
+///
+/// ```ignore
+/// let platform = SlintLayerShell::builder().build()?;
+/// slint::platform::set_platform(Box::new(platform.clone()))?;
+/// let _bars = Panel::new(Edge::Top)
+///     .namespace("my-bar")
+///     .spawn_per_output(&platform, |_output| Bar::new())?;
+/// slint::run_event_loop()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Panel {
+    config: LayerConfig,
+    edge: Edge,
+    resize: Option<ResizeHandle>,
+}
+
+impl Panel {
+    pub fn new(edge: Edge) -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Top,
+                anchor: edge.anchor(),
+                auto_exclusive_zone: true,
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                namespace: "panel".into(),
+                ..LayerConfig::default()
+            },
+            edge,
+            resize: None,
+        }
+    }
+
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.config.layer = layer;
+        self
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    /// Margins in logical pixels, e.g. `Margins::horizontal(8)` for a floating bar.
+    pub fn margins(mut self, margins: impl Into<Margins>) -> Self {
+        self.config.margins = margins.into();
+        self
+    }
+
+    /// Fixed thickness in logical pixels, instead of the preferred size of the content.
+    pub fn thickness(mut self, thickness: u32) -> Self {
+        let (stretch_width, _) = self.config.stretched();
+        self.config.size = Some(if stretch_width {
+            (0, thickness)
+        } else {
+            (thickness, 0)
+        });
+        self
+    }
+
+    /// Lets the user resize the panel by dragging its inner edge with the left button, within
+    /// `handle_width` logical pixels of it. The thickness follows the pointer within `range`,
+    /// and the exclusive zone follows the thickness.
+    pub fn resizable(mut self, range: RangeInclusive<u32>, handle_width: u32) -> Self {
+        self.resize = Some(ResizeHandle {
+            range,
+            width: handle_width,
+        });
+        self
+    }
+
+    pub fn keyboard_interactivity(mut self, interactivity: KeyboardInteractivity) -> Self {
+        self.config.keyboard_interactivity = interactivity;
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Creates and shows a single panel on the output picked by the platform.
+    pub fn show<C: ComponentHandle>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl FnOnce() -> Result<C, PlatformError>,
+    ) -> Result<C, PlatformError> {
+        let component = platform.with_layer_config(self.config, factory)?;
+        if let Some(resize) = self.resize {
+            install_resize_handle(platform, component.window(), self.edge, resize);
+        }
+        component.show()?;
+        Ok(component)
+    }
+
+    /// Creates and shows a panel on every output, following output hotplug.
+    pub fn spawn_per_output<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<PerOutputWindows<C>>, PlatformError> {
+        let Some(resize) = self.resize else {
+            return platform.spawn_per_output(self.config, factory);
+        };
+        let edge = self.edge;
+        let handle_platform = platform.clone();
+        platform.spawn_per_output(self.config, move |output| {
+            let component = factory(output)?;
+            install_resize_handle(&handle_platform, component.window(), edge, resize.clone());
+            Ok(component)
+        })
+    }
+}
+
+/// Resizes the layer surface of `window` while its inner edge is dragged.
+fn install_resize_handle(
+    platform: &SlintLayerShell,
+    window: &slint::Window,
+    edge: Edge,
+    resize: ResizeHandle,
+) {
+    let Some(window_adapter) = LayerShellWindowAdapter::from_slint_window(window) else {
+        return;
+    };
+    let window_adapter = Rc::downgrade(&window_adapter);
+    let dragging = Cell::new(false);
+    platform.add_raw_event_observer(window, move |event| {
+        let RawSurfaceEvent::Pointer { event } = event else {
+            return;
+        };
+        let Some(window_adapter) = window_adapter.upgrade() else {
+            return;
+        };
+        let window = window_adapter.window();
+        let size = window.size().to_logical(window.scale_factor());
+        let thickness = match edge {
+            Edge::Top | Edge::Bottom => size.height,
+            Edge::Left | Edge::Right => size.width,
+        } as f64;
+        let dragged = edge.dragged_thickness(event.position, thickness);
+        match event.kind {
+            PointerEventKind::Press { button, .. } if button == BTN_LEFT => {
+                let distance = thickness - dragged;
+                dragging.set((0.0..=resize.width as f64).contains(&distance));
+            }
+            PointerEventKind::Release { button, .. } if button == BTN_LEFT => {
+                dragging.set(false);
+            }
+            PointerEventKind::Motion { .. } if dragging.get() => {
+                let thickness = (dragged.round().max(0.0) as u32)
+                    .clamp(*resize.range.start(), *resize.range.end());
+                let result = window.update_layer_config(|config| {
+                    config.size = Some(match edge {
+                        Edge::Top | Edge::Bottom => (0, thickness),
+                        Edge::Left | Edge::Right => (thickness, 0),
+                    });
+                });
+                if let Err(err) = result {
+                    log::warn!("failed to resize the panel: {err}");
+                    dragging.set(false);
+                }
+            }
+            _ => {}
+        }
+    });
+}
@@ -0,0 +1,57 @@
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig};
+use crate::output::OutputInfo;
+use crate::per_output::PerOutputWindows;
+use crate::platform::SlintLayerShell;
+use slint::{ComponentHandle, PlatformError};
+use std::rc::Rc;
+
+/// A full screen background surface on every output, which never takes input.
+///
+/// Surfaces are re-created as outputs are hotplugged, and resized by the compositor when the
+/// resolution of an output changes.
+#[derive(Clone, Debug)]
+pub struct Wallpaper {
+    config: LayerConfig,
+}
+
+impl Default for Wallpaper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wallpaper {
+    pub fn new() -> Self {
+        Self {
+            config: LayerConfig {
+                layer: Layer::Background,
+                anchor: Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+                size: Some((0, 0)),
+                // Extend below panels instead of being shrunk by their exclusive zones.
+                exclusive_zone: LayerConfig::IGNORE_EXCLUSIVE_ZONES,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                pass_through_input: true,
+                namespace: "wallpaper".into(),
+                ..LayerConfig::default()
+            },
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.config.namespace = namespace.into();
+        self
+    }
+
+    pub fn layer_config(&self) -> &LayerConfig {
+        &self.config
+    }
+
+    /// Creates and shows the wallpaper component on every output.
+    pub fn spawn_per_output<C: ComponentHandle + 'static>(
+        self,
+        platform: &SlintLayerShell,
+        factory: impl Fn(&OutputInfo) -> Result<C, PlatformError> + 'static,
+    ) -> Result<Rc<PerOutputWindows<C>>, PlatformError> {
+        platform.spawn_per_output(self.config, factory)
+    }
+}
@@ -0,0 +1,203 @@
+//! Detection of the running compositor, and the workarounds applied for it.
+//!
+//! Behavior that differs between compositors is decided here rather than where it is used,
+//! so that workarounds can be inspected with [`SlintLayerShell::quirks`] and turned off with
+//! [`SlintLayerShell::set_quirks`] or `SLINT_LAYER_SHELL_QUIRKS=off` when a compositor update
+//! makes them unnecessary.
+
+use crate::platform::{LayerShellState, SlintLayerShell};
+use std::fmt;
+use std::time::Duration;
+
+/// Environment variable that disables all workarounds when set to `off`.
+pub const QUIRKS_ENV: &str = "SLINT_LAYER_SHELL_QUIRKS";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+    Kwin,
+    Niri,
+    Wayfire,
+    Other,
+}
+
+impl Compositor {
+    /// Identifies the compositor from the environment variables it sets for its clients, or
+    /// from the private globals it advertises.
+    pub(crate) fn detect(state: &LayerShellState) -> Self {
+        let env = |name: &str| std::env::var_os(name).is_some();
+        if env("HYPRLAND_INSTANCE_SIGNATURE") {
+            return Self::Hyprland;
+        }
+        if env("NIRI_SOCKET") {
+            return Self::Niri;
+        }
+        if env("SWAYSOCK") {
+            return Self::Sway;
+        }
+        if env("WAYFIRE_SOCKET") {
+            return Self::Wayfire;
+        }
+
+        let globals = state.registry_state.globals().collect::<Vec<_>>();
+        let advertises = |prefix: &str| {
+            globals
+                .iter()
+                .any(|global| global.interface.starts_with(prefix))
+        };
+        if advertises("hyprland_") {
+            Self::Hyprland
+        } else if advertises("org_kde_kwin_") || advertises("kde_") {
+            Self::Kwin
+        } else if advertises("wayfire_") {
+            Self::Wayfire
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl fmt::Display for Compositor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hyprland => "Hyprland",
+            Self::Sway => "sway",
+            Self::Kwin => "KWin",
+            Self::Niri => "niri",
+            Self::Wayfire => "Wayfire",
+            Self::Other => "unknown compositor",
+        })
+    }
+}
+
+/// Workarounds for compositor behavior, see [`SlintLayerShell::set_quirks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// How long a window waits for a frame callback before rendering pending changes anyway,
+    /// for compositors that stop sending callbacks to surfaces they do not show.
+    pub frame_callback_timeout: Duration,
+    /// Requests exclusive keyboard interactivity in place of on-demand, for compositors that
+    /// advertise version 4 of the layer shell but never focus on-demand surfaces.
+    pub on_demand_as_exclusive: bool,
+    /// Keeps the size set in the layer configuration for axes that are not stretched when a
+    /// configure event asks for another one.
+    pub prefer_requested_size: bool,
+    /// Namespaces replaced when creating layer surfaces, for compositors that give some
+    /// namespaces a meaning. KWin derives the window type of layer surfaces from their
+    /// namespace, and only places and animates `dock` and `desktop` surfaces as such.
+    pub namespaces: &'static [(&'static str, &'static str)],
+    /// How blur behind the windows is turned on, which the crate cannot request itself.
+    pub blur: Blur,
+}
+
+/// How a compositor blurs what is behind a window, see [`Quirks::blur`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blur {
+    /// The compositor does not blur.
+    Unsupported,
+    /// The user adds a rule matching the namespace to the compositor's configuration, see
+    /// [`Blur::rule`].
+    NamespaceRule,
+    /// The client requests it with `org_kde_kwin_blur`, which Plasma's own shell does.
+    KdeProtocol,
+}
+
+impl Blur {
+    /// Returns the configuration lines blurring the layer surfaces of `namespace`, for
+    /// compositors matching rules against namespaces.
+    pub fn rule(self, compositor: Compositor, namespace: &str) -> Option<String> {
+        match (self, compositor) {
+            // Transparent pixels are left unblurred, so rounded corners stay round.
+            (Self::NamespaceRule, Compositor::Hyprland) => Some(format!(
+                "layerrule = blur, ^({namespace})$\nlayerrule = ignorezero, ^({namespace})$"
+            )),
+            (Self::NamespaceRule, Compositor::Sway) => {
+                Some(format!("layer_effects \"{namespace}\" blur enable"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Window types KWin derives from namespaces, for the presets' namespaces.
+const KWIN_NAMESPACES: &[(&str, &str)] = &[("panel", "dock"), ("wallpaper", "desktop")];
+
+impl Quirks {
+    /// Behavior following the protocols, without workarounds.
+    pub const NONE: Self = Self {
+        frame_callback_timeout: Duration::from_secs(1),
+        on_demand_as_exclusive: false,
+        prefer_requested_size: false,
+        namespaces: &[],
+        blur: Blur::Unsupported,
+    };
+
+    /// Returns the workarounds known to be needed by `compositor`.
+    ///
+    /// Keyboard focus of on-demand surfaces follows the protocol on current versions of
+    /// these compositors, focusing them on click, so [`Self::on_demand_as_exclusive`] is
+    /// left to applications targeting older ones.
+    pub fn for_compositor(compositor: Compositor) -> Self {
+        match compositor {
+            // Blur is set up with `layerrule` in hyprland.conf.
+            Compositor::Hyprland => Self {
+                blur: Blur::NamespaceRule,
+                ..Self::NONE
+            },
+            // Only SwayFX blurs, with `layer_effects` in its configuration.
+            Compositor::Sway => Self {
+                blur: Blur::NamespaceRule,
+                ..Self::NONE
+            },
+            Compositor::Kwin => Self {
+                namespaces: KWIN_NAMESPACES,
+                blur: Blur::KdeProtocol,
+                ..Self::NONE
+            },
+            // Layer rules match namespaces, but only set opacity, shadows and corner radii.
+            Compositor::Niri => Self::NONE,
+            Compositor::Wayfire | Compositor::Other => Self::NONE,
+        }
+    }
+
+    /// Returns the namespace to create a layer surface with in place of `namespace`.
+    pub fn namespace<'a>(&self, namespace: &'a str) -> &'a str {
+        self.namespaces
+            .iter()
+            .find(|(from, _)| *from == namespace)
+            .map_or(namespace, |(_, to)| *to)
+    }
+
+    /// Returns the workarounds for `compositor`, unless disabled through [`QUIRKS_ENV`].
+    pub(crate) fn from_env(compositor: Compositor) -> Self {
+        match std::env::var(QUIRKS_ENV).as_deref() {
+            Ok("off" | "0" | "false") => Self::NONE,
+            _ => Self::for_compositor(compositor),
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl SlintLayerShell {
+    /// Returns the compositor the platform is connected to, as far as it can be told.
+    pub fn compositor(&self) -> Compositor {
+        self.compositor
+    }
+
+    /// Returns the workarounds in effect.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks.get()
+    }
+
+    /// Replaces the workarounds chosen for the detected compositor, e.g. with
+    /// [`Quirks::NONE`]. Applies to layer configurations committed from now on.
+    pub fn set_quirks(&self, quirks: Quirks) {
+        self.quirks.set(quirks);
+    }
+}
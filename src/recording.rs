@@ -0,0 +1,377 @@
+//! Recording of the input and configure events forwarded to Slint windows, and their replay,
+//! to reproduce input handling bugs from a recording made by a user.
+//!
+//! Recording starts with [`SlintLayerShell::start_recording`] or by setting
+//! `SLINT_LAYER_SHELL_RECORD` to a file path. Each line of the file holds one event as tab
+//! separated fields: milliseconds since the recording started, the window, the event name and
+//! its arguments. Windows are identified by their layer namespace, or `toplevel` for xdg
+//! toplevels, and their index among the windows sharing it, e.g. `bar#1` for the second bar
+//! created. A recording thus replays into the windows of a new run of the same application.
+
+use crate::platform::{LayerShellState, SlintLayerShell};
+use crate::window_adapter::LayerShellWindowAdapter;
+use i_slint_core::SharedString;
+use i_slint_core::api::{LogicalPosition, LogicalSize};
+use i_slint_core::input::PointerEventButton;
+use i_slint_core::platform::WindowEvent;
+use slint::PlatformError;
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+
+/// Environment variable holding the path of a file to record the events of the session to.
+pub const RECORD_ENV: &str = "SLINT_LAYER_SHELL_RECORD";
+
+/// Writes forwarded events to a file, shared by the platform and the window adapters.
+pub(crate) struct EventRecorder {
+    writer: LineWriter<File>,
+    start: Instant,
+}
+
+pub(crate) type SharedRecorder = Rc<RefCell<Option<EventRecorder>>>;
+
+impl EventRecorder {
+    fn create(path: &Path) -> Result<Self, PlatformError> {
+        let file = File::create(path).map_err(|e| {
+            PlatformError::Other(format!("failed to create {}: {e}", path.display()))
+        })?;
+        Ok(Self {
+            writer: LineWriter::new(file),
+            start: Instant::now(),
+        })
+    }
+
+    /// Returns a recorder writing to the file named by [`RECORD_ENV`], if set.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os(RECORD_ENV)?;
+        match Self::create(Path::new(&path)) {
+            Ok(recorder) => Some(recorder),
+            Err(err) => {
+                log::warn!("not recording events: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn record(&mut self, window: &str, event: &WindowEvent) {
+        let Some(event) = encode_event(event) else {
+            return;
+        };
+        let time = self.start.elapsed().as_millis();
+        if let Err(err) = writeln!(self.writer, "{time}\t{window}\t{event}") {
+            log::warn!("failed to record event: {err}");
+        }
+    }
+}
+
+impl LayerShellWindowAdapter {
+    /// Name identifying the window in recordings.
+    pub(crate) fn recording_key(&self) -> String {
+        format!("{}#{}", self.recording_name(), self.recording_index.get())
+    }
+
+    fn recording_name(&self) -> String {
+        self.layer_config
+            .borrow()
+            .as_ref()
+            .map_or_else(|| "toplevel".into(), |config| config.namespace.clone())
+    }
+
+    /// Gives the window the lowest index no other window with the same name has, as
+    /// per-output windows and presets usually share a namespace.
+    pub(crate) fn assign_recording_index(&self, state: &LayerShellState) {
+        let name = self.recording_name();
+        let taken = state
+            .window_adapters
+            .values()
+            .filter_map(Weak::upgrade)
+            .filter(|window_adapter| window_adapter.recording_name() == name)
+            .map(|window_adapter| window_adapter.recording_index.get())
+            .collect::<HashSet<_>>();
+        let index = (0..)
+            .find(|index| !taken.contains(index))
+            .unwrap_or_default();
+        self.recording_index.set(index);
+    }
+}
+
+impl SlintLayerShell {
+    /// Records the events forwarded to all windows to `path`, replacing the file and any
+    /// recording in progress.
+    pub fn start_recording(&self, path: impl AsRef<Path>) -> Result<(), PlatformError> {
+        let recorder = EventRecorder::create(path.as_ref())?;
+        *self.state.borrow().recorder.borrow_mut() = Some(recorder);
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) {
+        self.state.borrow().recorder.borrow_mut().take();
+    }
+
+    /// Dispatches the events recorded in `path` to the windows with the same names, with
+    /// the timing they were recorded with. Events for windows that do not exist when it is
+    /// their turn are skipped.
+    ///
+    /// The events are replayed by timers of the running event loop.
+    pub fn replay(&self, path: impl AsRef<Path>) -> Result<(), PlatformError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| PlatformError::Other(format!("failed to read {}: {e}", path.display())))?;
+        let events = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(index, line)| {
+                parse_line(line).ok_or_else(|| {
+                    PlatformError::Other(format!(
+                        "{}:{}: invalid event `{line}`",
+                        path.display(),
+                        index + 1
+                    ))
+                })
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        let replay = Rc::new(Replay {
+            state: Rc::downgrade(&self.state),
+            events: RefCell::new(events),
+            start: Instant::now(),
+        });
+        replay.schedule();
+        Ok(())
+    }
+}
+
+struct RecordedEvent {
+    time: Duration,
+    window: String,
+    event: WindowEvent,
+}
+
+struct Replay {
+    state: Weak<RefCell<LayerShellState>>,
+    events: RefCell<VecDeque<RecordedEvent>>,
+    start: Instant,
+}
+
+impl Replay {
+    /// Waits for the next event to be due, then dispatches every due event.
+    fn schedule(self: Rc<Self>) {
+        let Some(next) = self.events.borrow().front().map(|event| event.time) else {
+            return;
+        };
+        let delay = next.saturating_sub(self.start.elapsed());
+        slint::Timer::single_shot(delay, move || {
+            let Some(state) = self.state.upgrade() else {
+                return;
+            };
+            loop {
+                let event = {
+                    let mut events = self.events.borrow_mut();
+                    match events.front() {
+                        Some(event) if event.time <= self.start.elapsed() => events.pop_front(),
+                        _ => None,
+                    }
+                };
+                let Some(event) = event else {
+                    break;
+                };
+                let window_adapter = state
+                    .borrow()
+                    .window_adapters
+                    .values()
+                    .filter_map(Weak::upgrade)
+                    .find(|window_adapter| window_adapter.recording_key() == event.window);
+                if let Some(window_adapter) = window_adapter {
+                    window_adapter.forward_event(event.event);
+                }
+            }
+            self.schedule();
+        });
+    }
+}
+
+fn encode_event(event: &WindowEvent) -> Option<String> {
+    Some(match event {
+        WindowEvent::KeyPressed { text } => format!("KeyPressed\t{}", encode_text(text)),
+        WindowEvent::KeyPressRepeated { text } => {
+            format!("KeyPressRepeated\t{}", encode_text(text))
+        }
+        WindowEvent::KeyReleased { text } => format!("KeyReleased\t{}", encode_text(text)),
+        WindowEvent::PointerPressed { position, button } => format!(
+            "PointerPressed\t{}\t{}\t{}",
+            position.x,
+            position.y,
+            encode_button(*button)
+        ),
+        WindowEvent::PointerReleased { position, button } => format!(
+            "PointerReleased\t{}\t{}\t{}",
+            position.x,
+            position.y,
+            encode_button(*button)
+        ),
+        WindowEvent::PointerMoved { position } => {
+            format!("PointerMoved\t{}\t{}", position.x, position.y)
+        }
+        WindowEvent::PointerScrolled {
+            position,
+            delta_x,
+            delta_y,
+        } => format!(
+            "PointerScrolled\t{}\t{}\t{delta_x}\t{delta_y}",
+            position.x, position.y
+        ),
+        WindowEvent::PointerExited => "PointerExited".into(),
+        WindowEvent::Resized { size } => format!("Resized\t{}\t{}", size.width, size.height),
+        WindowEvent::ScaleFactorChanged { scale_factor } => {
+            format!("ScaleFactorChanged\t{scale_factor}")
+        }
+        WindowEvent::WindowActiveChanged(active) => format!("WindowActiveChanged\t{active}"),
+        _ => return None,
+    })
+}
+
+fn parse_line(line: &str) -> Option<RecordedEvent> {
+    let mut fields = line.split('\t');
+    let time = Duration::from_millis(fields.next()?.parse().ok()?);
+    let window = fields.next()?.to_string();
+    let name = fields.next()?;
+    let args = fields.collect::<Vec<_>>();
+    let number = |index: usize| args.get(index)?.parse::<f32>().ok();
+    let position = || Some(LogicalPosition::new(number(0)?, number(1)?));
+
+    let event = match name {
+        "KeyPressed" => WindowEvent::KeyPressed {
+            text: decode_text(args.first()?)?,
+        },
+        "KeyPressRepeated" => WindowEvent::KeyPressRepeated {
+            text: decode_text(args.first()?)?,
+        },
+        "KeyReleased" => WindowEvent::KeyReleased {
+            text: decode_text(args.first()?)?,
+        },
+        "PointerPressed" => WindowEvent::PointerPressed {
+            position: position()?,
+            button: decode_button(args.get(2)?),
+        },
+        "PointerReleased" => WindowEvent::PointerReleased {
+            position: position()?,
+            button: decode_button(args.get(2)?),
+        },
+        "PointerMoved" => WindowEvent::PointerMoved {
+            position: position()?,
+        },
+        "PointerScrolled" => WindowEvent::PointerScrolled {
+            position: position()?,
+            delta_x: number(2)?,
+            delta_y: number(3)?,
+        },
+        "PointerExited" => WindowEvent::PointerExited,
+        "Resized" => WindowEvent::Resized {
+            size: LogicalSize::new(number(0)?, number(1)?),
+        },
+        "ScaleFactorChanged" => WindowEvent::ScaleFactorChanged {
+            scale_factor: number(0)?,
+        },
+        "WindowActiveChanged" => WindowEvent::WindowActiveChanged(args.first()?.parse().ok()?),
+        _ => return None,
+    };
+    Some(RecordedEvent {
+        time,
+        window,
+        event,
+    })
+}
+
+/// Writes key text as hexadecimal code points, as it is often a control character.
+fn encode_text(text: &str) -> String {
+    text.chars()
+        .map(|c| format!("{:x}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_text(text: &str) -> Option<SharedString> {
+    text.split(' ')
+        .map(|code| char::from_u32(u32::from_str_radix(code, 16).ok()?))
+        .collect::<Option<String>>()
+        .map(Into::into)
+}
+
+fn encode_button(button: PointerEventButton) -> &'static str {
+    match button {
+        PointerEventButton::Left => "left",
+        PointerEventButton::Right => "right",
+        PointerEventButton::Middle => "middle",
+        _ => "other",
+    }
+}
+
+fn decode_button(button: &str) -> PointerEventButton {
+    match button {
+        "left" => PointerEventButton::Left,
+        "right" => PointerEventButton::Right,
+        "middle" => PointerEventButton::Middle,
+        _ => PointerEventButton::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trip(event: WindowEvent) {
+        let line = format!("1500\tbar#1\t{}", encode_event(&event).unwrap());
+        let recorded = parse_line(&line).unwrap();
+        assert_eq!(recorded.time, Duration::from_millis(1500));
+        assert_eq!(recorded.window, "bar#1");
+        assert_eq!(recorded.event, event);
+    }
+
+    #[test]
+    fn events_round_trip() {
+        assert_round_trip(WindowEvent::KeyPressed { text: "\n".into() });
+        assert_round_trip(WindowEvent::KeyPressRepeated { text: "ab".into() });
+        assert_round_trip(WindowEvent::KeyReleased { text: "é".into() });
+        assert_round_trip(WindowEvent::PointerPressed {
+            position: LogicalPosition::new(12.5, 3.0),
+            button: PointerEventButton::Right,
+        });
+        assert_round_trip(WindowEvent::PointerReleased {
+            position: LogicalPosition::new(0.0, 0.0),
+            button: PointerEventButton::Left,
+        });
+        assert_round_trip(WindowEvent::PointerMoved {
+            position: LogicalPosition::new(-1.0, 7.25),
+        });
+        assert_round_trip(WindowEvent::PointerScrolled {
+            position: LogicalPosition::new(1.0, 2.0),
+            delta_x: 0.0,
+            delta_y: -15.5,
+        });
+        assert_round_trip(WindowEvent::PointerExited);
+        assert_round_trip(WindowEvent::Resized {
+            size: LogicalSize::new(800.0, 32.0),
+        });
+        assert_round_trip(WindowEvent::ScaleFactorChanged { scale_factor: 1.25 });
+        assert_round_trip(WindowEvent::WindowActiveChanged(true));
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("soon\tbar#0\tPointerExited").is_none());
+        assert!(parse_line("0\tbar#0\tUnknown").is_none());
+        assert!(parse_line("0\tbar#0\tPointerMoved\t1").is_none());
+        assert!(parse_line("0\tbar#0\tKeyPressed\tzz").is_none());
+    }
+
+    #[test]
+    fn unsupported_events_are_not_recorded() {
+        assert!(encode_event(&WindowEvent::CloseRequested).is_none());
+    }
+}
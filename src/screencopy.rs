@@ -0,0 +1,280 @@
+//! Output captures through `zwlr_screencopy_manager_v1`.
+
+use crate::platform::LayerShellState;
+use slint::{Color, Image, PlatformError, Rgba8Pixel, SharedPixelBuffer, Timer};
+use smithay_client_toolkit::shm::raw::RawPool;
+use smithay_client_toolkit::shm::{Shm, ShmHandler};
+use std::collections::HashMap;
+use std::time::Duration;
+use wayland_client::backend::ObjectId;
+use wayland_client::protocol::wl_buffer::{self, WlBuffer};
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::protocol::wl_shm::Format;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+pub(crate) type CaptureCallback = Box<dyn FnOnce(Result<Capture, PlatformError>)>;
+
+/// The contents of an output at the time of the capture, in the output's buffer orientation.
+#[derive(Clone)]
+pub struct Capture {
+    pub output: WlOutput,
+    pub pixels: SharedPixelBuffer<Rgba8Pixel>,
+}
+
+impl Capture {
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    pub fn to_image(&self) -> Image {
+        Image::from_rgba8(self.pixels.clone())
+    }
+
+    /// Returns the color of the pixel at `(x, y)`, in buffer pixels.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width() || y >= self.height() {
+            return None;
+        }
+        let pixel = self.pixels.as_slice()[(y * self.width() + x) as usize];
+        Some(Color::from_argb_u8(pixel.a, pixel.r, pixel.g, pixel.b))
+    }
+
+    /// Returns the `size` × `size` pixels around `(x, y)`, each scaled up `zoom` times, as
+    /// shown by magnifiers. Pixels outside of the capture are transparent.
+    pub fn magnify(&self, x: i32, y: i32, size: u32, zoom: u32) -> SharedPixelBuffer<Rgba8Pixel> {
+        let mut patch = SharedPixelBuffer::<Rgba8Pixel>::new(size * zoom, size * zoom);
+        let half = (size / 2) as i32;
+        let width = size * zoom;
+        for (index, pixel) in patch.make_mut_slice().iter_mut().enumerate() {
+            let (patch_x, patch_y) = (index as u32 % width / zoom, index as u32 / width / zoom);
+            let (source_x, source_y) = (x + patch_x as i32 - half, y + patch_y as i32 - half);
+            if source_x >= 0 && source_y >= 0 {
+                if let Some(color) = self.pixel(source_x as u32, source_y as u32) {
+                    *pixel = Rgba8Pixel::new(color.red(), color.green(), color.blue(), 255);
+                }
+            }
+        }
+        patch
+    }
+}
+
+/// A capture in progress, tracked until the compositor reports `ready` or `failed`.
+pub(crate) struct PendingCapture {
+    output: WlOutput,
+    callback: CaptureCallback,
+    buffer_info: Option<(Format, u32, u32, u32)>,
+    pool: Option<RawPool>,
+    buffer: Option<WlBuffer>,
+    y_invert: bool,
+}
+
+impl PendingCapture {
+    pub(crate) fn new(output: WlOutput, callback: CaptureCallback) -> Self {
+        Self {
+            output,
+            callback,
+            buffer_info: None,
+            pool: None,
+            buffer: None,
+            y_invert: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Captures {
+    pending: HashMap<ObjectId, PendingCapture>,
+}
+
+impl Captures {
+    pub(crate) fn insert(&mut self, frame: &ZwlrScreencopyFrameV1, capture: PendingCapture) {
+        self.pending.insert(frame.id(), capture);
+    }
+}
+
+impl ShmHandler for LayerShellState {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl Dispatch<ZwlrScreencopyManagerV1, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrScreencopyManagerV1,
+        _event: <ZwlrScreencopyManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlBuffer, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _buffer: &WlBuffer,
+        _event: wl_buffer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        frame: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        use zwlr_screencopy_frame_v1::Event;
+
+        let id = frame.id();
+        let Some(capture) = state.captures.pending.get_mut(&id) else {
+            return;
+        };
+        match event {
+            Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } if capture.buffer_info.is_none() && supported_format(format) => {
+                capture.buffer_info = Some((format, width, height, stride));
+                // Before version 3 there is no `buffer_done`, the first buffer type is used.
+                if frame.version() < 3 {
+                    if let Err(err) = start_copy(&state.shm, frame, capture, qh) {
+                        fail(state.captures.pending.remove(&id).unwrap(), frame, err);
+                    }
+                }
+            }
+            Event::BufferDone => {
+                if let Err(err) = start_copy(&state.shm, frame, capture, qh) {
+                    fail(state.captures.pending.remove(&id).unwrap(), frame, err);
+                }
+            }
+            Event::Flags { flags } => {
+                capture.y_invert = matches!(
+                    flags,
+                    WEnum::Value(flags) if flags.contains(zwlr_screencopy_frame_v1::Flags::YInvert)
+                );
+            }
+            Event::Ready { .. } => {
+                let capture = state.captures.pending.remove(&id).unwrap();
+                frame.destroy();
+                finish(capture);
+            }
+            Event::Failed => {
+                let capture = state.captures.pending.remove(&id).unwrap();
+                fail(
+                    capture,
+                    frame,
+                    "the compositor failed to capture the output".into(),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn supported_format(format: Format) -> bool {
+    matches!(
+        format,
+        Format::Argb8888 | Format::Xrgb8888 | Format::Abgr8888 | Format::Xbgr8888
+    )
+}
+
+fn start_copy(
+    shm: &Shm,
+    frame: &ZwlrScreencopyFrameV1,
+    capture: &mut PendingCapture,
+    qh: &QueueHandle<LayerShellState>,
+) -> Result<(), String> {
+    if capture.buffer.is_some() {
+        return Ok(());
+    }
+    let Some((format, width, height, stride)) = capture.buffer_info else {
+        return Err("no supported screencopy buffer format".into());
+    };
+    let mut pool = RawPool::new((stride * height) as usize, shm)
+        .map_err(|e| format!("failed to allocate a screencopy buffer: {e}"))?;
+    let buffer = pool.create_buffer(
+        0,
+        width as i32,
+        height as i32,
+        stride as i32,
+        format,
+        (),
+        qh,
+    );
+    frame.copy(&buffer);
+    capture.pool = Some(pool);
+    capture.buffer = Some(buffer);
+    Ok(())
+}
+
+fn fail(capture: PendingCapture, frame: &ZwlrScreencopyFrameV1, message: String) {
+    frame.destroy();
+    let callback = capture.callback;
+    Timer::single_shot(Duration::ZERO, move || {
+        callback(Err(PlatformError::Other(message)))
+    });
+}
+
+fn finish(mut capture: PendingCapture) {
+    let (Some((format, width, height, stride)), Some(pool)) =
+        (capture.buffer_info, capture.pool.as_mut())
+    else {
+        let callback = capture.callback;
+        Timer::single_shot(Duration::ZERO, move || {
+            callback(Err(PlatformError::Other(
+                "no supported screencopy buffer format".into(),
+            )))
+        });
+        return;
+    };
+
+    let data = pool.mmap();
+    let mut pixels = SharedPixelBuffer::<Rgba8Pixel>::new(width, height);
+    let target = pixels.make_mut_slice();
+    for y in 0..height {
+        let source_y = if capture.y_invert { height - 1 - y } else { y };
+        let row = &data[(source_y * stride) as usize..];
+        for x in 0..width {
+            // The shm formats are little endian, so `Argb8888` is stored as B, G, R, A.
+            let [b0, b1, b2, b3] = row[x as usize * 4..x as usize * 4 + 4] else {
+                unreachable!()
+            };
+            let (r, g, b, a) = match format {
+                Format::Argb8888 => (b2, b1, b0, b3),
+                Format::Xrgb8888 => (b2, b1, b0, 255),
+                Format::Abgr8888 => (b0, b1, b2, b3),
+                _ => (b0, b1, b2, 255),
+            };
+            target[(y * width + x) as usize] = Rgba8Pixel::new(r, g, b, a);
+        }
+    }
+    if let Some(buffer) = capture.buffer.take() {
+        buffer.destroy();
+    }
+
+    let result = Capture {
+        output: capture.output,
+        pixels,
+    };
+    let callback = capture.callback;
+    // Report from Slint's timers, outside of the Wayland dispatch.
+    Timer::single_shot(Duration::ZERO, move || callback(Ok(result)));
+}
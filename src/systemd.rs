@@ -0,0 +1,63 @@
+use std::cell::Cell;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Minimal implementation of the `sd_notify` protocol, without linking against libsystemd.
+pub(crate) struct SystemdNotifier {
+    socket: UnixDatagram,
+    address: SocketAddr,
+    ready_sent: Cell<bool>,
+}
+
+impl SystemdNotifier {
+    /// Returns `None` when the process was not started by systemd with `NotifyAccess` set.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os("NOTIFY_SOCKET")?;
+        let path = path.to_str()?;
+        let address = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name.as_bytes()).ok()?,
+            None => SocketAddr::from_pathname(path).ok()?,
+        };
+        let socket = UnixDatagram::unbound().ok()?;
+
+        Some(Self {
+            socket,
+            address,
+            ready_sent: Cell::new(false),
+        })
+    }
+
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        self.socket
+            .send_to_addr(state.as_bytes(), &self.address)
+            .map(|_| ())
+    }
+
+    /// Sends `READY=1` the first time it is called.
+    pub fn notify_ready(&self) {
+        if self.ready_sent.replace(true) {
+            return;
+        }
+        if let Err(err) = self.notify("READY=1") {
+            log::warn!("failed to notify systemd readiness: {err}");
+        }
+    }
+
+    /// Returns the interval at which `WATCHDOG=1` must be sent, if the watchdog is enabled
+    /// for this process.
+    pub fn watchdog_interval() -> Option<Duration> {
+        if let Some(pid) = std::env::var_os("WATCHDOG_PID") {
+            if pid.to_str()?.parse::<u32>().ok()? != std::process::id() {
+                return None;
+            }
+        }
+        let usec = std::env::var_os("WATCHDOG_USEC")?
+            .to_str()?
+            .parse::<u64>()
+            .ok()?;
+        // Ping twice per period, as recommended by sd_watchdog_enabled(3).
+        Some(Duration::from_micros(usec) / 2)
+    }
+}
@@ -0,0 +1,373 @@
+//! Helpers to run layer shell applications against a private headless compositor, and to
+//! compare rendered windows against reference images.
+//!
+//! The harness starts a wlroots compositor with its headless backend (sway by default) in a
+//! temporary `XDG_RUNTIME_DIR`, so tests never touch the user's session.
+
+use crate::builder::SlintLayerShellBuilder;
+use crate::event::BackendEvent;
+use crate::platform::SlintLayerShell;
+use slint::platform::WindowEvent;
+use slint::{PhysicalSize, PlatformError, Rgba8Pixel, SharedPixelBuffer, Timer, TimerMode};
+use std::cell::Cell;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_pointer::ButtonState;
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle, delegate_noop};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1,
+    zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
+};
+
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct HeadlessCompositor {
+    child: Child,
+    runtime_dir: PathBuf,
+    socket: PathBuf,
+}
+
+impl HeadlessCompositor {
+    /// Spawns `sway` with the headless wlroots backend and an empty configuration.
+    pub fn spawn() -> io::Result<Self> {
+        let mut command = Command::new("sway");
+        command.args(["--config", "/dev/null"]);
+        Self::spawn_with(command)
+    }
+
+    /// Spawns a custom compositor command, which must implement a layer shell and
+    /// create its socket in `XDG_RUNTIME_DIR`.
+    pub fn spawn_with(mut command: Command) -> io::Result<Self> {
+        let runtime_dir = std::env::temp_dir().join(format!(
+            "slint-layer-shell-test-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&runtime_dir)?;
+        std::fs::set_permissions(&runtime_dir, std::fs::Permissions::from_mode(0o700))?;
+
+        let child = command
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env("WLR_RENDERER", "pixman")
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut compositor = Self {
+            child,
+            socket: PathBuf::new(),
+            runtime_dir,
+        };
+        compositor.socket = compositor.wait_for_socket()?;
+        Ok(compositor)
+    }
+
+    fn wait_for_socket(&mut self) -> io::Result<PathBuf> {
+        let deadline = Instant::now() + SOCKET_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(io::Error::other(format!(
+                    "headless compositor exited early with {status}"
+                )));
+            }
+            for entry in std::fs::read_dir(&self.runtime_dir)? {
+                let path = entry?.path();
+                let is_socket = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("wayland-") && !name.ends_with(".lock"));
+                if is_socket {
+                    return Ok(path);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "headless compositor did not create a Wayland socket",
+        ))
+    }
+
+    /// Absolute path of the compositor's Wayland socket.
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Runs a sway command through `swaymsg`, e.g. `create_output` or `output HEADLESS-1
+    /// scale 2` to test multi-monitor setups. Only works with the default compositor.
+    pub fn swaymsg(&self, command: &str) -> io::Result<()> {
+        let ipc_socket = std::fs::read_dir(&self.runtime_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .find(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("sway-ipc."))
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "sway has no IPC socket"))?;
+        let status = Command::new("swaymsg")
+            .arg("--socket")
+            .arg(ipc_socket)
+            .arg(command)
+            .stdout(Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "swaymsg {command:?} failed with {status}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns a builder already pointed at this compositor.
+    pub fn platform_builder(&self) -> SlintLayerShellBuilder {
+        SlintLayerShell::builder().display(&self.socket)
+    }
+
+    /// Adds a pointer to the compositor's seat, which has no input devices otherwise.
+    pub fn virtual_pointer(&self) -> io::Result<VirtualPointer> {
+        let connection = Connection::from_socket(UnixStream::connect(&self.socket)?)
+            .map_err(io::Error::other)?;
+        let (globals, queue) =
+            registry_queue_init::<VirtualPointerState>(&connection).map_err(io::Error::other)?;
+        let manager: ZwlrVirtualPointerManagerV1 = globals
+            .bind(&queue.handle(), 1..=1, ())
+            .map_err(io::Error::other)?;
+        let pointer = manager.create_virtual_pointer(None, &queue.handle(), ());
+        connection.flush().map_err(io::Error::other)?;
+        Ok(VirtualPointer {
+            connection,
+            _queue: queue,
+            pointer,
+            time: Instant::now(),
+        })
+    }
+}
+
+/// Pointer input injected through `zwlr_virtual_pointer_manager_v1` from a connection of its
+/// own, see [`HeadlessCompositor::virtual_pointer`]. The application under test receives it
+/// like input from a real mouse.
+pub struct VirtualPointer {
+    connection: Connection,
+    _queue: EventQueue<VirtualPointerState>,
+    pointer: ZwlrVirtualPointerV1,
+    time: Instant,
+}
+
+impl VirtualPointer {
+    /// Moves the pointer to `position` in the compositor's global space of size `extent`,
+    /// e.g. the logical size of a single output.
+    pub fn move_to(&self, position: (u32, u32), extent: (u32, u32)) -> io::Result<()> {
+        self.pointer
+            .motion_absolute(self.time(), position.0, position.1, extent.0, extent.1);
+        self.pointer.frame();
+        self.connection.flush().map_err(io::Error::other)
+    }
+
+    /// Presses and releases `button`, a Linux input event code such as `BTN_LEFT`.
+    pub fn click(&self, button: u32) -> io::Result<()> {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.pointer.button(self.time(), button, state);
+            self.pointer.frame();
+        }
+        self.connection.flush().map_err(io::Error::other)
+    }
+
+    fn time(&self) -> u32 {
+        self.time.elapsed().as_millis() as u32
+    }
+}
+
+impl Drop for VirtualPointer {
+    fn drop(&mut self) {
+        self.pointer.destroy();
+        let _ = self.connection.flush();
+    }
+}
+
+struct VirtualPointerState;
+
+impl Dispatch<WlRegistry, GlobalListContents> for VirtualPointerState {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(VirtualPointerState: ignore ZwlrVirtualPointerManagerV1);
+delegate_noop!(VirtualPointerState: ignore ZwlrVirtualPointerV1);
+
+impl Drop for HeadlessCompositor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.runtime_dir);
+    }
+}
+
+/// Runs the event loop of `platform` until `done` returns `true` for a backend event, e.g.
+/// until a window is configured.
+///
+/// Returns `false` if that did not happen within `timeout`.
+pub fn run_until(
+    platform: &SlintLayerShell,
+    timeout: Duration,
+    mut done: impl FnMut(&BackendEvent) -> bool,
+) -> Result<bool, PlatformError> {
+    let timed_out = Rc::new(Cell::new(false));
+    let timer = Timer::default();
+    timer.start(TimerMode::SingleShot, timeout, {
+        let platform = platform.clone();
+        let timed_out = timed_out.clone();
+        move || {
+            timed_out.set(true);
+            platform.quit_with_code(0);
+        }
+    });
+    platform.run_event_loop_with(|event| {
+        if done(&event) {
+            platform.quit_with_code(0);
+        }
+    })?;
+    Ok(!timed_out.get())
+}
+
+/// Parameters for [`assert_snapshot`].
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotOptions {
+    pub size: PhysicalSize,
+    pub scale_factor: f32,
+    /// Maximum per-channel difference for two pixels to be considered equal.
+    pub tolerance: u8,
+    /// Number of differing pixels accepted before the comparison fails.
+    pub max_mismatched_pixels: usize,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            size: PhysicalSize::new(400, 40),
+            scale_factor: 1.0,
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+}
+
+/// Renders `window` at the configured size and scale, and compares the result against the
+/// PNG at `reference`.
+///
+/// Fails when the window did not take the configured size, as the compositor sizes layer
+/// surfaces, and when the reference does not exist. The reference is written instead when
+/// `SLINT_LAYER_SHELL_UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(
+    window: &slint::Window,
+    reference: impl AsRef<Path>,
+    options: SnapshotOptions,
+) -> io::Result<()> {
+    let reference = reference.as_ref();
+
+    window.dispatch_event(WindowEvent::ScaleFactorChanged {
+        scale_factor: options.scale_factor,
+    });
+    window.set_size(options.size);
+    let size = window.size();
+    if size != options.size {
+        return Err(io::Error::other(format!(
+            "the window is {}x{} instead of {}x{}, configure its layer surface at that size",
+            size.width, size.height, options.size.width, options.size.height
+        )));
+    }
+    let snapshot = window.take_snapshot().map_err(io::Error::other)?;
+
+    if std::env::var_os("SLINT_LAYER_SHELL_UPDATE_SNAPSHOTS").is_some() {
+        return write_png(reference, &snapshot);
+    }
+    if !reference.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "no reference {}, set SLINT_LAYER_SHELL_UPDATE_SNAPSHOTS to write it",
+                reference.display()
+            ),
+        ));
+    }
+
+    let (width, height, expected) = read_png(reference)?;
+    if (width, height) != (snapshot.width(), snapshot.height()) {
+        return Err(io::Error::other(format!(
+            "snapshot is {}x{}, reference {} is {width}x{height}",
+            snapshot.width(),
+            snapshot.height(),
+            reference.display()
+        )));
+    }
+
+    let mismatched = snapshot
+        .as_bytes()
+        .chunks_exact(4)
+        .zip(expected.chunks_exact(4))
+        .filter(|(actual, expected)| {
+            actual
+                .iter()
+                .zip(expected.iter())
+                .any(|(a, e)| a.abs_diff(*e) > options.tolerance)
+        })
+        .count();
+    if mismatched > options.max_mismatched_pixels {
+        let actual = reference.with_extension("actual.png");
+        write_png(&actual, &snapshot)?;
+        return Err(io::Error::other(format!(
+            "{mismatched} pixels differ from {}, see {}",
+            reference.display(),
+            actual.display()
+        )));
+    }
+    Ok(())
+}
+
+fn write_png(path: &Path, image: &SharedPixelBuffer<Rgba8Pixel>) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer
+        .write_image_data(image.as_bytes())
+        .map_err(io::Error::other)
+}
+
+fn read_png(path: &Path) -> io::Result<(u32, u32, Vec<u8>)> {
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(io::Error::other)?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::other(format!(
+            "{} is not an 8-bit RGBA PNG",
+            path.display()
+        )));
+    }
+    buffer.truncate(info.buffer_size());
+    Ok((info.width, info.height, buffer))
+}
@@ -0,0 +1,165 @@
+//! Coordinates `zwp_text_input_v3` with the focused Slint `TextInput`, so that the system's
+//! input method and on-screen keyboard appear while a text field has focus, and text they
+//! commit is typed into it.
+//!
+//! Pre-edit text and surrounding text are not supported.
+
+use crate::platform::LayerShellState;
+use i_slint_core::SharedString;
+use i_slint_core::platform::WindowEvent;
+use std::collections::HashMap;
+use wayland_client::backend::ObjectId;
+use wayland_client::protocol::{wl_seat::WlSeat, wl_surface::WlSurface};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::wp::text_input::zv3::client::{
+    zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+    zwp_text_input_v3::{self, ContentHint, ContentPurpose, ZwpTextInputV3},
+};
+
+/// What the focused Slint text field asks of the input method.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum TextInputRequest {
+    Enable {
+        purpose: ContentPurpose,
+        /// Cursor rectangle in surface coordinates, so popups can avoid covering it.
+        cursor: (i32, i32, i32, i32),
+    },
+    Update {
+        cursor: (i32, i32, i32, i32),
+    },
+    Disable,
+}
+
+/// Purpose and cursor rectangle of a text field that asked for the input method.
+type Enabled = (ContentPurpose, (i32, i32, i32, i32));
+
+#[derive(Default)]
+pub(crate) struct TextInputState {
+    text_input: Option<ZwpTextInputV3>,
+    /// Surface the text input entered, which follows keyboard focus.
+    entered_surface: Option<WlSurface>,
+    /// The text field of each surface that asked for the input method. Slint focuses text
+    /// fields before the text input enters their surface, so entering applies them.
+    requested: HashMap<ObjectId, Enabled>,
+    pending_commit: Option<String>,
+}
+
+impl LayerShellState {
+    /// Creates the text input of `seat`, unless the compositor lacks the protocol or one
+    /// exists already.
+    pub(crate) fn create_text_input(&mut self, seat: &WlSeat, qh: &QueueHandle<Self>) {
+        if self.text_input.text_input.is_some() {
+            return;
+        }
+        if let Some(manager) = &self.text_input_manager {
+            self.text_input.text_input = Some(manager.get_text_input(seat, qh, ()));
+        }
+    }
+
+    /// Applies a request of the text field focused in the window of `surface`. Requests of
+    /// surfaces the text input has not entered are kept until it does, as the protocol only
+    /// allows enabling it on the focused surface.
+    pub(crate) fn request_text_input(&mut self, surface: &WlSurface, request: TextInputRequest) {
+        let state = &mut self.text_input;
+        let id = surface.id();
+        match request {
+            TextInputRequest::Enable { purpose, cursor } => {
+                state.requested.insert(id, (purpose, cursor));
+            }
+            TextInputRequest::Update { cursor } => match state.requested.get_mut(&id) {
+                Some(enabled) => enabled.1 = cursor,
+                None => return,
+            },
+            TextInputRequest::Disable => {
+                if state.requested.remove(&id).is_none() {
+                    return;
+                }
+            }
+        }
+        if state.entered_surface.as_ref() != Some(surface) {
+            return;
+        }
+        let Some(text_input) = &state.text_input else {
+            return;
+        };
+        match request {
+            TextInputRequest::Enable { purpose, cursor } => enable(text_input, purpose, cursor),
+            TextInputRequest::Update { cursor } => {
+                text_input.set_cursor_rectangle(cursor.0, cursor.1, cursor.2, cursor.3);
+            }
+            TextInputRequest::Disable => text_input.disable(),
+        }
+        text_input.commit();
+    }
+}
+
+fn enable(text_input: &ZwpTextInputV3, purpose: ContentPurpose, cursor: (i32, i32, i32, i32)) {
+    text_input.enable();
+    text_input.set_content_type(ContentHint::None, purpose);
+    text_input.set_cursor_rectangle(cursor.0, cursor.1, cursor.2, cursor.3);
+}
+
+impl Dispatch<ZwpTextInputManagerV3, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwpTextInputManagerV3,
+        _event: <ZwpTextInputManagerV3 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTextInputV3, ()> for LayerShellState {
+    fn event(
+        state: &mut Self,
+        _text_input: &ZwpTextInputV3,
+        event: zwp_text_input_v3::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_text_input_v3::Event::Enter { surface } => {
+                let window_adapters = &state.window_adapters;
+                let text_input = &mut state.text_input;
+                text_input
+                    .requested
+                    .retain(|id, _| window_adapters.contains_key(id));
+                if let (Some(proxy), Some(&(purpose, cursor))) = (
+                    &text_input.text_input,
+                    text_input.requested.get(&surface.id()),
+                ) {
+                    enable(proxy, purpose, cursor);
+                    proxy.commit();
+                }
+                text_input.entered_surface = Some(surface);
+            }
+            zwp_text_input_v3::Event::Leave { .. } => {
+                state.text_input.entered_surface = None;
+            }
+            zwp_text_input_v3::Event::CommitString { text } => {
+                state.text_input.pending_commit = text;
+            }
+            zwp_text_input_v3::Event::Done { .. } => {
+                let Some(text) = state.text_input.pending_commit.take() else {
+                    return;
+                };
+                let window_adapter = state
+                    .text_input
+                    .entered_surface
+                    .as_ref()
+                    .and_then(|surface| state.window_adapters.get(&surface.id()))
+                    .and_then(|window_adapter| window_adapter.upgrade());
+                if let Some(window_adapter) = window_adapter {
+                    let text = SharedString::from(text);
+                    window_adapter.forward_event(WindowEvent::KeyPressed { text: text.clone() });
+                    window_adapter.forward_event(WindowEvent::KeyReleased { text });
+                    window_adapter.pending_redraw.set(true);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! A `StatusNotifierItem` host, exposing the tray icons of other applications.
+//!
+//! D-Bus is handled on background threads, and changes are applied to the model on the event
+//! loop thread.
+
+use crate::dbus::unwrap_variant;
+use crate::platform::SlintLayerShell;
+use slint::{Image, Model, ModelRc, PlatformError, Rgba8Pixel, SharedPixelBuffer, VecModel};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::{Connection, MessageIterator, Proxy};
+use zbus::zvariant::{OwnedValue, Value};
+
+const WATCHER_SERVICE: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+
+/// An ARGB32 icon image, in network byte order, as sent by tray items.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IconPixmap {
+    pub width: i32,
+    pub height: i32,
+    pub data: Vec<u8>,
+}
+
+impl IconPixmap {
+    pub fn to_image(&self) -> Option<Image> {
+        let (width, height) = (
+            u32::try_from(self.width).ok()?,
+            u32::try_from(self.height).ok()?,
+        );
+        if self.data.len() < width as usize * height as usize * 4 {
+            return None;
+        }
+        let mut buffer = SharedPixelBuffer::<Rgba8Pixel>::new(width, height);
+        for (pixel, argb) in buffer
+            .make_mut_slice()
+            .iter_mut()
+            .zip(self.data.chunks_exact(4))
+        {
+            *pixel = Rgba8Pixel::new(argb[1], argb[2], argb[3], argb[0]);
+        }
+        Some(Image::from_rgba8(buffer))
+    }
+}
+
+/// A tray icon registered by another application.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrayItem {
+    /// The name the item registered with, used to address it in [`Tray`] calls.
+    pub id: String,
+    pub category: String,
+    pub title: String,
+    /// `Passive`, `Active` or `NeedsAttention`.
+    pub status: String,
+    /// Freedesktop icon name, preferred over [`Self::icon_pixmaps`] when set.
+    pub icon_name: String,
+    pub icon_pixmaps: Vec<IconPixmap>,
+    pub attention_icon_name: String,
+    pub tooltip_title: String,
+    pub tooltip_description: String,
+    /// When set, activating the item should open its menu instead.
+    pub item_is_menu: bool,
+    /// Object path of the `com.canonical.dbusmenu` menu of the item.
+    pub menu: Option<String>,
+}
+
+impl TrayItem {
+    /// Returns the largest pixmap of the icon as an image.
+    pub fn icon(&self) -> Option<Image> {
+        self.icon_pixmaps
+            .iter()
+            .max_by_key(|pixmap| pixmap.width * pixmap.height)
+            .and_then(IconPixmap::to_image)
+    }
+}
+
+/// An entry of a tray item menu, as returned by [`Tray::menu`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MenuItem {
+    pub id: i32,
+    pub label: String,
+    pub enabled: bool,
+    pub visible: bool,
+    pub separator: bool,
+    /// The state of check boxes and radio buttons, `None` for other items.
+    pub toggled: Option<bool>,
+    pub children: Vec<MenuItem>,
+}
+
+enum TrayUpdate {
+    Changed(TrayItem),
+    Removed(String),
+}
+
+#[derive(Clone, Debug)]
+struct ItemAddress {
+    service: String,
+    path: String,
+    owner: String,
+}
+
+impl ItemAddress {
+    fn parse(connection: &Connection, id: &str) -> Self {
+        let (service, path) = match id.find('/') {
+            Some(index) => (id[..index].to_string(), id[index..].to_string()),
+            None => (id.to_string(), "/StatusNotifierItem".to_string()),
+        };
+        let owner = if service.starts_with(':') {
+            service.clone()
+        } else {
+            Proxy::new(
+                connection,
+                "org.freedesktop.DBus",
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+            )
+            .and_then(|proxy| proxy.call::<_, _, String>("GetNameOwner", &(service.as_str(),)))
+            .unwrap_or_else(|_| service.clone())
+        };
+        Self {
+            service,
+            path,
+            owner,
+        }
+    }
+}
+
+/// Hosts the tray icons of other applications through the `StatusNotifierItem` protocol.
+///
+/// ```ignore
+/// let tray = Tray::new(&platform)?;
+/// ui.set_tray_items(tray.model(|item| TrayItemData {
+///     id: item.id.as_str().into(),
+///     icon: item.icon().unwrap_or_default(),
+///     tooltip: item.tooltip_title.as_str().into(),
+/// }));
+/// ```
+pub struct Tray {
+    connection: Connection,
+    addresses: Arc<Mutex<HashMap<String, ItemAddress>>>,
+    items: Rc<VecModel<TrayItem>>,
+}
+
+impl Tray {
+    /// Registers as a `StatusNotifierHost` on the session bus and starts tracking items.
+    ///
+    /// Requires a running `StatusNotifierWatcher`, which most desktops and bars provide.
+    pub fn new(platform: &SlintLayerShell) -> Result<Rc<Self>, PlatformError> {
+        let connection = Connection::session()
+            .map_err(|e| PlatformError::Other(format!("failed to connect to D-Bus: {e}")))?;
+        let host_name = format!("org.kde.StatusNotifierHost-{}-1", std::process::id());
+        connection
+            .request_name(host_name.as_str())
+            .map_err(|e| PlatformError::Other(format!("failed to request {host_name}: {e}")))?;
+        let watcher = Proxy::new(&connection, WATCHER_SERVICE, WATCHER_PATH, WATCHER_SERVICE)
+            .map_err(|e| PlatformError::Other(format!("failed to reach the tray watcher: {e}")))?;
+        watcher
+            .call::<_, _, ()>("RegisterStatusNotifierHost", &(host_name.as_str(),))
+            .map_err(|e| PlatformError::Other(format!("failed to register tray host: {e}")))?;
+
+        let this = Rc::new(Self {
+            connection: connection.clone(),
+            addresses: Arc::default(),
+            items: Rc::new(VecModel::default()),
+        });
+
+        let (sender, channel) = calloop::channel::channel();
+        platform.channel_to_event_loop(channel, &this, "tray updates", |this, update| {
+            this.apply(update);
+        })?;
+
+        let registered = watcher
+            .get_property::<Vec<String>>("RegisteredStatusNotifierItems")
+            .unwrap_or_default();
+        let addresses = this.addresses.clone();
+        let watcher_sender = sender.clone();
+        let watcher_connection = connection.clone();
+        std::thread::spawn(move || {
+            for id in registered {
+                add_item(&watcher_connection, &addresses, &watcher_sender, id);
+            }
+            watch_watcher(&watcher_connection, &addresses, &watcher_sender);
+        });
+
+        let addresses = this.addresses.clone();
+        std::thread::spawn(move || watch_items(&connection, &addresses, &sender));
+
+        Ok(this)
+    }
+
+    /// Returns the items currently shown in the tray.
+    pub fn items(&self) -> Vec<TrayItem> {
+        self.items.iter().collect()
+    }
+
+    /// Returns a model of the tray items, converted with `map` and kept up to date.
+    pub fn model<T: Clone + 'static>(&self, map: impl Fn(&TrayItem) -> T + 'static) -> ModelRc<T> {
+        ModelRc::new(self.items.clone().map(move |item| map(&item)))
+    }
+
+    /// Activates the item, usually as a result of a left click at `(x, y)` on the screen.
+    pub fn activate(&self, id: &str, x: i32, y: i32) -> Result<(), PlatformError> {
+        self.call_item(id, "Activate", |proxy| proxy.call("Activate", &(x, y)))
+    }
+
+    /// Secondary activation, usually a middle click.
+    pub fn secondary_activate(&self, id: &str, x: i32, y: i32) -> Result<(), PlatformError> {
+        self.call_item(id, "SecondaryActivate", |proxy| {
+            proxy.call("SecondaryActivate", &(x, y))
+        })
+    }
+
+    /// Asks the item to show its own context menu, for items without a [`TrayItem::menu`].
+    pub fn context_menu(&self, id: &str, x: i32, y: i32) -> Result<(), PlatformError> {
+        self.call_item(id, "ContextMenu", |proxy| {
+            proxy.call("ContextMenu", &(x, y))
+        })
+    }
+
+    /// Scrolls over the item, `orientation` is `"vertical"` or `"horizontal"`.
+    pub fn scroll(&self, id: &str, delta: i32, orientation: &str) -> Result<(), PlatformError> {
+        self.call_item(id, "Scroll", |proxy| {
+            proxy.call("Scroll", &(delta, orientation))
+        })
+    }
+
+    /// Fetches the menu of the item. This blocks until the item replies.
+    pub fn menu(&self, id: &str) -> Result<Option<MenuItem>, PlatformError> {
+        let Some(proxy) = self.menu_proxy(id)? else {
+            return Ok(None);
+        };
+        let _ = proxy.call::<_, _, bool>("AboutToShow", &(0i32,));
+        let (_revision, (id, properties, children)) = proxy
+            .call::<_, _, (u32, (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>))>(
+                "GetLayout",
+                &(0i32, -1i32, Vec::<String>::new()),
+            )
+            .map_err(|e| PlatformError::Other(format!("failed to fetch tray menu: {e}")))?;
+        let properties = properties
+            .iter()
+            .map(|(name, value)| (name.as_str(), &**value))
+            .collect::<Vec<_>>();
+        Ok(Some(menu_item(
+            id,
+            &properties,
+            children.iter().map(|child| &**child),
+        )))
+    }
+
+    /// Triggers the menu entry `item_id` of the menu returned by [`Self::menu`].
+    pub fn activate_menu_item(&self, id: &str, item_id: i32) -> Result<(), PlatformError> {
+        let Some(proxy) = self.menu_proxy(id)? else {
+            return Ok(());
+        };
+        proxy
+            .call::<_, _, ()>("Event", &(item_id, "clicked", Value::I32(0), 0u32))
+            .map_err(|e| PlatformError::Other(format!("failed to activate tray menu item: {e}")))
+    }
+
+    fn address(&self, id: &str) -> Result<ItemAddress, PlatformError> {
+        self.addresses
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| PlatformError::Other(format!("unknown tray item {id}")))
+    }
+
+    fn call_item(
+        &self,
+        id: &str,
+        method: &str,
+        call: impl FnOnce(&Proxy) -> zbus::Result<()>,
+    ) -> Result<(), PlatformError> {
+        let address = self.address(id)?;
+        Proxy::new(
+            &self.connection,
+            address.service,
+            address.path,
+            ITEM_INTERFACE,
+        )
+        .and_then(|proxy| call(&proxy))
+        .map_err(|e| PlatformError::Other(format!("{method} failed on tray item {id}: {e}")))
+    }
+
+    fn menu_proxy(&self, id: &str) -> Result<Option<Proxy<'static>>, PlatformError> {
+        let address = self.address(id)?;
+        let Some(menu) = self
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .and_then(|item| item.menu)
+        else {
+            return Ok(None);
+        };
+        Proxy::new(&self.connection, address.service, menu, MENU_INTERFACE)
+            .map(Some)
+            .map_err(|e| PlatformError::Other(format!("failed to reach tray menu: {e}")))
+    }
+
+    fn apply(&self, update: TrayUpdate) {
+        match update {
+            TrayUpdate::Changed(item) => {
+                match self
+                    .items
+                    .iter()
+                    .position(|existing| existing.id == item.id)
+                {
+                    Some(row) => self.items.set_row_data(row, item),
+                    None => self.items.push(item),
+                }
+            }
+            TrayUpdate::Removed(id) => {
+                if let Some(row) = self.items.iter().position(|item| item.id == id) {
+                    self.items.remove(row);
+                }
+            }
+        }
+    }
+}
+
+type Addresses = Mutex<HashMap<String, ItemAddress>>;
+
+fn add_item(
+    connection: &Connection,
+    addresses: &Addresses,
+    sender: &calloop::channel::Sender<TrayUpdate>,
+    id: String,
+) {
+    let address = ItemAddress::parse(connection, &id);
+    addresses
+        .lock()
+        .unwrap()
+        .insert(id.clone(), address.clone());
+    match fetch_item(connection, &id, &address) {
+        Ok(item) => {
+            let _ = sender.send(TrayUpdate::Changed(item));
+        }
+        Err(err) => log::warn!("failed to query tray item {id}: {err}"),
+    }
+}
+
+fn watch_watcher(
+    connection: &Connection,
+    addresses: &Addresses,
+    sender: &calloop::channel::Sender<TrayUpdate>,
+) {
+    let Ok(messages) = signals_of(connection, WATCHER_SERVICE) else {
+        return;
+    };
+    for message in messages.flatten() {
+        let header = message.header();
+        let Ok(id) = message.body().deserialize::<String>() else {
+            continue;
+        };
+        match header.member().map(|member| member.as_str()) {
+            Some("StatusNotifierItemRegistered") => add_item(connection, addresses, sender, id),
+            Some("StatusNotifierItemUnregistered") => {
+                addresses.lock().unwrap().remove(&id);
+                if sender.send(TrayUpdate::Removed(id)).is_err() {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Re-fetches items whenever they signal a change of their icon, title, tooltip or status.
+fn watch_items(
+    connection: &Connection,
+    addresses: &Addresses,
+    sender: &calloop::channel::Sender<TrayUpdate>,
+) {
+    let Ok(messages) = signals_of(connection, ITEM_INTERFACE) else {
+        return;
+    };
+    for message in messages.flatten() {
+        let header = message.header();
+        let (Some(owner), Some(path)) = (header.sender(), header.path()) else {
+            continue;
+        };
+        let changed = addresses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, address)| address.owner == owner.as_str() && address.path == path.as_str())
+            .map(|(id, address)| (id.clone(), address.clone()))
+            .collect::<Vec<_>>();
+        for (id, address) in changed {
+            if let Ok(item) = fetch_item(connection, &id, &address) {
+                if sender.send(TrayUpdate::Changed(item)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn signals_of(connection: &Connection, interface: &str) -> zbus::Result<MessageIterator> {
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(interface)?
+        .build();
+    MessageIterator::for_match_rule(rule, connection, None)
+}
+
+fn fetch_item(connection: &Connection, id: &str, address: &ItemAddress) -> zbus::Result<TrayItem> {
+    let properties = Proxy::new(
+        connection,
+        address.service.as_str(),
+        address.path.as_str(),
+        "org.freedesktop.DBus.Properties",
+    )?
+    .call::<_, _, HashMap<String, OwnedValue>>("GetAll", &(ITEM_INTERFACE,))?;
+
+    let string = |name: &str| {
+        properties
+            .get(name)
+            .and_then(|value| match unwrap_variant(value) {
+                Value::Str(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    };
+    let (tooltip_title, tooltip_description) =
+        match properties.get("ToolTip").map(|value| unwrap_variant(value)) {
+            Some(Value::Structure(tooltip)) => match tooltip.fields() {
+                [_, _, Value::Str(title), Value::Str(description)] => {
+                    (title.to_string(), description.to_string())
+                }
+                _ => Default::default(),
+            },
+            _ => Default::default(),
+        };
+
+    Ok(TrayItem {
+        id: id.to_string(),
+        category: string("Category"),
+        title: string("Title"),
+        status: string("Status"),
+        icon_name: string("IconName"),
+        icon_pixmaps: properties
+            .get("IconPixmap")
+            .map(|value| parse_pixmaps(unwrap_variant(value)))
+            .unwrap_or_default(),
+        attention_icon_name: string("AttentionIconName"),
+        tooltip_title,
+        tooltip_description,
+        item_is_menu: properties
+            .get("ItemIsMenu")
+            .is_some_and(|value| matches!(unwrap_variant(value), Value::Bool(true))),
+        menu: properties
+            .get("Menu")
+            .and_then(|value| match unwrap_variant(value) {
+                Value::ObjectPath(path) => Some(path.to_string()),
+                _ => None,
+            }),
+    })
+}
+
+fn parse_pixmaps(value: &Value) -> Vec<IconPixmap> {
+    let Value::Array(pixmaps) = value else {
+        return Vec::new();
+    };
+    pixmaps
+        .iter()
+        .filter_map(|pixmap| match pixmap {
+            Value::Structure(pixmap) => match pixmap.fields() {
+                [Value::I32(width), Value::I32(height), Value::Array(data)] => Some(IconPixmap {
+                    width: *width,
+                    height: *height,
+                    data: data
+                        .iter()
+                        .filter_map(|byte| match byte {
+                            Value::U8(byte) => Some(*byte),
+                            _ => None,
+                        })
+                        .collect(),
+                }),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_menu_item(value: &Value) -> Option<MenuItem> {
+    let Value::Structure(layout) = unwrap_variant(value) else {
+        return None;
+    };
+    let [
+        Value::I32(id),
+        Value::Dict(properties),
+        Value::Array(children),
+    ] = layout.fields()
+    else {
+        return None;
+    };
+    let properties = properties
+        .iter()
+        .filter_map(|(name, value)| match name {
+            Value::Str(name) => Some((name.as_str(), value)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    Some(menu_item(*id, &properties, children.iter()))
+}
+
+fn menu_item<'a>(
+    id: i32,
+    properties: &[(&str, &Value)],
+    children: impl Iterator<Item = &'a Value<'a>>,
+) -> MenuItem {
+    let property = |name: &str| {
+        properties
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| unwrap_variant(value))
+    };
+    let flag = |name: &str| match property(name) {
+        Some(Value::Bool(value)) => *value,
+        _ => true,
+    };
+
+    MenuItem {
+        id,
+        label: match property("label") {
+            // Mnemonics are marked with underscores, which are not shown.
+            Some(Value::Str(label)) => label
+                .replace("__", "\0")
+                .replace('_', "")
+                .replace('\0', "_"),
+            _ => String::new(),
+        },
+        enabled: flag("enabled"),
+        visible: flag("visible"),
+        separator: matches!(property("type"), Some(Value::Str(kind)) if kind.as_str() == "separator"),
+        toggled: match (property("toggle-type"), property("toggle-state")) {
+            (Some(Value::Str(kind)), Some(Value::I32(state))) if !kind.is_empty() => {
+                Some(*state == 1)
+            }
+            _ => None,
+        },
+        children: children.filter_map(parse_menu_item).collect(),
+    }
+}
@@ -0,0 +1,138 @@
+//! Typing into other applications through `zwp_virtual_keyboard_v1`.
+
+use crate::platform::LayerShellState;
+use slint::PlatformError;
+use std::cell::Cell;
+use std::io::Write;
+use std::time::Instant;
+use wayland_client::protocol::wl_keyboard::{KeyState, KeymapFormat};
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
+    zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
+    zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+};
+
+/// A US layout, resolved by the compositor from its own XKB data.
+pub const DEFAULT_KEYMAP: &str = r#"xkb_keymap {
+    xkb_keycodes { include "evdev+aliases(qwerty)" };
+    xkb_types { include "complete" };
+    xkb_compat { include "complete" };
+    xkb_symbols { include "pc+us+inet(evdev)" };
+};
+"#;
+
+/// A virtual keyboard sending key events to whichever client has keyboard focus.
+///
+/// Keys are identified by their evdev key code, e.g. `30` for `KEY_A`.
+pub struct VirtualKeyboard {
+    keyboard: ZwpVirtualKeyboardV1,
+    connection: Connection,
+    created: Instant,
+    modifiers: Cell<(u32, u32, u32, u32)>,
+}
+
+impl VirtualKeyboard {
+    pub(crate) fn new(
+        manager: &ZwpVirtualKeyboardManagerV1,
+        state: &LayerShellState,
+        connection: Connection,
+        qh: &QueueHandle<LayerShellState>,
+        keymap: &str,
+    ) -> Result<Self, PlatformError> {
+        let seat = state.seat_state.seats().next().ok_or_else(|| {
+            PlatformError::Other("no seat to create a virtual keyboard on".into())
+        })?;
+        let keyboard = manager.create_virtual_keyboard(&seat, qh, ());
+
+        // The keymap is passed as a file descriptor, including the terminating nul byte.
+        let path = std::env::temp_dir().join(format!(
+            "slint-layer-shell-keymap-{}-{:p}",
+            std::process::id(),
+            &keyboard
+        ));
+        let mut file = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| PlatformError::Other(format!("failed to create keymap file: {e}")))?;
+        let _ = std::fs::remove_file(&path);
+        file.write_all(keymap.as_bytes())
+            .and_then(|_| file.write_all(&[0]))
+            .map_err(|e| PlatformError::Other(format!("failed to write keymap: {e}")))?;
+        keyboard.keymap(
+            KeymapFormat::XkbV1 as u32,
+            std::os::fd::AsFd::as_fd(&file),
+            keymap.len() as u32 + 1,
+        );
+
+        Ok(Self {
+            keyboard,
+            connection,
+            created: Instant::now(),
+            modifiers: Cell::new((0, 0, 0, 0)),
+        })
+    }
+
+    pub fn press(&self, keycode: u32) {
+        self.key(keycode, KeyState::Pressed);
+    }
+
+    pub fn release(&self, keycode: u32) {
+        self.key(keycode, KeyState::Released);
+    }
+
+    /// Presses and immediately releases `keycode`.
+    pub fn tap(&self, keycode: u32) {
+        self.press(keycode);
+        self.release(keycode);
+    }
+
+    /// Sets the XKB modifier state, e.g. `set_modifiers(1, 0, 0, 0)` to hold Shift.
+    pub fn set_modifiers(&self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        self.modifiers.set((depressed, latched, locked, group));
+        self.keyboard.modifiers(depressed, latched, locked, group);
+        let _ = self.connection.flush();
+    }
+
+    /// Returns the modifier state last set with [`Self::set_modifiers`].
+    pub fn modifiers(&self) -> (u32, u32, u32, u32) {
+        self.modifiers.get()
+    }
+
+    fn key(&self, keycode: u32, state: KeyState) {
+        let time = self.created.elapsed().as_millis() as u32;
+        self.keyboard.key(time, keycode, state as u32);
+        let _ = self.connection.flush();
+    }
+}
+
+impl Drop for VirtualKeyboard {
+    fn drop(&mut self) {
+        self.keyboard.destroy();
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpVirtualKeyboardManagerV1,
+        _event: <ZwpVirtualKeyboardManagerV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for LayerShellState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpVirtualKeyboardV1,
+        _event: <ZwpVirtualKeyboardV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
@@ -1,23 +1,50 @@
+use crate::builder::Renderer;
+use crate::error::SurfaceError;
+use crate::event::{
+    BackendEvent, EVENTS_LOG_TARGET, InputTimestamp, PointerButton, RawSurfaceEvent,
+    VisibilityChange, debug_events,
+};
+use crate::layer::LayerConfig;
+use crate::layer_shell::{LayerShell, LayerSurface};
 use crate::platform::LayerShellState;
+use crate::quirks::Quirks;
+use crate::window_ext::WindowColorScheme;
+use i_slint_core::Property;
+use i_slint_core::items::ColorScheme;
+use i_slint_core::window::{InputMethodRequest, WindowAdapterInternal, WindowInner};
 use i_slint_renderer_skia::SkiaRenderer;
 use raw_window_handle::{
     DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
     RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle, WindowHandle,
 };
 use slint::{
-    PhysicalSize, Window as SlintWindow,
-    platform::{PlatformError, WindowAdapter},
+    LogicalSize, PhysicalPosition, PhysicalSize, Window as SlintWindow,
+    platform::{PlatformError, WindowAdapter, WindowEvent},
 };
+use smithay_client_toolkit::compositor::Region;
+use smithay_client_toolkit::registry::SimpleGlobal;
+#[cfg(feature = "session-lock")]
+use smithay_client_toolkit::session_lock::{SessionLock, SessionLockSurface};
 use smithay_client_toolkit::shell::{
-    WaylandSurface, wlr_layer::LayerSurface, xdg::window::Window as XdgWindow,
+    WaylandSurface,
+    wlr_layer::{KeyboardInteractivity, Layer},
+    xdg::window::Window as XdgWindow,
     xdg::window::WindowDecorations,
 };
+use std::any::Any;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fmt;
+use std::pin::Pin;
 use std::{cell::Cell, ptr::NonNull, rc::Rc, sync::Arc};
 use wayland_client::{
     Connection, Proxy, QueueHandle,
-    protocol::{wl_buffer::WlBuffer, wl_surface::WlSurface},
+    protocol::{
+        wl_buffer::WlBuffer,
+        wl_compositor::WlCompositor,
+        wl_output::{Transform, WlOutput},
+        wl_surface::WlSurface,
+    },
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -27,22 +54,99 @@ pub enum WindowState {
     Destroy,
 }
 
+/// The Wayland role given to the surface of a new window.
+#[derive(Clone, Debug)]
+pub enum SurfaceRole {
+    /// A regular xdg toplevel window.
+    Toplevel,
+    Layer(LayerConfig),
+    /// A lock screen surface covering `output` while the session is locked.
+    #[cfg(feature = "session-lock")]
+    Lock {
+        session_lock: SessionLock,
+        output: WlOutput,
+    },
+}
+
 pub struct LayerShellWindowAdapter {
     pub layer_shell_state: Rc<RefCell<LayerShellState>>,
+    queue_handle: QueueHandle<LayerShellState>,
+    /// Creates input regions without borrowing the state, which the Wayland dispatch holds
+    /// while Slint callbacks change the layer configuration.
+    wl_compositor: WlCompositor,
 
     pub render: SkiaRenderer,
 
     pub window: SlintWindow,
     pub surface: WlSurface,
     pub xdg_window: Option<XdgWindow>,
-    pub layer_surface: Option<LayerSurface>,
+    /// The layer surface role of [`Self::surface`], which is replaced on the same surface.
+    pub layer_surface: RefCell<Option<LayerSurface>>,
+    /// Taken when the window is hidden, as lock surfaces cannot be unmapped, only destroyed.
+    #[cfg(feature = "session-lock")]
+    pub session_lock_surface: RefCell<Option<SessionLockSurface>>,
     pub connection: Connection,
 
     pub window_state: Cell<WindowState>,
+    /// Whether Slint shows the window, regardless of whether it is configured yet.
+    pub visible: Cell<bool>,
     pub pending_redraw: Cell<bool>,
+    /// Whether Slint changed the content since the input region was last derived from it,
+    /// see [`LayerConfig::click_through_transparent`].
+    input_region_outdated: Cell<bool>,
+    /// When the input region was last derived, and for which window size.
+    input_region_updated: Cell<Option<(std::time::Instant, PhysicalSize)>>,
+    /// Derives the input region once the content stopped changing faster than
+    /// [`INPUT_REGION_INTERVAL`].
+    input_region_timer: slint::Timer,
     pub frame_callback_pending: Cell<bool>,
+    /// When the pending frame callback was requested, to recover from lost callbacks.
+    pub frame_requested_at: Cell<std::time::Instant>,
     pub size: Cell<PhysicalSize>,
     pub pending_size: Cell<Option<PhysicalSize>>,
+    /// Position in the compositor's global space, derived from the anchors, margins and the
+    /// geometry of the output, as Wayland does not tell clients where surfaces are.
+    pub position: Cell<Option<PhysicalPosition>>,
+    pub preferred_size: Cell<Option<LogicalSize>>,
+    pub layer_config: RefCell<Option<LayerConfig>>,
+    /// Outputs the surface currently overlaps, in the order they were entered.
+    pub entered_outputs: RefCell<Vec<WlOutput>>,
+    /// Scale requested by the compositor through `wl_surface.preferred_buffer_scale`, which
+    /// takes precedence over the scales of the entered outputs.
+    pub preferred_buffer_scale: Cell<Option<i32>>,
+    /// Transform of the output the surface is shown on, as reported by the compositor.
+    pub transform: Cell<Transform>,
+    /// Color scheme reported to Slint, which selects the light or dark style palette.
+    color_scheme: Pin<Box<Property<ColorScheme>>>,
+    /// Color scheme set for the whole platform, reported unless the window overrides it.
+    system_color_scheme: Cell<ColorScheme>,
+    color_scheme_override: Cell<WindowColorScheme>,
+    /// Application state attached to the window, see [`crate::window_ext::WindowExt`].
+    pub user_data: RefCell<Option<Rc<dyn Any>>>,
+    /// Latest key, button, scroll, motion or touch event received by the window.
+    pub last_input: Cell<Option<InputTimestamp>>,
+    /// Version of `zwlr_layer_shell_v1` offering the requests of the bound layer shell, which
+    /// limits the requests that can be made on the layer surface.
+    layer_shell_version: u32,
+    quirks: Rc<Cell<Quirks>>,
+    /// Features the compositor lacks that were already reported, to warn once per feature.
+    reported_degradations: RefCell<HashSet<&'static str>>,
+
+    raw_event_observer: RefCell<Option<Rc<dyn Fn(&RawSurfaceEvent)>>>,
+    /// Observers added alongside [`Self::raw_event_observer`], e.g. by the presets.
+    added_raw_event_observers: RefCell<Vec<Rc<dyn Fn(&RawSurfaceEvent)>>>,
+    pointer_button_callback: RefCell<Option<Box<dyn Fn(PointerButton, bool)>>>,
+    visibility_callback: RefCell<Option<Box<dyn Fn(&VisibilityChange)>>>,
+    /// Whether the xdg toplevel is suspended, to report only changes.
+    pub suspended: Cell<bool>,
+    #[cfg(feature = "recording")]
+    recorder: crate::recording::SharedRecorder,
+    /// Tells the window apart in recordings from others with the same namespace.
+    #[cfg(feature = "recording")]
+    pub(crate) recording_index: Cell<usize>,
+    /// Handles passed to the renderer on the first configure.
+    pending_window_handle: RefCell<Option<Arc<HandleHelper>>>,
+    self_weak: std::rc::Weak<Self>,
 }
 
 struct HandleHelper {
@@ -73,29 +177,91 @@ impl LayerShellWindowAdapter {
         connection: Connection,
         layer_shell_state: Rc<RefCell<LayerShellState>>,
         qh: QueueHandle<LayerShellState>,
+        role: SurfaceRole,
     ) -> Result<Rc<Self>, PlatformError> {
+        let layer_config = match &role {
+            SurfaceRole::Layer(config) => Some(config.clone()),
+            _ => None,
+        };
+        if let Some(config) = &layer_config {
+            config.validate()?;
+        }
         let skia_context = layer_shell_state.borrow().skia_shard_context.clone();
+        let (color_scheme, renderer, layer_shell_version, wl_compositor) = {
+            let state = layer_shell_state.borrow();
+            (
+                state.color_scheme,
+                state.renderer,
+                state
+                    .layer_shell
+                    .as_ref()
+                    .map_or(0, LayerShell::wlr_version),
+                state.compositor_state.wl_compositor().clone(),
+            )
+        };
         let handle_helper = Arc::new(HandleHelper {
             surface: surface.clone(),
             connection: connection.clone(),
         });
-        let render = SkiaRenderer::default_wgpu_27(&skia_context);
-        render.set_window_handle(
-            handle_helper.clone(),
-            handle_helper.clone(),
-            PhysicalSize::new(120, 120),
-            None,
-        )?;
-
-        let xdg_window = {
-            let state = layer_shell_state.borrow();
-            state
-                .xdg_shell
-                .create_window(surface.clone(), WindowDecorations::RequestServer, &qh)
+        let render = match renderer {
+            #[cfg(feature = "wgpu")]
+            Renderer::Wgpu => SkiaRenderer::default_wgpu_27(&skia_context),
+            Renderer::Software => SkiaRenderer::default_software(&skia_context),
+        };
+        // The renderer gets the surface once the first configure arrived, see
+        // `attach_renderer`, so that no buffer is created before the size is known.
+
+        if layer_config
+            .as_ref()
+            .is_some_and(|config| config.pass_through_input)
+        {
+            let region = create_region(&wl_compositor)?;
+            surface.set_input_region(Some(region.wl_region()));
+        }
+
+        #[cfg(feature = "session-lock")]
+        let mut session_lock_surface = None;
+        let (xdg_window, layer_surface) = match &role {
+            // Layer surfaces are committed in `set_visible`, once the preferred size is known.
+            SurfaceRole::Layer(config) => {
+                let state = layer_shell_state.borrow();
+                let layer_shell = state.layer_shell.as_ref().ok_or_else(|| {
+                    PlatformError::Other("the compositor has no layer shell".into())
+                })?;
+                let layer_surface = layer_shell.create_layer_surface(
+                    &qh,
+                    surface.clone(),
+                    config.layer,
+                    state.quirks.get().namespace(&config.namespace).to_owned(),
+                    config.output.as_ref(),
+                );
+                (None, Some(layer_surface))
+            }
+            #[cfg(feature = "session-lock")]
+            SurfaceRole::Lock {
+                session_lock,
+                output,
+            } => {
+                // Lock surfaces are configured by the compositor right away, no commit needed.
+                session_lock_surface =
+                    Some(session_lock.create_lock_surface(surface.clone(), output, &qh));
+                (None, None)
+            }
+            SurfaceRole::Toplevel => {
+                let xdg_window = {
+                    let state = layer_shell_state.borrow();
+                    state.xdg_shell.create_window(
+                        surface.clone(),
+                        WindowDecorations::RequestServer,
+                        &qh,
+                    )
+                };
+                xdg_window.set_title("slint-layer-shell");
+                xdg_window.set_app_id("slint-layer-shell");
+                xdg_window.commit();
+                (Some(xdg_window), None)
+            }
         };
-        xdg_window.set_title("slint-layer-shell");
-        xdg_window.set_app_id("slint-layer-shell");
-        xdg_window.commit();
 
         let adapter = Rc::new_cyclic(|weak_self: &std::rc::Weak<Self>| {
             let weak_dyn: std::rc::Weak<dyn WindowAdapter> = weak_self.clone();
@@ -103,38 +269,623 @@ impl LayerShellWindowAdapter {
 
             Self {
                 layer_shell_state: layer_shell_state.clone(),
+                queue_handle: qh.clone(),
+                wl_compositor: wl_compositor.clone(),
                 render,
                 window,
                 surface: surface.clone(),
-                xdg_window: Some(xdg_window.clone()),
-                layer_surface: None,
+                xdg_window,
+                layer_surface: RefCell::new(layer_surface),
+                #[cfg(feature = "session-lock")]
+                session_lock_surface: RefCell::new(session_lock_surface),
                 connection: connection.clone(),
 
                 window_state: Cell::new(WindowState::Pending),
+                visible: Cell::new(false),
                 pending_redraw: Cell::new(false),
+                input_region_outdated: Cell::new(true),
+                input_region_updated: Cell::new(None),
+                input_region_timer: slint::Timer::default(),
                 frame_callback_pending: Cell::new(false),
+                frame_requested_at: Cell::new(std::time::Instant::now()),
                 size: Cell::new(PhysicalSize::new(0, 0)),
                 pending_size: Cell::new(None),
+                position: Cell::new(None),
+                preferred_size: Cell::new(None),
+                layer_config: RefCell::new(layer_config),
+                entered_outputs: RefCell::new(Vec::new()),
+                preferred_buffer_scale: Cell::new(None),
+                transform: Cell::new(Transform::Normal),
+                color_scheme: Box::pin(Property::new(color_scheme)),
+                system_color_scheme: Cell::new(color_scheme),
+                color_scheme_override: Cell::new(WindowColorScheme::System),
+                user_data: RefCell::new(None),
+                last_input: Cell::new(None),
+                layer_shell_version,
+                quirks: layer_shell_state.borrow().quirks.clone(),
+                reported_degradations: RefCell::default(),
+
+                raw_event_observer: RefCell::new(None),
+                added_raw_event_observers: RefCell::new(Vec::new()),
+                pointer_button_callback: RefCell::new(None),
+                visibility_callback: RefCell::new(None),
+                suspended: Cell::new(false),
+                #[cfg(feature = "recording")]
+                recorder: layer_shell_state.borrow().recorder.clone(),
+                #[cfg(feature = "recording")]
+                recording_index: Cell::new(0),
+                pending_window_handle: RefCell::new(Some(handle_helper.clone())),
+                self_weak: weak_self.clone(),
             }
         });
 
+        // Surfaces pinned to an output start out at that output's scale, so that the first
+        // frame is not rendered at 1x and then re-rendered once `surface_enter` arrives.
+        let initial_scale = adapter
+            .layer_config
+            .borrow()
+            .as_ref()
+            .filter(|_| adapter.surface.version() >= 3)
+            .and_then(|config| config.output.as_ref())
+            .and_then(|output| layer_shell_state.borrow().output_state.info(output))
+            .map(|info| info.scale_factor.max(1));
+        if let Some(scale) = initial_scale {
+            adapter.surface.set_buffer_scale(scale);
+            adapter.forward_event(WindowEvent::ScaleFactorChanged {
+                scale_factor: scale as f32,
+            });
+        }
+
+        #[cfg(feature = "recording")]
+        adapter.assign_recording_index(&layer_shell_state.borrow());
         let id = adapter.surface.id();
-        layer_shell_state
+        let mut state = layer_shell_state.borrow_mut();
+        state.window_adapters.insert(id, Rc::downgrade(&adapter));
+        state
+            .shared
             .borrow_mut()
             .window_adapters
-            .insert(id, Rc::downgrade(&adapter));
+            .push(Rc::downgrade(&adapter));
+        drop(state);
 
         Ok(adapter)
     }
 
-    pub fn set_size(&self, size: PhysicalSize) {
+    /// Returns the adapter backing `window`, or `None` if the window belongs to another
+    /// platform. Lets libraries reach backend functionality from a public Slint window.
+    pub fn from_slint_window(window: &SlintWindow) -> Option<Rc<Self>> {
+        let window_adapter = WindowInner::from_pub(window).window_adapter();
+        window_adapter
+            .internal(i_slint_core::InternalToken)?
+            .as_any()
+            .downcast_ref::<Self>()?
+            .self_weak
+            .upgrade()
+    }
+
+    /// Hands the surface to the renderer at the configured `size`. Does nothing once the
+    /// renderer has it.
+    pub(crate) fn attach_renderer(&self, size: PhysicalSize) -> Result<(), PlatformError> {
+        let Some(handle_helper) = self.pending_window_handle.borrow().clone() else {
+            return Ok(());
+        };
+        // Kept until the renderer accepts it, so that a failed attempt is retried on the next
+        // configure.
+        self.render
+            .set_window_handle(handle_helper.clone(), handle_helper, size, None)?;
+        self.pending_window_handle.borrow_mut().take();
+        Ok(())
+    }
+
+    /// Records an input event with the Wayland timestamp `time`, as Slint's window events
+    /// carry no timestamps.
+    pub(crate) fn record_input(&self, time: u32) {
+        self.last_input.set(Some(InputTimestamp {
+            time,
+            received: std::time::Instant::now(),
+        }));
+    }
+
+    pub(crate) fn set_color_scheme(&self, color_scheme: ColorScheme) {
+        self.system_color_scheme.set(color_scheme);
+        self.update_color_scheme();
+    }
+
+    /// Overrides the platform's color scheme for this window only, see
+    /// [`crate::window_ext::WindowExt::set_color_scheme`].
+    pub fn set_color_scheme_override(&self, color_scheme: WindowColorScheme) {
+        self.color_scheme_override.set(color_scheme);
+        self.update_color_scheme();
+    }
+
+    fn update_color_scheme(&self) {
+        let color_scheme = match self.color_scheme_override.get() {
+            WindowColorScheme::System => self.system_color_scheme.get(),
+            WindowColorScheme::Light => ColorScheme::Light,
+            WindowColorScheme::Dark => ColorScheme::Dark,
+        };
+        self.color_scheme.as_ref().set(color_scheme);
+    }
+
+    /// Resizes the window on its next frame. Fails before the compositor configured the
+    /// window, which picks its first size.
+    pub fn set_size(&self, size: PhysicalSize) -> Result<(), SurfaceError> {
+        if self.window_state.get() != WindowState::Configured {
+            return Err(SurfaceError::NotConfigured);
+        }
         self.pending_size.set(Some(size));
         self.pending_redraw.set(true);
+        Ok(())
     }
 
     pub fn surface(&self) -> &WlSurface {
         &self.surface
     }
+
+    /// Returns the layer surface backing the window, to make protocol requests this crate
+    /// does not wrap. It changes when the surface is recreated on another output.
+    pub fn layer_surface(&self) -> Option<LayerSurface> {
+        self.layer_surface.borrow().clone()
+    }
+
+    /// Returns the xdg toplevel backing the window, if it is not a layer surface.
+    pub fn xdg_window(&self) -> Option<&XdgWindow> {
+        self.xdg_window.as_ref()
+    }
+
+    #[cfg(feature = "session-lock")]
+    pub fn session_lock_surface(&self) -> Option<SessionLockSurface> {
+        self.session_lock_surface.borrow().clone()
+    }
+
+    /// Installs a callback receiving the raw Wayland events of this surface.
+    ///
+    /// The observer runs while the Wayland queue is being dispatched, so it must not create
+    /// windows. Only one observer can be installed at a time, besides the ones added with
+    /// [`Self::add_raw_event_observer`].
+    pub fn set_raw_event_observer(&self, observer: impl Fn(&RawSurfaceEvent) + 'static) {
+        *self.raw_event_observer.borrow_mut() = Some(Rc::new(observer));
+    }
+
+    /// Adds a callback receiving the raw Wayland events of this surface, which stays
+    /// installed along with the window. Unlike [`Self::set_raw_event_observer`], it does not
+    /// replace other observers.
+    pub fn add_raw_event_observer(&self, observer: impl Fn(&RawSurfaceEvent) + 'static) {
+        self.added_raw_event_observers
+            .borrow_mut()
+            .push(Rc::new(observer));
+    }
+
+    pub fn clear_raw_event_observer(&self) {
+        self.raw_event_observer.borrow_mut().take();
+    }
+
+    /// Installs a callback receiving every pointer button press (`true`) and release
+    /// (`false`) on this surface, including buttons Slint has no name for.
+    ///
+    /// Like raw event observers, the callback runs while the Wayland queue is being
+    /// dispatched, so it must not create windows.
+    pub fn on_pointer_button(&self, callback: impl Fn(PointerButton, bool) + 'static) {
+        *self.pointer_button_callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    pub(crate) fn pointer_button(&self, code: u32, pressed: bool) {
+        if let Some(callback) = self.pointer_button_callback.borrow().as_ref() {
+            callback(PointerButton::from_code(code), pressed);
+        }
+    }
+
+    /// Limits the input region to the pixels of the frame about to be rendered that are not
+    /// fully transparent, for [`LayerConfig::click_through_transparent`]. The region is
+    /// committed along with the frame, and only recomputed when Slint changed the content.
+    ///
+    /// Reading back a frame renders it a second time, so while the content keeps changing,
+    /// as during animations, the region is derived at most every [`INPUT_REGION_INTERVAL`].
+    /// A new window size derives it right away.
+    pub(crate) fn update_input_region_from_alpha(&self) {
+        let enabled =
+            self.layer_config.borrow().as_ref().is_some_and(|config| {
+                config.click_through_transparent && !config.pass_through_input
+            });
+        if !enabled || !self.input_region_outdated.get() {
+            return;
+        }
+        let now = std::time::Instant::now();
+        let size = self.size.get();
+        if let Some((updated_at, updated_size)) = self.input_region_updated.get() {
+            let due = updated_at + INPUT_REGION_INTERVAL;
+            if updated_size == size && now < due {
+                self.update_input_region_later(due - now);
+                return;
+            }
+        }
+        self.input_region_outdated.set(false);
+        self.input_region_updated.set(Some((now, size)));
+        let snapshot = match self.window.take_snapshot() {
+            Ok(snapshot) if snapshot.width() > 0 => snapshot,
+            Ok(_) => return,
+            Err(err) => {
+                log::warn!("failed to read back the frame for the input region: {err}");
+                return;
+            }
+        };
+        let region = match create_region(&self.wl_compositor) {
+            Ok(region) => region,
+            Err(err) => {
+                log::warn!("{err}");
+                return;
+            }
+        };
+
+        // Rows are combined into bands and consecutive bands with the same opaque spans are
+        // added as one rectangle per span, which bounds the number of rectangles.
+        let scale_factor = self.window.scale_factor();
+        let add_spans = |spans: &[(usize, usize)], first_row: usize, end_row: usize| {
+            let y = (first_row as f32 / scale_factor).floor() as i32;
+            let height = (end_row as f32 / scale_factor).ceil() as i32 - y;
+            for &(start, end) in spans {
+                let x = (start as f32 / scale_factor).floor() as i32;
+                let width = (end as f32 / scale_factor).ceil() as i32 - x;
+                region.add(x, y, width, height);
+            }
+        };
+        let granularity = ((INPUT_REGION_GRANULARITY * scale_factor).round() as usize).max(1);
+        let width = snapshot.width() as usize;
+        let mut band_alpha = vec![0; width];
+        let mut spans = Vec::new();
+        let mut first_row = 0;
+        for (band_index, band) in snapshot.as_slice().chunks(width * granularity).enumerate() {
+            band_alpha.fill(0);
+            for row in band.chunks(width) {
+                for (alpha, pixel) in band_alpha.iter_mut().zip(row) {
+                    *alpha = pixel.a.max(*alpha);
+                }
+            }
+            let band_spans = opaque_spans(&band_alpha, granularity);
+            if band_spans != spans {
+                let row = band_index * granularity;
+                add_spans(&spans, first_row, row);
+                spans = band_spans;
+                first_row = row;
+            }
+        }
+        add_spans(&spans, first_row, snapshot.height() as usize);
+
+        self.surface.set_input_region(Some(region.wl_region()));
+    }
+
+    /// Redraws the window after `delay` to derive the input region of content that changed
+    /// too soon after the last one, unless such a redraw is scheduled already.
+    fn update_input_region_later(&self, delay: std::time::Duration) {
+        if self.input_region_timer.running() {
+            return;
+        }
+        let weak = self.self_weak.clone();
+        self.input_region_timer
+            .start(slint::TimerMode::SingleShot, delay, move || {
+                if let Some(this) = weak.upgrade() {
+                    this.window.request_redraw();
+                }
+            });
+    }
+
+    /// Installs a callback receiving the visibility changes of this window.
+    ///
+    /// Like raw event observers, the callback may run while the Wayland queue is being
+    /// dispatched, so it must not create windows.
+    pub fn on_visibility_changed(&self, callback: impl Fn(&VisibilityChange) + 'static) {
+        *self.visibility_callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    pub(crate) fn notify_visibility(&self, change: VisibilityChange) {
+        if let Some(callback) = self.visibility_callback.borrow().as_ref() {
+            callback(&change);
+        }
+    }
+
+    /// Applies the stored layer configuration and commits the surface without a buffer, which
+    /// makes the compositor send the initial configure.
+    fn commit_layer_surface(&self, layer_surface: &LayerSurface) {
+        let config = self.layer_config.borrow();
+        let Some(config) = config.as_ref() else {
+            return;
+        };
+
+        // Axes anchored to both opposite edges are sized by the compositor.
+        let preferred_size = self.preferred_size.get().unwrap_or_default();
+        let (stretch_width, stretch_height) = config.stretched();
+        let (width, height) = config.size.unwrap_or((
+            // A width or height of 0 is a protocol error on axes that are not stretched.
+            if stretch_width {
+                0
+            } else {
+                (config.rounding.apply(preferred_size.width) as u32).max(1)
+            },
+            if stretch_height {
+                0
+            } else {
+                (config.rounding.apply(preferred_size.height) as u32).max(1)
+            },
+        ));
+        layer_surface.set_anchor(config.anchor);
+        layer_surface.set_size(width, height);
+        let margins = config.margins;
+        layer_surface.set_margin(margins.top, margins.right, margins.bottom, margins.left);
+        let logical_size = self.size.get().to_logical(self.window.scale_factor());
+        let size = if logical_size.width > 0.0 {
+            (logical_size.width, logical_size.height)
+        } else {
+            (preferred_size.width, preferred_size.height)
+        };
+        layer_surface.set_exclusive_zone(config.effective_exclusive_zone(size));
+        layer_surface.set_keyboard_interactivity(
+            self.supported_keyboard_interactivity(config.keyboard_interactivity),
+        );
+        layer_surface.commit();
+    }
+
+    /// Returns `requested`, or `Exclusive` in place of `OnDemand` if the compositor's layer
+    /// shell predates version 4, where sending it would be a protocol error.
+    fn supported_keyboard_interactivity(
+        &self,
+        requested: KeyboardInteractivity,
+    ) -> KeyboardInteractivity {
+        if requested == KeyboardInteractivity::OnDemand && self.quirks.get().on_demand_as_exclusive
+        {
+            return KeyboardInteractivity::Exclusive;
+        }
+        if requested == KeyboardInteractivity::OnDemand && self.layer_shell_version < 4 {
+            self.report_degradation(
+                "on-demand keyboard interactivity needs zwlr_layer_shell_v1 version 4, \
+                 using exclusive instead",
+            );
+            return KeyboardInteractivity::Exclusive;
+        }
+        requested
+    }
+
+    /// Moves the layer surface to `layer`, which needs layer shell version 2. Older
+    /// compositors keep the layer the surface was created on.
+    fn set_surface_layer(&self, layer_surface: &LayerSurface, layer: Layer) {
+        if self.layer_shell_version < 2 {
+            self.report_degradation(
+                "changing the layer needs zwlr_layer_shell_v1 version 2, re-creating the surface",
+            );
+            self.recreate_layer_surface_later();
+            return;
+        }
+        layer_surface.set_layer(layer);
+    }
+
+    fn report_degradation(&self, message: &'static str) {
+        if self.reported_degradations.borrow_mut().insert(message) {
+            log::warn!(
+                "{message} (the compositor provides version {})",
+                self.layer_shell_version
+            );
+        }
+    }
+
+    /// Updates the exclusive zone of a configured layer surface whose zone follows its size.
+    /// The change is applied with the next rendered frame.
+    pub(crate) fn update_auto_exclusive_zone(&self) {
+        let config = self.layer_config.borrow();
+        let Some(config) = config.as_ref().filter(|config| config.auto_exclusive_zone) else {
+            return;
+        };
+        if let Some(layer_surface) = self.layer_surface.borrow().as_ref() {
+            let size = self.size.get().to_logical(self.window.scale_factor());
+            layer_surface
+                .set_exclusive_zone(config.effective_exclusive_zone((size.width, size.height)));
+        }
+    }
+
+    /// Re-sends the layer configuration so that the compositor sends a new configure.
+    pub(crate) fn reconfigure(&self) {
+        if self.window_state.get() != WindowState::Configured {
+            return;
+        }
+        if let Some(layer_surface) = self.layer_surface.borrow().as_ref() {
+            self.commit_layer_surface(layer_surface);
+        }
+    }
+
+    /// Replaces the layer configuration of a layer surface at runtime. A new output or
+    /// namespace re-creates the layer surface, as both are set when it is created. Without an
+    /// output, the surface stays on its current one.
+    pub(crate) fn set_layer_config(&self, config: LayerConfig) -> Result<(), PlatformError> {
+        let Some(previous) = self.layer_config.borrow().clone() else {
+            return Err(PlatformError::Other(
+                "the window is not a layer surface".into(),
+            ));
+        };
+        let config = LayerConfig {
+            output: config.output.or_else(|| previous.output.clone()),
+            ..config
+        };
+        config.validate()?;
+        if config == previous {
+            return Ok(());
+        }
+        *self.layer_config.borrow_mut() = Some(config.clone());
+
+        let click_through_disabled =
+            previous.click_through_transparent && !config.click_through_transparent;
+        if config.pass_through_input != previous.pass_through_input || click_through_disabled {
+            if config.pass_through_input {
+                let region = create_region(&self.wl_compositor)?;
+                self.surface.set_input_region(Some(region.wl_region()));
+            } else {
+                self.surface.set_input_region(None);
+            }
+        }
+        if config.click_through_transparent && !previous.click_through_transparent {
+            // The input region is derived from the next frame.
+            self.pending_redraw.set(true);
+            self.input_region_outdated.set(true);
+        }
+        if config.output != previous.output || config.namespace != previous.namespace {
+            self.recreate_layer_surface_later();
+            return Ok(());
+        }
+        if let Some(layer_surface) = self.layer_surface.borrow().as_ref() {
+            if config.layer != previous.layer {
+                self.set_surface_layer(layer_surface, config.layer);
+            }
+        }
+        self.reconfigure();
+        Ok(())
+    }
+
+    /// Replaces the layer surface with one created from the current layer configuration, for
+    /// properties that cannot change on an existing surface. The surface is replaced outside
+    /// of event dispatch, which borrows the state.
+    fn recreate_layer_surface_later(&self) {
+        let weak = self.self_weak.clone();
+        slint::Timer::single_shot(std::time::Duration::ZERO, move || {
+            let Some(this) = weak.upgrade() else {
+                return;
+            };
+            if this.layer_surface.borrow().is_none() {
+                return;
+            }
+            let output = this
+                .layer_config
+                .borrow()
+                .as_ref()
+                .and_then(|config| config.output.clone());
+            let state = this.layer_shell_state.borrow();
+            if let Some(layer_shell) = &state.layer_shell {
+                this.recreate_layer_surface(layer_shell, &this.queue_handle, output);
+            }
+        });
+    }
+
+    /// Replaces the layer surface with a new one on `output`, as layer surfaces cannot move
+    /// between outputs. The window is mapped again once the new surface is configured.
+    pub(crate) fn recreate_layer_surface(
+        &self,
+        layer_shell: &LayerShell,
+        qh: &QueueHandle<LayerShellState>,
+        output: Option<WlOutput>,
+    ) {
+        let Some(config) = self.layer_config.borrow_mut().as_mut().map(|config| {
+            config.output = output;
+            config.clone()
+        }) else {
+            return;
+        };
+
+        self.retire_layer_surface();
+        self.surface.attach(None::<&WlBuffer>, 0, 0);
+        let layer_surface = layer_shell.create_layer_surface(
+            qh,
+            self.surface.clone(),
+            config.layer,
+            self.quirks.get().namespace(&config.namespace).to_owned(),
+            config.output.as_ref(),
+        );
+        self.window_state.set(WindowState::Pending);
+        self.frame_callback_pending.set(false);
+        self.commit_layer_surface(&layer_surface);
+        *self.layer_surface.borrow_mut() = Some(layer_surface);
+    }
+
+    /// Destroys the current layer surface, which unmaps the window, while keeping its
+    /// `wl_surface` alive to create another layer surface on it.
+    ///
+    /// The role object is destroyed explicitly, as clones of the layer surface handed out by
+    /// [`Self::layer_surface`] would keep it alive otherwise.
+    pub(crate) fn retire_layer_surface(&self) {
+        let Some(layer_surface) = self.layer_surface.borrow_mut().take() else {
+            return;
+        };
+        layer_surface.destroy_role();
+    }
+
+    /// Releases the renderer's surface and destroys the role of a layer or lock surface, for
+    /// [`SlintLayerShell::suspend`]. The window must be hidden. Showing it again creates a
+    /// new layer surface, and hands the surface to the renderer again once configured.
+    ///
+    /// [`SlintLayerShell::suspend`]: crate::platform::SlintLayerShell::suspend
+    pub(crate) fn tear_down(&self) -> Result<(), PlatformError> {
+        self.render.suspend()?;
+        *self.pending_window_handle.borrow_mut() = Some(Arc::new(HandleHelper {
+            surface: self.surface.clone(),
+            connection: self.connection.clone(),
+        }));
+        self.retire_layer_surface();
+        #[cfg(feature = "session-lock")]
+        self.session_lock_surface.borrow_mut().take();
+        self.window_state.set(WindowState::Pending);
+        self.frame_callback_pending.set(false);
+        Ok(())
+    }
+
+    /// Sends `CloseRequested` to the Slint window, which hides it unless its
+    /// `close-requested` callback keeps it shown. The event is dispatched outside of event
+    /// dispatch, as the callback may call into the platform.
+    pub(crate) fn request_close(&self) {
+        let weak = self.self_weak.clone();
+        slint::Timer::single_shot(std::time::Duration::ZERO, move || {
+            if let Some(this) = weak.upgrade() {
+                this.forward_event(WindowEvent::CloseRequested);
+            }
+        });
+    }
+
+    /// Dispatches `event` to the Slint window, recording it while a recording is in
+    /// progress.
+    pub(crate) fn forward_event(&self, event: WindowEvent) {
+        if debug_events() {
+            log::info!(target: EVENTS_LOG_TARGET, "{:?} -> {event:?}", self.surface.id());
+        }
+        #[cfg(feature = "recording")]
+        if let Some(recorder) = self.recorder.borrow_mut().as_mut() {
+            recorder.record(&self.recording_key(), &event);
+        }
+        let _ = self.window.try_dispatch_event(event);
+    }
+
+    pub(crate) fn observe_raw_event(&self, event: RawSurfaceEvent) {
+        if debug_events() {
+            log::info!(target: EVENTS_LOG_TARGET, "{:?} <- {event:?}", self.surface.id());
+        }
+        // Cloned out of the cells, as the observers may replace, clear or add observers.
+        let observer = self.raw_event_observer.borrow().clone();
+        let added = self.added_raw_event_observers.borrow().clone();
+        for observer in added.iter().chain(&observer) {
+            observer(&event);
+        }
+    }
+}
+
+impl Drop for LayerShellWindowAdapter {
+    fn drop(&mut self) {
+        // Destroy the renderer's surface before the role objects, and those before the
+        // `wl_surface`. sctk's windows and lock surfaces destroy it along with themselves,
+        // layer surfaces leave it to the window.
+        let _ = self.render.suspend();
+        self.retire_layer_surface();
+        self.xdg_window.take();
+        #[cfg(feature = "session-lock")]
+        self.session_lock_surface.borrow_mut().take();
+        self.surface.destroy();
+
+        let id = self.surface.id();
+        if let Ok(mut state) = self.layer_shell_state.try_borrow_mut() {
+            state.forget_surface(&id);
+            return;
+        }
+        // Windows closed from a Slint callback are dropped during Wayland dispatch, while the
+        // state is borrowed.
+        let layer_shell_state = Rc::downgrade(&self.layer_shell_state);
+        slint::Timer::single_shot(std::time::Duration::ZERO, move || {
+            if let Some(layer_shell_state) = layer_shell_state.upgrade() {
+                layer_shell_state.borrow_mut().forget_surface(&id);
+            }
+        });
+    }
 }
 
 impl WindowAdapter for LayerShellWindowAdapter {
@@ -143,9 +894,44 @@ impl WindowAdapter for LayerShellWindowAdapter {
     }
 
     fn set_visible(&self, visible: bool) -> Result<(), PlatformError> {
+        self.visible.set(visible);
+        #[cfg(feature = "session-lock")]
+        if !visible && self.session_lock_surface.borrow_mut().take().is_some() {
+            // Attaching a null buffer to a lock surface is a protocol error, so hiding destroys
+            // it. Its window cannot be shown again, lock surfaces belong to a single lock.
+            self.notify_visibility(VisibilityChange::Unmapped);
+            return Ok(());
+        }
         if !visible {
             self.surface.attach(None::<&WlBuffer>, 0, 0);
             self.surface.commit();
+            if self.layer_surface.borrow().is_some() || self.xdg_window.is_some() {
+                // Unmapping a surface resets it, a new configure is sent on the next commit.
+                self.window_state.set(WindowState::Pending);
+            }
+            self.notify_visibility(VisibilityChange::Unmapped);
+        } else if let Some(layer_surface) = self.layer_surface.borrow().as_ref() {
+            if self.window_state.get() == WindowState::Pending {
+                self.commit_layer_surface(layer_surface);
+            }
+        } else if let Some(xdg_window) = &self.xdg_window {
+            if self.window_state.get() == WindowState::Pending {
+                xdg_window.commit();
+            }
+        } else if self.layer_config.borrow().is_some() {
+            // The compositor closed the previous layer surface, e.g. as its output was removed.
+            let state = self.layer_shell_state.try_borrow().map_err(|_| {
+                PlatformError::Other("cannot show a closed layer surface during dispatch".into())
+            })?;
+            let output = self
+                .layer_config
+                .borrow()
+                .as_ref()
+                .and_then(|config| config.output.clone())
+                .filter(|output| state.outputs().iter().any(|info| &info.output == output));
+            if let Some(layer_shell) = &state.layer_shell {
+                self.recreate_layer_surface(layer_shell, &self.queue_handle, output);
+            }
         }
         Ok(())
     }
@@ -154,8 +940,13 @@ impl WindowAdapter for LayerShellWindowAdapter {
         self.size.get()
     }
 
+    fn position(&self) -> Option<PhysicalPosition> {
+        self.position.get()
+    }
+
     fn request_redraw(&self) {
         self.pending_redraw.set(true);
+        self.input_region_outdated.set(true);
     }
 
     fn renderer(&self) -> &dyn slint::platform::Renderer {
@@ -163,10 +954,117 @@ impl WindowAdapter for LayerShellWindowAdapter {
     }
 
     fn update_window_properties(&self, properties: slint::platform::WindowProperties<'_>) {
-        println!("{:#?}", DebugWindowProperties(properties));
+        let preferred_size = properties.layout_constraints().preferred;
+        let previous_size = self.preferred_size.replace(Some(preferred_size));
+
+        // Layer surfaces without a fixed size follow the preferred size of their content.
+        let follows_content = self
+            .layer_config
+            .borrow()
+            .as_ref()
+            .is_some_and(|config| config.size.is_none());
+        let title = properties.title();
+        let config = self.layer_config.borrow().clone();
+        if let Some(mut config) = config {
+            let result = match config.apply_title_hints(&title) {
+                Ok(true) => self.set_layer_config(config),
+                Ok(false) => Ok(()),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                log::warn!("ignoring the layer shell hints of the window title: {err}");
+            }
+        }
+
+        if follows_content && previous_size.is_some_and(|size| size != preferred_size) {
+            self.reconfigure();
+        }
+        log::trace!("{:#?}", DebugWindowProperties(properties));
+    }
+
+    fn internal(&self, _: i_slint_core::InternalToken) -> Option<&dyn WindowAdapterInternal> {
+        Some(self)
+    }
+}
+
+impl WindowAdapterInternal for LayerShellWindowAdapter {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme.as_ref().get()
+    }
+
+    fn input_method_request(&self, request: InputMethodRequest) {
+        let event = match &request {
+            InputMethodRequest::Enable(_) => Some(BackendEvent::TextInputEnabled {
+                surface: self.surface.clone(),
+            }),
+            InputMethodRequest::Disable => Some(BackendEvent::TextInputDisabled {
+                surface: self.surface.clone(),
+            }),
+            _ => None,
+        };
+        #[cfg(feature = "text-input")]
+        let request = {
+            use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::ContentPurpose;
+            let cursor = |properties: &i_slint_core::window::InputMethodProperties| {
+                let origin = properties.cursor_rect_origin;
+                let size = properties.cursor_rect_size;
+                (
+                    origin.x.round() as i32,
+                    origin.y.round() as i32,
+                    size.width.ceil() as i32,
+                    size.height.ceil() as i32,
+                )
+            };
+            match &request {
+                InputMethodRequest::Enable(properties) => {
+                    Some(crate::text_input::TextInputRequest::Enable {
+                        purpose: match properties.input_type {
+                            i_slint_core::items::InputType::Password => ContentPurpose::Password,
+                            i_slint_core::items::InputType::Number => ContentPurpose::Digits,
+                            i_slint_core::items::InputType::Decimal => ContentPurpose::Number,
+                            _ => ContentPurpose::Normal,
+                        },
+                        cursor: cursor(properties),
+                    })
+                }
+                InputMethodRequest::Update(properties) => {
+                    Some(crate::text_input::TextInputRequest::Update {
+                        cursor: cursor(properties),
+                    })
+                }
+                InputMethodRequest::Disable => Some(crate::text_input::TextInputRequest::Disable),
+                _ => None,
+            }
+        };
+        // Focus changes while events are dispatched, when the platform state is borrowed.
+        let state = self.layer_shell_state.clone();
+        #[cfg(feature = "text-input")]
+        let surface = self.surface.clone();
+        slint::Timer::single_shot(std::time::Duration::ZERO, move || {
+            let mut state = state.borrow_mut();
+            #[cfg(feature = "text-input")]
+            if let Some(request) = request {
+                state.request_text_input(&surface, request);
+            }
+            if let Some(event) = event {
+                state.backend_events.push_back(event);
+            }
+        });
     }
 }
 
+/// Creates an empty region, to which rectangles can be added.
+fn create_region(wl_compositor: &WlCompositor) -> Result<Region, PlatformError> {
+    Region::new(&SimpleGlobal::<WlCompositor, 6>::from_bound(
+        wl_compositor.clone(),
+    ))
+    .map_err(|e| PlatformError::Other(format!("failed to create region: {e}")))
+}
+
 struct DebugWindowProperties<'a>(slint::platform::WindowProperties<'a>);
 
 impl fmt::Debug for DebugWindowProperties<'_> {
@@ -181,3 +1079,61 @@ impl fmt::Debug for DebugWindowProperties<'_> {
             .finish()
     }
 }
+
+/// Size in logical pixels of the bands and gaps the input region is simplified to, see
+/// [`LayerShellWindowAdapter::update_input_region_from_alpha`]. Anti-aliased text would
+/// otherwise add thousands of rectangles.
+const INPUT_REGION_GRANULARITY: f32 = 4.0;
+
+/// Minimum time between two input regions derived from changing content, see
+/// [`LayerShellWindowAdapter::update_input_region_from_alpha`].
+const INPUT_REGION_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Returns the ranges of pixels in `alphas` that are not fully transparent, bridging
+/// transparent gaps narrower than `min_gap`.
+fn opaque_spans(alphas: &[u8], min_gap: usize) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut add_span = |start: usize, end: usize| match spans.last_mut() {
+        Some(last) if start - last.1 < min_gap => last.1 = end,
+        _ => spans.push((start, end)),
+    };
+    let mut start = None;
+    for (index, &alpha) in alphas.iter().enumerate() {
+        match (alpha > 0, start) {
+            (true, None) => start = Some(index),
+            (false, Some(span_start)) => {
+                add_span(span_start, index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(span_start) = start {
+        add_span(span_start, alphas.len());
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::opaque_spans;
+
+    #[test]
+    fn opaque_spans_finds_runs_of_visible_pixels() {
+        assert!(opaque_spans(&[], 1).is_empty());
+        assert!(opaque_spans(&[0, 0, 0], 1).is_empty());
+        assert_eq!(opaque_spans(&[255, 255], 1), vec![(0, 2)]);
+        assert_eq!(
+            opaque_spans(&[0, 1, 255, 0, 0, 128, 0], 1),
+            vec![(1, 3), (5, 6)]
+        );
+    }
+
+    #[test]
+    fn opaque_spans_bridges_narrow_gaps() {
+        let alphas = [255, 0, 255, 0, 0, 0, 255, 255];
+        assert_eq!(opaque_spans(&alphas, 1), vec![(0, 1), (2, 3), (6, 8)]);
+        assert_eq!(opaque_spans(&alphas, 2), vec![(0, 3), (6, 8)]);
+        assert_eq!(opaque_spans(&alphas, 4), vec![(0, 8)]);
+    }
+}
@@ -0,0 +1,216 @@
+//! Layer surface properties accessed directly on a [`slint::Window`].
+
+use crate::event::InputTimestamp;
+use crate::layer::{Anchor, KeyboardInteractivity, Layer, LayerConfig, Margins};
+use crate::layer_shell::LayerSurface;
+use crate::window_adapter::LayerShellWindowAdapter;
+use slint::PlatformError;
+#[cfg(feature = "session-lock")]
+use smithay_client_toolkit::session_lock::SessionLockSurface;
+use smithay_client_toolkit::shell::xdg::window::Window as XdgWindow;
+use std::any::Any;
+use std::rc::Rc;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+/// Color scheme of a single window, see [`WindowExt::set_color_scheme`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowColorScheme {
+    /// Follows [`SlintLayerShell::set_color_scheme`].
+    ///
+    /// [`SlintLayerShell::set_color_scheme`]: crate::platform::SlintLayerShell::set_color_scheme
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Changes the layer surface backing a Slint window at runtime.
+///
+/// ```ignore
+/// use slint_layer_shell::window_ext::WindowExt;
+///
+/// ui.window().set_anchor(Anchor::TOP | Anchor::LEFT)?;
+/// ui.window().set_exclusive_zone(32)?;
+/// ```
+///
+/// The setters fail if the window is not backed by this platform or is not a layer surface.
+pub trait WindowExt {
+    /// Returns the layer configuration, or `None` if the window is not a layer surface.
+    fn layer_config(&self) -> Option<LayerConfig>;
+
+    /// Replaces the layer configuration, see [`SlintLayerShell::set_layer_config`].
+    ///
+    /// [`SlintLayerShell::set_layer_config`]: crate::platform::SlintLayerShell::set_layer_config
+    fn set_layer_config(&self, config: LayerConfig) -> Result<(), PlatformError>;
+
+    /// Moves the surface to other edges, see [`LayerConfig::set_anchor`].
+    fn set_anchor(&self, anchor: Anchor) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| config.set_anchor(anchor))
+    }
+
+    /// Changes the anchors and margins in a single commit, so the surface is never shown
+    /// with the new anchors and the old margins.
+    fn set_placement(&self, anchor: Anchor, margins: Margins) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| {
+            config.set_anchor(anchor);
+            config.margins = margins;
+        })
+    }
+
+    /// Moves the surface to another layer, e.g. to raise an urgent notification to
+    /// [`Layer::Overlay`]. Compositors without version 2 of the layer shell cannot change the
+    /// layer of a surface, so it is re-created on the new layer, and mapped again once
+    /// configured.
+    fn set_layer(&self, layer: Layer) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| config.layer = layer)
+    }
+
+    fn set_margins(&self, margins: Margins) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| config.margins = margins)
+    }
+
+    /// Sets a fixed exclusive zone, turning off the automatic exclusive zone. Use
+    /// [`LayerConfig::IGNORE_EXCLUSIVE_ZONES`] to extend the surface below panels.
+    fn set_exclusive_zone(&self, exclusive_zone: i32) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| {
+            config.exclusive_zone = exclusive_zone;
+            config.auto_exclusive_zone = false;
+        })
+    }
+
+    /// Keeps the exclusive zone equal to the window's thickness along its anchored edge, so
+    /// a panel whose content grows reserves more space, see
+    /// [`LayerConfig::auto_exclusive_zone`].
+    fn set_auto_exclusive_zone(&self, enabled: bool) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| config.auto_exclusive_zone = enabled)
+    }
+
+    fn set_keyboard_interactivity(
+        &self,
+        keyboard_interactivity: KeyboardInteractivity,
+    ) -> Result<(), PlatformError> {
+        self.update_layer_config(|config| config.keyboard_interactivity = keyboard_interactivity)
+    }
+
+    /// Attaches application state to the window, replacing any previous value. It can be
+    /// retrieved from the window's surface in backend callbacks with
+    /// [`SlintLayerShell::user_data`].
+    ///
+    /// Returns `false` if the window is not backed by this platform.
+    ///
+    /// [`SlintLayerShell::user_data`]: crate::platform::SlintLayerShell::user_data
+    fn set_user_data(&self, user_data: Rc<dyn Any>) -> bool;
+
+    fn user_data(&self) -> Option<Rc<dyn Any>>;
+
+    /// Returns the attached application state if it is of type `T`.
+    fn user_data_as<T: Any>(&self) -> Option<Rc<T>> {
+        self.user_data()?.downcast().ok()
+    }
+
+    /// Returns the Wayland surface of the window, or `None` if the window is not backed by
+    /// this platform.
+    fn wl_surface(&self) -> Option<WlSurface>;
+
+    /// Returns the layer surface of the window, see [`LayerShellWindowAdapter::layer_surface`].
+    fn layer_surface(&self) -> Option<LayerSurface>;
+
+    /// Returns the xdg toplevel of the window, if it is not a layer surface.
+    fn xdg_window(&self) -> Option<XdgWindow>;
+
+    /// Returns the session lock surface of the window, if it was created by a lock screen.
+    #[cfg(feature = "session-lock")]
+    fn session_lock_surface(&self) -> Option<SessionLockSurface>;
+
+    /// Selects the light or dark palette of Slint's styles for this window, independently of
+    /// the color scheme of the other windows, e.g. for a dark bar next to a light settings
+    /// window.
+    ///
+    /// Returns `false` if the window is not backed by this platform.
+    fn set_color_scheme(&self, color_scheme: WindowColorScheme) -> bool;
+
+    /// Returns when the window last received input, e.g. to hide it after a period of
+    /// inactivity.
+    fn last_input(&self) -> Option<InputTimestamp>;
+
+    /// Applies `update` to the current layer configuration.
+    fn update_layer_config(
+        &self,
+        update: impl FnOnce(&mut LayerConfig),
+    ) -> Result<(), PlatformError> {
+        let mut config = self
+            .layer_config()
+            .ok_or_else(|| PlatformError::Other("the window is not a layer surface".into()))?;
+        update(&mut config);
+        self.set_layer_config(config)
+    }
+}
+
+impl WindowExt for slint::Window {
+    fn layer_config(&self) -> Option<LayerConfig> {
+        with_window_adapter(self, |window_adapter| {
+            window_adapter.layer_config.borrow().clone()
+        })
+        .flatten()
+    }
+
+    fn set_user_data(&self, user_data: Rc<dyn Any>) -> bool {
+        with_window_adapter(self, |window_adapter| {
+            *window_adapter.user_data.borrow_mut() = Some(user_data);
+        })
+        .is_some()
+    }
+
+    fn user_data(&self) -> Option<Rc<dyn Any>> {
+        with_window_adapter(self, |window_adapter| {
+            window_adapter.user_data.borrow().clone()
+        })
+        .flatten()
+    }
+
+    fn wl_surface(&self) -> Option<WlSurface> {
+        with_window_adapter(self, |window_adapter| window_adapter.surface.clone())
+    }
+
+    fn layer_surface(&self) -> Option<LayerSurface> {
+        with_window_adapter(self, LayerShellWindowAdapter::layer_surface).flatten()
+    }
+
+    fn xdg_window(&self) -> Option<XdgWindow> {
+        with_window_adapter(self, |window_adapter| window_adapter.xdg_window().cloned()).flatten()
+    }
+
+    #[cfg(feature = "session-lock")]
+    fn session_lock_surface(&self) -> Option<SessionLockSurface> {
+        with_window_adapter(self, LayerShellWindowAdapter::session_lock_surface).flatten()
+    }
+
+    fn set_color_scheme(&self, color_scheme: WindowColorScheme) -> bool {
+        with_window_adapter(self, |window_adapter| {
+            window_adapter.set_color_scheme_override(color_scheme)
+        })
+        .is_some()
+    }
+
+    fn last_input(&self) -> Option<InputTimestamp> {
+        with_window_adapter(self, |window_adapter| window_adapter.last_input.get()).flatten()
+    }
+
+    fn set_layer_config(&self, config: LayerConfig) -> Result<(), PlatformError> {
+        with_window_adapter(self, |window_adapter| {
+            window_adapter.set_layer_config(config)
+        })
+        .unwrap_or_else(|| {
+            Err(PlatformError::Other(
+                "the window is not backed by this platform".into(),
+            ))
+        })
+    }
+}
+
+fn with_window_adapter<R>(
+    window: &slint::Window,
+    f: impl FnOnce(&LayerShellWindowAdapter) -> R,
+) -> Option<R> {
+    LayerShellWindowAdapter::from_slint_window(window).map(|window_adapter| f(&window_adapter))
+}
@@ -0,0 +1,91 @@
+//! Setup shared by the integration tests, which run against a headless sway and are skipped
+//! where sway is not installed.
+//!
+//! Slint accepts a single platform per process, so each test binary runs its cases one after
+//! another on one platform, instead of using the libtest harness.
+
+#![allow(dead_code)]
+
+use slint_layer_shell::builder::Renderer;
+use slint_layer_shell::event::BackendEvent;
+use slint_layer_shell::platform::SlintLayerShell;
+use slint_layer_shell::testing::{self, HeadlessCompositor};
+use std::io;
+use std::time::Duration;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Harness {
+    pub compositor: HeadlessCompositor,
+    pub platform: SlintLayerShell,
+}
+
+impl Harness {
+    /// Starts the compositor and installs the platform, or returns `None` if sway is not
+    /// installed.
+    fn start() -> Option<Self> {
+        let compositor = match HeadlessCompositor::spawn() {
+            Ok(compositor) => compositor,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                eprintln!("sway is not installed, skipping");
+                return None;
+            }
+            Err(err) => panic!("failed to start the headless compositor: {err}"),
+        };
+        let platform = compositor
+            .platform_builder()
+            .renderer(Renderer::Software)
+            .build()
+            .expect("failed to connect to the headless compositor");
+        slint::platform::set_platform(Box::new(platform.clone()))
+            .expect("another platform is already installed");
+        Some(Self {
+            compositor,
+            platform,
+        })
+    }
+
+    /// Runs the event loop until `done` returns `true`, failing the test after a timeout.
+    pub fn run_until(&self, what: &str, done: impl FnMut(&BackendEvent) -> bool) {
+        let done =
+            testing::run_until(&self.platform, TIMEOUT, done).expect("the event loop failed");
+        assert!(done, "timed out waiting until {what}");
+    }
+
+    /// Runs the event loop until `condition` holds, checking it after every backend event.
+    pub fn wait_for(&self, what: &str, condition: impl Fn() -> bool) {
+        if !condition() {
+            self.run_until(what, |_| condition());
+        }
+    }
+
+    /// Runs the event loop until the window owning `surface` is configured, returning the
+    /// configured size.
+    pub fn wait_configured(&self, surface: &WlSurface) -> slint::PhysicalSize {
+        let mut configured_size = None;
+        self.run_until("the window is configured", |event| match event {
+            BackendEvent::WindowConfigured {
+                surface: configured,
+                size,
+            } if configured == surface => {
+                configured_size = Some(*size);
+                true
+            }
+            _ => false,
+        });
+        configured_size.unwrap()
+    }
+}
+
+/// Runs every case against one compositor, reporting them like libtest does.
+pub fn run(cases: &[(&str, fn(&Harness))]) {
+    let Some(harness) = Harness::start() else {
+        return;
+    };
+    for (name, case) in cases {
+        eprint!("test {name} ... ");
+        case(&harness);
+        eprintln!("ok");
+    }
+}
@@ -0,0 +1,107 @@
+//! End-to-end tests of windows defined by a configuration file that changes at runtime.
+
+mod common;
+
+use common::Harness;
+use slint::{ComponentHandle, PlatformError};
+use slint_layer_shell::config::{
+    ConfiguredWindow, EdgeDef, LayerShellConfig, LiveConfig, WindowDef,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+
+slint::slint! {
+    export component Bar inherits Window {
+        preferred-width: 200px;
+        preferred-height: 32px;
+        background: #1e1e2e;
+    }
+}
+
+fn main() {
+    common::run(&[("broken_reload_keeps_windows", broken_reload_keeps_windows)]);
+}
+
+/// Parses one window per line, as `name component edge [width]`.
+fn parse(text: &str) -> Result<LayerShellConfig, String> {
+    let windows = text
+        .lines()
+        .map(|line| {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let edge = match words.get(2) {
+                Some(&"top") => EdgeDef::Top,
+                Some(&"bottom") => EdgeDef::Bottom,
+                _ => return Err(format!("no edge in {line:?}")),
+            };
+            Ok(WindowDef {
+                name: words[0].into(),
+                component: words[1].into(),
+                anchor: vec![edge, EdgeDef::Left, EdgeDef::Right],
+                size: words
+                    .get(3)
+                    .map(|width| [width.parse().unwrap_or_default(), 32]),
+                ..WindowDef::default()
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(LayerShellConfig { windows })
+}
+
+fn visible_windows(config: &LiveConfig<Bar>) -> Vec<String> {
+    config
+        .windows()
+        .windows
+        .iter()
+        .filter(|(_, window)| match window {
+            ConfiguredWindow::Single(component) => component.window().is_visible(),
+            ConfiguredWindow::PerOutput(_) => false,
+        })
+        .map(|(def, _)| def.name.clone())
+        .collect()
+}
+
+fn broken_reload_keeps_windows(harness: &Harness) {
+    let path = std::env::temp_dir().join(format!("slint-layer-shell-{}.conf", std::process::id()));
+    std::fs::write(&path, "bar Bar top\ndock Bar bottom").unwrap();
+    let created = Rc::new(Cell::new(0));
+    let config = LiveConfig::watch(&harness.platform, &path, parse, {
+        let created = created.clone();
+        move |def, _| match def.component.as_str() {
+            "Bar" => {
+                created.set(created.get() + 1);
+                Bar::new()
+            }
+            component => Err(PlatformError::Other(format!("no component {component}"))),
+        }
+    })
+    .unwrap();
+    assert_eq!(visible_windows(&config), ["bar", "dock"]);
+    assert_eq!(created.get(), 2);
+
+    // Rejected before any window is touched: a width of 0 without stretching horizontally.
+    let invalid = LayerShellConfig {
+        windows: vec![WindowDef {
+            anchor: vec![EdgeDef::Top],
+            ..parse("bar Bar top 0").unwrap().windows.remove(0)
+        }],
+    };
+    assert!(config.apply(&invalid).is_err());
+    assert_eq!(visible_windows(&config), ["bar", "dock"]);
+
+    // Fails halfway, as the factory knows no `Missing` component.
+    std::fs::write(&path, "bar Bar bottom\ndock Missing bottom").unwrap();
+    assert!(config.reload().is_err());
+    assert_eq!(visible_windows(&config), ["bar", "dock"]);
+    assert_eq!(created.get(), 2);
+
+    // The next valid configuration applies to the kept windows instead of duplicating them.
+    std::fs::write(&path, "bar Bar top\ndock Bar bottom").unwrap();
+    config.reload().unwrap();
+    assert_eq!(visible_windows(&config), ["bar", "dock"]);
+    assert_eq!(created.get(), 2);
+
+    std::fs::write(&path, "").unwrap();
+    config.reload().unwrap();
+    assert!(visible_windows(&config).is_empty());
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,200 @@
+//! End-to-end tests of surface roles and their configure and render cycle.
+
+mod common;
+
+use common::Harness;
+use slint::ComponentHandle;
+use slint_layer_shell::layer::{Anchor, LayerConfig};
+use slint_layer_shell::testing::{SnapshotOptions, assert_snapshot};
+use slint_layer_shell::window_adapter::LayerShellWindowAdapter;
+use slint_layer_shell::window_ext::WindowExt;
+use smithay_client_toolkit::shell::WaylandSurface;
+use wayland_client::Proxy;
+
+slint::slint! {
+    export component Bar inherits Window {
+        preferred-width: 200px;
+        preferred-height: 32px;
+        background: #1e1e2e;
+    }
+
+    export component TitledBar inherits Window {
+        in property <string> hints;
+        title: hints;
+        preferred-width: 200px;
+        preferred-height: 32px;
+        background: #1e1e2e;
+    }
+
+    // Pixel-aligned solid colors, which every renderer draws exactly.
+    export component Swatch inherits Window {
+        preferred-width: 40px;
+        preferred-height: 20px;
+        background: #3366cc;
+        Rectangle {
+            x: 0;
+            y: 0;
+            width: 20px;
+            height: 20px;
+            background: #cc3333;
+        }
+    }
+}
+
+fn main() {
+    common::run(&[
+        ("layer_surface_is_configured", layer_surface_is_configured),
+        ("toplevel_is_configured", toplevel_is_configured),
+        ("snapshot_matches_reference", snapshot_matches_reference),
+        ("suspended_window_shows_again", suspended_window_shows_again),
+        (
+            "namespace_change_recreates_layer_surface",
+            namespace_change_recreates_layer_surface,
+        ),
+        (
+            "layer_config_namespace_recreates_layer_surface",
+            layer_config_namespace_recreates_layer_surface,
+        ),
+    ]);
+}
+
+fn bar_config() -> LayerConfig {
+    LayerConfig {
+        anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        namespace: "test-bar".into(),
+        ..LayerConfig::default()
+    }
+}
+
+fn layer_surface_is_configured(harness: &Harness) {
+    let bar = harness
+        .platform
+        .with_layer_config(bar_config(), Bar::new)
+        .unwrap();
+    bar.show().unwrap();
+    let surface = bar.window().wl_surface().unwrap();
+    let size = harness.wait_configured(&surface);
+
+    // Stretched horizontally by the compositor, the preferred height elsewhere.
+    assert_eq!(size.height, 32);
+    assert!(size.width > 200, "the bar was not stretched: {size:?}");
+    assert_eq!(bar.window().size(), size);
+
+    let window_adapter = LayerShellWindowAdapter::from_slint_window(bar.window()).unwrap();
+    window_adapter.render.render().unwrap();
+    bar.hide().unwrap();
+}
+
+fn toplevel_is_configured(harness: &Harness) {
+    let window = harness.platform.with_toplevel(Bar::new).unwrap();
+    window.show().unwrap();
+    let surface = window.window().wl_surface().unwrap();
+    let size = harness.wait_configured(&surface);
+
+    assert!(size.width > 0 && size.height > 0);
+    assert!(window.window().layer_config().is_none());
+    window.hide().unwrap();
+}
+
+fn snapshot_matches_reference(harness: &Harness) {
+    // The snapshot is taken at the configured size, so the surface gets the reference's size.
+    let config = LayerConfig {
+        size: Some((40, 20)),
+        namespace: "test-swatch".into(),
+        ..LayerConfig::default()
+    };
+    let swatch = harness
+        .platform
+        .with_layer_config(config, Swatch::new)
+        .unwrap();
+    swatch.show().unwrap();
+    harness.wait_configured(&swatch.window().wl_surface().unwrap());
+
+    assert_snapshot(
+        swatch.window(),
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/swatch.png"),
+        SnapshotOptions {
+            size: slint::PhysicalSize::new(40, 20),
+            ..SnapshotOptions::default()
+        },
+    )
+    .unwrap();
+    swatch.hide().unwrap();
+}
+
+fn suspended_window_shows_again(harness: &Harness) {
+    let bar = harness
+        .platform
+        .with_layer_config(bar_config(), Bar::new)
+        .unwrap();
+    bar.show().unwrap();
+    let surface = bar.window().wl_surface().unwrap();
+    harness.wait_configured(&surface);
+
+    harness.platform.suspend().unwrap();
+    assert!(!bar.window().is_visible());
+    assert!(bar.window().layer_surface().is_none());
+
+    // The same `wl_surface` gets a new layer surface and goes through configure again.
+    bar.show().unwrap();
+    assert_eq!(harness.wait_configured(&surface).height, 32);
+    let window_adapter = LayerShellWindowAdapter::from_slint_window(bar.window()).unwrap();
+    window_adapter.render.render().unwrap();
+    bar.hide().unwrap();
+}
+
+fn namespace_change_recreates_layer_surface(harness: &Harness) {
+    let bar = harness
+        .platform
+        .with_layer_config(bar_config(), TitledBar::new)
+        .unwrap();
+    bar.show().unwrap();
+    let surface = bar.window().wl_surface().unwrap();
+    harness.wait_configured(&surface);
+    let previous_layer_surface = bar.window().layer_surface().unwrap();
+
+    // The namespace is only set on creation, so the layer surface is replaced on the same
+    // `wl_surface`, which must survive the old one.
+    bar.set_hints("layer-shell:namespace=test-renamed".into());
+    assert_eq!(harness.wait_configured(&surface).height, 32);
+    let layer_surface = bar.window().layer_surface().unwrap();
+    assert!(layer_surface != previous_layer_surface);
+    assert_eq!(layer_surface.wl_surface(), &surface);
+    assert!(surface.is_alive());
+    assert_eq!(
+        bar.window().layer_config().unwrap().namespace,
+        "test-renamed"
+    );
+
+    let window_adapter = LayerShellWindowAdapter::from_slint_window(bar.window()).unwrap();
+    window_adapter.render.render().unwrap();
+    bar.hide().unwrap();
+}
+
+fn layer_config_namespace_recreates_layer_surface(harness: &Harness) {
+    let bar = harness
+        .platform
+        .with_layer_config(bar_config(), Bar::new)
+        .unwrap();
+    bar.show().unwrap();
+    let surface = bar.window().wl_surface().unwrap();
+    harness.wait_configured(&surface);
+    let previous_layer_surface = bar.window().layer_surface().unwrap();
+    let output = bar.window().layer_config().unwrap().output;
+
+    // Treated like a namespace from the title hints rather than kept.
+    let config = LayerConfig {
+        namespace: "test-renamed".into(),
+        ..bar_config()
+    };
+    harness
+        .platform
+        .set_layer_config(bar.window(), config)
+        .unwrap();
+    assert_eq!(harness.wait_configured(&surface).height, 32);
+    assert!(bar.window().layer_surface().unwrap() != previous_layer_surface);
+    let config = bar.window().layer_config().unwrap();
+    assert_eq!(config.namespace, "test-renamed");
+    assert_eq!(config.output, output);
+    bar.hide().unwrap();
+}
@@ -0,0 +1,120 @@
+//! End-to-end tests of Slint callbacks run by pointer input, which are called while the
+//! Wayland events are dispatched.
+
+mod common;
+
+use common::Harness;
+use slint::ComponentHandle;
+use slint_layer_shell::event::BackendEvent;
+use slint_layer_shell::foreign_toplevel::Taskbar;
+use slint_layer_shell::layer::{Anchor, LayerConfig};
+use slint_layer_shell::window_ext::WindowExt;
+use smithay_client_toolkit::seat::Capability;
+use smithay_client_toolkit::seat::pointer::BTN_LEFT;
+use std::cell::Cell;
+use std::rc::Rc;
+
+slint::slint! {
+    export component Button inherits Window {
+        callback clicked;
+        preferred-width: 200px;
+        preferred-height: 32px;
+        background: #1e1e2e;
+        TouchArea {
+            clicked => {
+                root.clicked();
+            }
+        }
+    }
+}
+
+fn main() {
+    common::run(&[
+        ("activate_toplevel_from_click", activate_toplevel_from_click),
+        (
+            "platform_is_usable_from_click",
+            platform_is_usable_from_click,
+        ),
+    ]);
+}
+
+fn bar_config() -> LayerConfig {
+    LayerConfig {
+        anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        namespace: "test-bar".into(),
+        ..LayerConfig::default()
+    }
+}
+
+/// Shows a bar running `on_click` when clicked, and clicks it with a new virtual pointer.
+fn click_bar(harness: &Harness, on_click: impl Fn(&Button) + 'static) -> Button {
+    let bar = harness
+        .platform
+        .with_layer_config(bar_config(), Button::new)
+        .unwrap();
+    let clicked = Rc::new(Cell::new(false));
+    bar.on_clicked({
+        let bar = bar.as_weak();
+        let clicked = clicked.clone();
+        move || {
+            on_click(&bar.unwrap());
+            clicked.set(true);
+        }
+    });
+    bar.show().unwrap();
+    harness.wait_configured(&bar.window().wl_surface().unwrap());
+
+    let pointer = harness.compositor.virtual_pointer().unwrap();
+    harness.run_until("the seat has a pointer", |event| {
+        matches!(
+            event,
+            BackendEvent::SeatCapabilityAdded {
+                capability: Capability::Pointer,
+                ..
+            }
+        )
+    });
+    let (width, height) = harness.platform.outputs()[0].logical_size.unwrap();
+    pointer
+        .move_to((100, 16), (width as u32, height as u32))
+        .unwrap();
+    pointer.click(BTN_LEFT).unwrap();
+    harness.wait_for("the bar is clicked", || clicked.get());
+    bar
+}
+
+fn activate_toplevel_from_click(harness: &Harness) {
+    let taskbar = Taskbar::new(&harness.platform).unwrap();
+    let window = harness.platform.with_toplevel(Button::new).unwrap();
+    window.show().unwrap();
+    harness.wait_for("the toplevel is listed", || {
+        !harness.platform.toplevels().is_empty()
+    });
+
+    let bar = click_bar(harness, move |_| taskbar.activate(0));
+    bar.hide().unwrap();
+    window.hide().unwrap();
+}
+
+fn platform_is_usable_from_click(harness: &Harness) {
+    let platform = harness.platform.clone();
+    let bar = click_bar(harness, move |bar| {
+        let surface = bar.window().wl_surface().unwrap();
+        assert!(!platform.outputs().is_empty());
+        assert!(platform.user_data(&surface).is_none());
+        assert!(platform.last_input().is_some());
+        platform.quirks();
+        platform.hide_cursor();
+        assert!(platform.set_raw_event_observer(bar.window(), |_| {}));
+        let config = LayerConfig {
+            anchor: Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+            ..bar_config()
+        };
+        platform.set_layer_config(bar.window(), config).unwrap();
+    });
+    assert_eq!(
+        bar.window().layer_config().unwrap().anchor,
+        Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT
+    );
+    bar.hide().unwrap();
+}
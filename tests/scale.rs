@@ -0,0 +1,139 @@
+//! End-to-end tests of windows on outputs with different scales, side by side:
+//! `HEADLESS-1` at scale 1 on the left and `HEADLESS-2` at scale 2 on the right.
+
+mod common;
+
+use common::Harness;
+use slint::ComponentHandle;
+use slint_layer_shell::layer::{Anchor, LayerConfig};
+use slint_layer_shell::output::OutputInfo;
+use slint_layer_shell::window_adapter::LayerShellWindowAdapter;
+use slint_layer_shell::window_ext::WindowExt;
+
+slint::slint! {
+    export component Bar inherits Window {
+        preferred-width: 200px;
+        preferred-height: 32px;
+        background: #1e1e2e;
+    }
+}
+
+fn main() {
+    common::run(&[
+        ("outputs_have_mixed_scales", outputs_have_mixed_scales),
+        (
+            "layer_surfaces_follow_their_output",
+            layer_surfaces_follow_their_output,
+        ),
+        (
+            "window_spanning_outputs_takes_the_largest_scale",
+            window_spanning_outputs_takes_the_largest_scale,
+        ),
+    ]);
+}
+
+fn output(harness: &Harness, name: &str) -> Option<OutputInfo> {
+    harness
+        .platform
+        .outputs()
+        .into_iter()
+        .find(|info| info.name.as_deref() == Some(name))
+}
+
+fn sway(harness: &Harness, command: &str) {
+    harness
+        .compositor
+        .swaymsg(command)
+        .unwrap_or_else(|err| panic!("{err}"));
+}
+
+fn outputs_have_mixed_scales(harness: &Harness) {
+    sway(harness, "create_output");
+    sway(
+        harness,
+        "output HEADLESS-1 resolution 800x600 position 0 0 scale 1",
+    );
+    sway(
+        harness,
+        "output HEADLESS-2 resolution 800x600 position 800 0 scale 2",
+    );
+    harness.wait_for("both outputs are configured", || {
+        output(harness, "HEADLESS-1").is_some_and(|info| info.scale_factor == 1)
+            && output(harness, "HEADLESS-2").is_some_and(|info| {
+                info.scale_factor == 2 && info.logical_position == Some((800, 0))
+            })
+    });
+}
+
+fn bar_on(harness: &Harness, output_name: &str) -> Bar {
+    let config = LayerConfig {
+        anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+        namespace: format!("test-bar-{output_name}"),
+        output: output(harness, output_name).map(|info| info.output),
+        ..LayerConfig::default()
+    };
+    let bar = harness
+        .platform
+        .with_layer_config(config, Bar::new)
+        .unwrap();
+    bar.show().unwrap();
+    harness.wait_configured(&bar.window().wl_surface().unwrap());
+    bar
+}
+
+fn layer_surfaces_follow_their_output(harness: &Harness) {
+    let bar_1x = bar_on(harness, "HEADLESS-1");
+    let bar_2x = bar_on(harness, "HEADLESS-2");
+    harness.wait_for("each bar has the scale of its output", || {
+        bar_1x.window().scale_factor() == 1.0 && bar_2x.window().scale_factor() == 2.0
+    });
+
+    // Both bars span their output's logical width at the same logical height.
+    assert_eq!(bar_1x.window().size(), slint::PhysicalSize::new(800, 32));
+    assert_eq!(bar_2x.window().size(), slint::PhysicalSize::new(800, 64));
+    assert_eq!(
+        bar_2x.window().size().to_logical(2.0),
+        slint::LogicalSize::new(400.0, 32.0)
+    );
+
+    for bar in [&bar_1x, &bar_2x] {
+        let window_adapter = LayerShellWindowAdapter::from_slint_window(bar.window()).unwrap();
+        window_adapter.render.render().unwrap();
+        bar.hide().unwrap();
+    }
+}
+
+fn window_spanning_outputs_takes_the_largest_scale(harness: &Harness) {
+    let window = harness.platform.with_toplevel(Bar::new).unwrap();
+    window.show().unwrap();
+    harness.wait_configured(&window.window().wl_surface().unwrap());
+    let window_adapter = LayerShellWindowAdapter::from_slint_window(window.window()).unwrap();
+
+    // Straddles the edge between the outputs at x = 800.
+    sway(
+        harness,
+        "[title=\"slint-layer-shell\"] floating enable, resize set 200 100, \
+         move absolute position 700 100",
+    );
+    harness.wait_for("the window entered both outputs", || {
+        window_adapter.entered_outputs.borrow().len() == 2
+    });
+    harness.wait_for("the window takes the larger scale", || {
+        window.window().scale_factor() == 2.0
+    });
+    window_adapter.render.render().unwrap();
+
+    // Back on the 1x output only, the scale drops again.
+    sway(
+        harness,
+        "[title=\"slint-layer-shell\"] move absolute position 100 100",
+    );
+    harness.wait_for("the window left the 2x output", || {
+        window_adapter.entered_outputs.borrow().len() == 1
+    });
+    harness.wait_for("the window takes the scale of the 1x output", || {
+        window.window().scale_factor() == 1.0
+    });
+    window_adapter.render.render().unwrap();
+    window.hide().unwrap();
+}